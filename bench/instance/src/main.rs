@@ -81,7 +81,7 @@ fn main() {
         let store_sled_path = path.join("store_sled");
 
         let mut store: Instance<Key, Vec<u8>> =
-            Instance::open(store_path.clone().join("store")).unwrap();
+            Instance::open_or_create(store_path.clone()).unwrap();
         let store_sled = sled::open(store_sled_path.clone()).unwrap();
 
         let start = Instant::now();
@@ -98,7 +98,7 @@ fn main() {
         }
         let store_sled_end = start.elapsed();
 
-        drop(store);
+        store.close().unwrap();
         drop(store_sled);
 
         let store_sled_size = fs_extra::dir::get_size(store_sled_path).unwrap();
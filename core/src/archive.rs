@@ -0,0 +1,317 @@
+//! Portable archives of the entries of a store.
+//!
+//! Archives do not depend on the layout of the store they were exported from, so they can be
+//! imported into any in-memory or persisted store with the same key and value types. An archive
+//! is a stream of length-prefixed bincode records, with entries sorted by key:
+//!
+//! ```text
+//! +-------+--------+---------+---------+-----+---------+
+//! | magic | header | entry 0 | entry 1 | ... | trailer |
+//! +-------+--------+---------+---------+-----+---------+
+//! ```
+//!
+//! Every record is prefixed with its length as a little endian `u32`. The header names the
+//! archive format version and the key and value types. The trailer is marked by a length of
+//! `u32::MAX` and holds the number of entries, so that truncated archives are caught on import.
+//!
+//! The key and value types are recorded with `std::any::type_name`, whose output is not
+//! guaranteed to be stable across compiler versions, and which changes whenever a type is
+//! renamed or moved to another module. Differing names are therefore only logged as a warning
+//! on import, not treated as an error. Archives are not self-describing, so importing them with
+//! other types is only caught once a record fails to deserialize into them or leaves bytes
+//! over, and types of the same encoding are silently accepted.
+
+use crate::Error;
+use bincode::Options;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::io::{ErrorKind, Read, Write};
+use std::marker::PhantomData;
+
+/// Version of the archive format, bumped whenever the framing or the header changes
+pub const ARCHIVE_FORMAT_VERSION: u8 = 1;
+
+const MAGIC: [u8; 8] = *b"LIMOARCH";
+
+const TRAILER: u32 = u32::MAX;
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+struct ArchiveHeader {
+    format_version: u8,
+    key_type: String,
+    value_type: String,
+}
+
+impl ArchiveHeader {
+    fn new<K, V>() -> Self {
+        Self {
+            format_version: ARCHIVE_FORMAT_VERSION,
+            key_type: std::any::type_name::<K>().to_string(),
+            value_type: std::any::type_name::<V>().to_string(),
+        }
+    }
+}
+
+fn write_record(writer: &mut impl Write, record: &impl Serialize) -> crate::Result<()> {
+    let data = bincode::serialize(record)?;
+    let len = u32::try_from(data.len())
+        .ok()
+        .filter(|&len| len != TRAILER)
+        .ok_or_else(|| Error::Archive(format!("Record of {} bytes is too large", data.len())))?;
+
+    writer.write_all(&len.to_le_bytes())?;
+    writer.write_all(&data)?;
+
+    Ok(())
+}
+
+// Reading past the end of an archive means it was cut off
+fn read_exact(reader: &mut impl Read, data: &mut [u8]) -> crate::Result<()> {
+    reader.read_exact(data).map_err(|err| match err.kind() {
+        ErrorKind::UnexpectedEof => Error::Archive("Archive is truncated".to_string()),
+        _ => Error::Io(err),
+    })
+}
+
+/// Write `entries`, sorted by key, to `writer` as an archive. Returns the number of entries
+/// written, or the first error yielded by `entries`.
+pub fn export<K, V>(
+    entries: impl Iterator<Item = crate::Result<(K, V)>>,
+    mut writer: impl Write,
+) -> crate::Result<u64>
+where
+    K: Serialize,
+    V: Serialize,
+{
+    writer.write_all(&MAGIC)?;
+    write_record(&mut writer, &ArchiveHeader::new::<K, V>())?;
+
+    let mut count = 0u64;
+    for entry in entries {
+        write_record(&mut writer, &entry?)?;
+        count += 1;
+    }
+
+    writer.write_all(&TRAILER.to_le_bytes())?;
+    writer.write_all(&count.to_le_bytes())?;
+    writer.flush()?;
+
+    Ok(count)
+}
+
+/// Iterator over the entries of an archive written by `export`
+///
+/// Entries are checked to be sorted by key without duplicates, so they can be bulk built into
+/// a store directly. Iteration stops after the first error.
+pub struct ArchiveReader<K, V, R> {
+    reader: R,
+    count: u64,
+    last: Option<K>,
+    done: bool,
+    _ph: PhantomData<V>,
+}
+
+impl<K, V, R> ArchiveReader<K, V, R>
+where
+    K: DeserializeOwned + Ord + Clone,
+    V: DeserializeOwned,
+    R: Read,
+{
+    /// Read the archive header, warning if it names different key or value types
+    pub fn new(mut reader: R) -> crate::Result<Self> {
+        let mut magic = [0; MAGIC.len()];
+        read_exact(&mut reader, &mut magic)?;
+        if magic != MAGIC {
+            return Err(Error::Archive("Stream is not an archive".to_string()));
+        }
+
+        let mut reader = Self {
+            reader,
+            count: 0,
+            last: None,
+            done: false,
+            _ph: PhantomData,
+        };
+
+        let header: ArchiveHeader = reader
+            .read_record()?
+            .ok_or_else(|| Error::Archive("Archive has no header".to_string()))?;
+        let expected = ArchiveHeader::new::<K, V>();
+
+        if header.format_version != expected.format_version {
+            return Err(Error::Archive(format!(
+                "Archive has format version {}, expected {}",
+                header.format_version, expected.format_version
+            )));
+        }
+
+        // Type names are unstable, so they can only serve as a hint
+        if header != expected {
+            log::warn!(
+                "Archive holds `{}` keys and `{}` values, expected `{}` and `{}`",
+                header.key_type,
+                header.value_type,
+                expected.key_type,
+                expected.value_type
+            );
+        }
+
+        Ok(reader)
+    }
+
+    // Returns `None` once the trailer has been read
+    fn read_record<T: DeserializeOwned>(&mut self) -> crate::Result<Option<T>> {
+        let mut len = [0; 4];
+        read_exact(&mut self.reader, &mut len)?;
+        let len = u32::from_le_bytes(len);
+
+        if len == TRAILER {
+            let mut count = [0; 8];
+            read_exact(&mut self.reader, &mut count)?;
+            let count = u64::from_le_bytes(count);
+
+            if count != self.count {
+                return Err(Error::Archive(format!(
+                    "Archive should hold {} entries, but {} were read",
+                    count, self.count
+                )));
+            }
+
+            return Ok(None);
+        }
+
+        // Read through `take` instead of allocating the untrusted length up front
+        let mut data = Vec::new();
+        (&mut self.reader).take(len as u64).read_to_end(&mut data)?;
+        if data.len() != len as usize {
+            return Err(Error::Archive("Archive is truncated".to_string()));
+        }
+
+        // Records are decoded as written by `bincode::serialize`, but must be consumed entirely
+        bincode::DefaultOptions::new()
+            .with_fixint_encoding()
+            .reject_trailing_bytes()
+            .deserialize(&data)
+            .map(Some)
+            .map_err(|err| Error::Archive(format!("Malformed archive record: {err}")))
+    }
+
+    fn read_entry(&mut self) -> crate::Result<Option<(K, V)>> {
+        let Some((key, value)) = self.read_record::<(K, V)>()? else {
+            return Ok(None);
+        };
+
+        if self.last.as_ref().is_some_and(|last| *last >= key) {
+            return Err(Error::Archive(
+                "Archive entries are not sorted by key".to_string(),
+            ));
+        }
+
+        self.last = Some(key.clone());
+        self.count += 1;
+
+        Ok(Some((key, value)))
+    }
+}
+
+impl<K, V, R> Iterator for ArchiveReader<K, V, R>
+where
+    K: DeserializeOwned + Ord + Clone,
+    V: DeserializeOwned,
+    R: Read,
+{
+    type Item = crate::Result<(K, V)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let entry = self.read_entry().transpose();
+        self.done = !matches!(entry, Some(Ok(_)));
+        entry
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn archive(entries: &[(u64, u32)]) -> Vec<u8> {
+        let mut data = Vec::new();
+        export(entries.iter().copied().map(Ok), &mut data).unwrap();
+        data
+    }
+
+    fn import(data: &[u8]) -> crate::Result<Vec<(u64, u32)>> {
+        ArchiveReader::new(data)?.collect()
+    }
+
+    #[test]
+    fn export_import_roundtrip() {
+        let entries = vec![(1, 10), (2, 20), (5, 50)];
+        assert_eq!(import(&archive(&entries)).unwrap(), entries);
+        assert_eq!(import(&archive(&[])).unwrap(), vec![]);
+    }
+
+    #[test]
+    fn renamed_types() {
+        #[derive(Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+        struct Key(u64);
+
+        let data = archive(&[(1, 10), (2, 20)]);
+        let entries: Vec<(Key, u32)> = ArchiveReader::new(&data[..])
+            .unwrap()
+            .collect::<crate::Result<_>>()
+            .unwrap();
+
+        assert_eq!(entries, vec![(Key(1), 10), (Key(2), 20)]);
+    }
+
+    #[test]
+    fn wrong_types() {
+        let data = archive(&[(1, 10)]);
+
+        let narrower = ArchiveReader::<u32, u32, _>::new(&data[..]).unwrap();
+        assert!(matches!(
+            narrower.collect::<crate::Result<Vec<_>>>(),
+            Err(Error::Archive(_))
+        ));
+
+        let wider = ArchiveReader::<u64, u64, _>::new(&data[..]).unwrap();
+        assert!(matches!(
+            wider.collect::<crate::Result<Vec<_>>>(),
+            Err(Error::Archive(_))
+        ));
+    }
+
+    #[test]
+    fn wrong_format_version() {
+        let mut data = MAGIC.to_vec();
+        let header = ArchiveHeader {
+            format_version: ARCHIVE_FORMAT_VERSION + 1,
+            ..ArchiveHeader::new::<u64, u32>()
+        };
+        write_record(&mut data, &header).unwrap();
+
+        assert!(matches!(import(&data), Err(Error::Archive(_))));
+    }
+
+    #[test]
+    fn truncated() {
+        let data = archive(&[(1, 10), (2, 20)]);
+
+        for len in 0..data.len() {
+            assert!(
+                matches!(import(&data[..len]), Err(Error::Archive(_))),
+                "Archive truncated to {len} bytes was accepted"
+            );
+        }
+    }
+
+    #[test]
+    fn unsorted() {
+        let data = archive(&[(2, 20), (1, 10)]);
+        assert!(matches!(import(&data), Err(Error::Archive(_))));
+    }
+}
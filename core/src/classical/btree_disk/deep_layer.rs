@@ -1,43 +1,64 @@
+use slice_search::{OptimalSearch, Search};
 use std::ops::Bound;
 
 use crate::{
     classical::node::BTreeNode,
     common::{
         list::deep_disk::DeepDiskList,
-        storage::{GlobalStore, StoreID},
+        storage::{GlobalStore, LayerStats, StoreID},
     },
-    impl_node_layer, Address, Key, KeyBounded, NodeLayer, Persisted,
+    describe::LayerSize,
+    impl_node_layer, Address, Error, Key, KeyBounded, NodeLayer, NodeMap, Persisted,
 };
 
-pub struct DeepDiskBTreeLayer<K, V, const FANOUT: usize, PA>
+pub struct DeepDiskBTreeLayer<K, V, const FANOUT: usize, PA, M = (), S = OptimalSearch>
 where
     K: Persisted + Ord,
     V: Persisted + Eq,
     PA: Persisted + Eq,
+    S: Search<K> + 'static,
 {
-    inner: DeepDiskList<BTreeNode<K, V, FANOUT>, PA>,
+    inner: DeepDiskList<BTreeNode<K, V, FANOUT, S>, PA>,
+    map: M,
 }
 
-impl<K, V, const FANOUT: usize, PA> DeepDiskBTreeLayer<K, V, FANOUT, PA>
+impl<K, V, const FANOUT: usize, PA, M, S> DeepDiskBTreeLayer<K, V, FANOUT, PA, M, S>
 where
     K: Persisted + Key,
     V: Persisted + Eq,
     PA: Persisted + Address,
+    M: NodeMap<K, V, FANOUT>,
+    S: Search<K> + 'static,
 {
     pub fn load(store: &mut GlobalStore, ident: impl ToString) -> crate::Result<Self> {
-        Ok(Self {
-            inner: DeepDiskList::load(store, ident)?,
-        })
+        let ident = ident.to_string();
+
+        let mut layer = Self {
+            inner: DeepDiskList::load(store, &ident)?,
+            map: M::open(store, &ident)?,
+        };
+
+        if layer.map.is_stale() {
+            layer.map.mark_dirty()?;
+
+            let mut next = Some(layer.first());
+            while let Some(ptr) = next {
+                layer.mirror(ptr)?;
+                next = layer.inner.try_next(ptr)?;
+            }
+        }
+
+        Ok(layer)
     }
 
-    pub fn fill(&mut self, iter: impl Iterator<Item = (K, V)>) -> crate::Result<()> {
+    pub fn fill(&mut self, iter: impl Iterator<Item = (K, V)>, fill: usize) -> crate::Result<()> {
         // Add empty cap node
-        let mut ptr = self.inner.clear()?;
+        let mut ptr = self.clear()?;
 
         for (key, address) in iter {
             // If node too full, carry over to next
-            if self.inner.get_node(ptr)?.unwrap().is_half_full() {
-                ptr = self.inner.insert_after(BTreeNode::empty(), ptr)?;
+            if self.get_node(ptr)?.is_filled_to(fill) {
+                ptr = self.insert_after(BTreeNode::empty(), ptr)?;
             }
 
             self.insert_into_node(key, &address, ptr)?;
@@ -49,31 +70,176 @@ where
     pub fn fill_with_parent<B: NodeLayer<K, V, StoreID>>(
         &mut self,
         base: &mut B,
+        fill: usize,
     ) -> crate::Result<()> {
         if let Some(mut ptr) = self.inner.is_empty()? {
             let mut iter = base.range_mut(Bound::Unbounded, Bound::Unbounded);
 
             while let Some((key, address, parent)) = iter.next() {
                 // If node too full, carry over to next
-                if self.inner.get_node(ptr)?.unwrap().is_half_full() {
-                    ptr = self.inner.insert_after(BTreeNode::empty(), ptr)?;
+                if self.get_node(ptr)?.is_filled_to(fill) {
+                    ptr = self.insert_after(BTreeNode::empty(), ptr)?;
                 }
 
                 self.insert_into_node(key.clone(), &address, ptr)?;
                 parent.set(ptr);
             }
+
+            // The base ends early if one of its nodes could not be read
+            base.take_error()?;
         }
 
         Ok(())
     }
 
+    pub fn entries(&self) -> impl Iterator<Item = crate::Result<(K, V)>> + '_ {
+        let mut next = Some(self.first());
+
+        // Traversal stops at the first node that cannot be read, after yielding its error
+        std::iter::from_fn(move || {
+            let ptr = next.take()?;
+            Some(self.get_node(ptr).and_then(|node| {
+                next = self.inner.try_next(ptr)?;
+                Ok(node)
+            }))
+        })
+        .flat_map(|node| match node {
+            Ok(node) => node
+                .entries()
+                .iter()
+                .map(|entry| Ok((entry.key, entry.value.clone())))
+                .collect(),
+            Err(error) => vec![Err(error)],
+        })
+    }
+
     fn insert_into_node(&mut self, key: K, value: &V, ptr: StoreID) -> crate::Result<Option<V>> {
+        self.transform_node(ptr, |node| node.insert(key.clone(), value.clone()))
+    }
+
+    // Node writes go through the helpers below, which mirror them into the node map
+
+    fn transform_node<T>(
+        &mut self,
+        ptr: StoreID,
+        closure: impl Fn(&mut BTreeNode<K, V, FANOUT, S>) -> T,
+    ) -> crate::Result<T> {
+        self.map.mark_dirty()?;
+        let result = self.inner.transform_node(ptr, closure)?;
+        self.mirror(ptr)?;
+
+        Ok(result)
+    }
+
+    fn insert_after(
+        &mut self,
+        node: BTreeNode<K, V, FANOUT, S>,
+        ptr: StoreID,
+    ) -> crate::Result<StoreID> {
+        self.map.mark_dirty()?;
+        let new_node_ptr = self.inner.insert_after(node, ptr)?;
+        self.mirror(new_node_ptr)?;
+
+        Ok(new_node_ptr)
+    }
+
+    fn clear(&mut self) -> crate::Result<StoreID> {
+        self.map.mark_dirty()?;
+        let ptr = self.inner.clear()?;
+        self.mirror(ptr)?;
+
+        Ok(ptr)
+    }
+
+    fn mirror(&mut self, ptr: StoreID) -> crate::Result<()> {
+        if self.map.is_dirty() {
+            let node = self.get_node(ptr)?;
+            self.map.write(ptr, &node)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn flush(&self) -> crate::Result<()> {
+        if !self.map.is_dirty() {
+            return self.inner.flush();
+        }
+
+        // The map may only be sealed once the pages it mirrors are durable
+        self.inner.sync()?;
+        self.map.seal()
+    }
+
+    pub fn stats(&self) -> crate::Result<LayerStats> {
+        self.inner.stats()
+    }
+
+    /// Count the nodes and keys of the layer, reading every node
+    pub fn measure(&self) -> crate::Result<LayerSize> {
+        // Parents of a deep layer are persisted along with its nodes
+        let mut size = LayerSize {
+            memory_bytes: 0,
+            disk_bytes: self.stats()?.bytes,
+            ..Default::default()
+        };
+
+        let mut next = Some(self.first());
+        while let Some(ptr) = next {
+            size.nodes += 1;
+            size.keys += self.get_node(ptr)?.len();
+            next = self.inner.try_next(ptr)?;
+        }
+
+        Ok(size)
+    }
+
+    // Parents of a deep layer are persisted with its nodes, and set for every node it has
+    fn parent_of(&self, ptr: StoreID) -> crate::Result<PA> {
+        self.inner.try_parent(ptr)?.ok_or_else(|| {
+            Error::Catalog(format!("Parent of deep node {} was never recorded", ptr))
+        })
+    }
+
+    /// Find the value of the entry with exactly `key` in a node, reading it from the node map if
+    /// possible
+    pub fn search_exact(&self, ptr: StoreID, key: &K) -> crate::Result<Option<V>> {
+        if let Some(node) = self.map.view::<S>(ptr) {
+            return Ok(node.get_exact(key).cloned());
+        }
+
+        Ok(self.get_node(ptr)?.get_exact(key).cloned())
+    }
+
+    /// Find the value of the last entry at or below `key` in a node, or its first entry, reading
+    /// it from the node map if possible
+    pub fn search_lower_bound(&self, ptr: StoreID, key: &K) -> crate::Result<V> {
+        if let Some(node) = self.map.view::<S>(ptr) {
+            return Ok(node.get_lower_bound_always(key).clone());
+        }
+
+        Ok(self.get_node(ptr)?.get_lower_bound_always(key).clone())
+    }
+
+    pub fn get_node(&self, ptr: StoreID) -> crate::Result<BTreeNode<K, V, FANOUT, S>> {
         self.inner
-            .transform_node(ptr, |node| node.insert(key.clone(), value.clone()))
+            .get_node(ptr)?
+            .ok_or_else(|| Error::Catalog(format!("Node page {} of a deep layer is missing", ptr)))
     }
 
-    pub fn get_node(&self, ptr: StoreID) -> crate::Result<BTreeNode<K, V, FANOUT>> {
-        self.inner.get_node(ptr).map(|node| node.unwrap())
+    // Split a full node before `key` is inserted. With `rightmost` set, keys appended past the end
+    // of the layer leave the node full and start a new empty node instead, so that monotonically
+    // increasing keys fill every node
+    fn split(
+        &mut self,
+        ptr: StoreID,
+        key: &K,
+        rightmost: bool,
+    ) -> crate::Result<(K, BTreeNode<K, V, FANOUT, S>)> {
+        if rightmost && ptr == self.last() && self.get_node(ptr)?.is_appended(key) {
+            return Ok((*key, BTreeNode::empty()));
+        }
+
+        self.transform_node(ptr, BTreeNode::split)
     }
 
     pub fn insert(
@@ -81,13 +247,14 @@ where
         key: K,
         value: V,
         ptr: StoreID,
+        rightmost: bool,
     ) -> crate::Result<Option<(K, StoreID, PA)>> {
-        if self.inner.get_node(ptr)?.unwrap().is_full() {
-            let parent = self.inner.parent(ptr).unwrap();
+        if self.get_node(ptr)?.is_full() {
+            let parent = self.parent_of(ptr)?;
 
             // Split
-            let (split_point, new_node) = self.inner.transform_node(ptr, BTreeNode::split)?;
-            let new_node_ptr = self.inner.insert_after(new_node, ptr)?;
+            let (split_point, new_node) = self.split(ptr, &key, rightmost)?;
+            let new_node_ptr = self.insert_after(new_node, ptr)?;
 
             // Insert into the right node
             if key < split_point {
@@ -97,11 +264,7 @@ where
             }
 
             return Ok(Some((
-                self.inner
-                    .get_node(new_node_ptr)?
-                    .unwrap()
-                    .lower_bound()
-                    .clone(),
+                self.get_node(new_node_ptr)?.lower_bound().clone(),
                 new_node_ptr,
                 parent,
             )));
@@ -118,16 +281,17 @@ where
         value: V,
         base: &mut B,
         ptr: StoreID,
+        rightmost: bool,
     ) -> crate::Result<Option<(K, StoreID, PA)>> {
-        if self.inner.get_node(ptr)?.unwrap().is_full() {
-            let parent = self.inner.parent(ptr).unwrap();
+        if self.get_node(ptr)?.is_full() {
+            let parent = self.parent_of(ptr)?;
 
             // Split
-            let (split_point, new_node) = self.inner.transform_node(ptr, BTreeNode::split)?;
-            let new_node_ptr = self.inner.insert_after(new_node, ptr)?;
+            let (split_point, new_node) = self.split(ptr, &key, rightmost)?;
+            let new_node_ptr = self.insert_after(new_node, ptr)?;
 
             // Update all of the parents for the split node
-            for entry in self.inner.get_node(new_node_ptr)?.unwrap().entries() {
+            for entry in self.get_node(new_node_ptr)?.entries() {
                 base.set_parent(entry.value.clone(), new_node_ptr)
             }
 
@@ -140,30 +304,32 @@ where
                 base.set_parent(value, new_node_ptr);
             }
 
+            // Parents of a persisted base are written to its pages, which may fail
+            base.take_error()?;
+
             return Ok(Some((
-                self.inner
-                    .get_node(new_node_ptr)?
-                    .unwrap()
-                    .lower_bound()
-                    .clone(),
+                self.get_node(new_node_ptr)?.lower_bound().clone(),
                 new_node_ptr,
                 parent,
             )));
         } else {
             self.insert_into_node(key, &value, ptr)?;
             base.set_parent(value, ptr);
+            base.take_error()?;
         }
 
         Ok(None)
     }
 }
 
-impl<K, V, const FANOUT: usize, PA> NodeLayer<K, StoreID, PA>
-    for DeepDiskBTreeLayer<K, V, FANOUT, PA>
+impl<K, V, const FANOUT: usize, PA, M, S> NodeLayer<K, StoreID, PA>
+    for DeepDiskBTreeLayer<K, V, FANOUT, PA, M, S>
 where
     K: Persisted + Key,
     V: Persisted + Eq,
     PA: Persisted + Address,
+    M: NodeMap<K, V, FANOUT>,
+    S: Search<K> + 'static,
 {
     impl_node_layer!(StoreID, PA);
 }
@@ -0,0 +1,506 @@
+//! Memory-mapped mirror of the nodes of a disk B-tree layer.
+//!
+//! Nodes are stored in fixed-size slots indexed by their page ID, so that searches can read keys
+//! and values straight out of the mapping instead of deserializing the page:
+//!
+//! ```text
+//! +-----------+---------------------+-----------------------+---------+
+//! | len (u64) | keys ([K; FANOUT])  | values ([V; FANOUT])  | padding |
+//! +-----------+---------------------+-----------------------+---------+
+//! ```
+//!
+//! Page 0 always holds the global catalog, so slot 0 is used for the file header instead. The
+//! map is only a cache of the pages: it is marked dirty before the layer is modified and sealed
+//! with the store generation once the pages are durable. A map which was not sealed by the
+//! previous session is rebuilt from the pages on open.
+
+use crate::{
+    classical::node::BTreeNode,
+    common::storage::{GlobalStore, StoreID},
+    Error,
+};
+use bytemuck::Pod;
+use memmap2::{MmapMut, MmapOptions};
+use sorted_array::SortedSlice;
+use std::{
+    cell::Cell,
+    fs::File,
+    io::{ErrorKind, Seek, SeekFrom, Write},
+    marker::PhantomData,
+    mem::{align_of, size_of},
+    ops::Range,
+    path::Path,
+};
+
+/// Mirror of the nodes of a disk B-tree layer which can be searched without deserialization
+pub trait NodeMap<K: Ord, V, const FANOUT: usize>: Sized {
+    fn open(store: &GlobalStore, ident: &str) -> crate::Result<Self>;
+
+    /// Whether the map no longer matches the pages and must be rebuilt by writing every node
+    fn is_stale(&self) -> bool;
+
+    /// Whether nodes have been written since the map was last sealed
+    fn is_dirty(&self) -> bool;
+
+    /// Must be called before the pages of the layer are modified
+    fn mark_dirty(&mut self) -> crate::Result<()>;
+
+    fn write<S>(&mut self, ptr: StoreID, node: &BTreeNode<K, V, FANOUT, S>) -> crate::Result<()>;
+
+    /// Borrow a node straight from the map, if it is mapped, to be searched with `S`
+    fn view<S>(&self, ptr: StoreID) -> Option<SortedSlice<'_, K, V, S>>;
+
+    /// Record that the map matches the pages, which must already be durable
+    fn seal(&self) -> crate::Result<()>;
+}
+
+/// No map at all, every search deserializes the node from the store
+impl<K: Ord, V, const FANOUT: usize> NodeMap<K, V, FANOUT> for () {
+    fn open(_: &GlobalStore, _: &str) -> crate::Result<Self> {
+        Ok(())
+    }
+
+    fn is_stale(&self) -> bool {
+        false
+    }
+
+    fn is_dirty(&self) -> bool {
+        false
+    }
+
+    fn mark_dirty(&mut self) -> crate::Result<()> {
+        Ok(())
+    }
+
+    fn write<S>(&mut self, _: StoreID, _: &BTreeNode<K, V, FANOUT, S>) -> crate::Result<()> {
+        Ok(())
+    }
+
+    fn view<S>(&self, _: StoreID) -> Option<SortedSlice<'_, K, V, S>> {
+        None
+    }
+
+    fn seal(&self) -> crate::Result<()> {
+        Ok(())
+    }
+}
+
+const MAGIC: u64 = u64::from_le_bytes(*b"LIMOUMAP");
+
+// Header fields, stored as little endian u64s at the start of slot 0
+const MAGIC_OFFSET: usize = 0;
+const GENERATION_OFFSET: usize = 8;
+const SLOT_SIZE_OFFSET: usize = 16;
+const FANOUT_OFFSET: usize = 24;
+const HEADER_SIZE: usize = 32;
+
+// Slots are aligned to a cache line, which also bounds the supported key and value alignment
+const SLOT_ALIGN: usize = 64;
+const INITIAL_SLOTS: usize = 64;
+
+// Generation of a map which is being modified or was never sealed
+const DIRTY: u64 = 0;
+
+const fn align_up(offset: usize, align: usize) -> usize {
+    offset.div_ceil(align) * align
+}
+
+/// Memory-mapped node mirror, enabled with `btree(fanout = N, persist, mmap)`
+///
+/// Stores backed by `Backend::Memory` have no directory to put the map in, and fall back to
+/// reading nodes from the store.
+pub struct MappedNodes<K, V, const FANOUT: usize> {
+    inner: Option<MappedFile>,
+    _ph: PhantomData<(K, V)>,
+}
+
+struct MappedFile {
+    file: File,
+    map: MmapMut,
+    generation: u64,
+    stale: bool,
+    dirty: Cell<bool>,
+}
+
+impl<K: Pod, V: Pod, const FANOUT: usize> MappedNodes<K, V, FANOUT> {
+    const KEYS_OFFSET: usize = align_up(size_of::<u64>(), align_of::<K>());
+    const VALUES_OFFSET: usize =
+        align_up(Self::KEYS_OFFSET + FANOUT * size_of::<K>(), align_of::<V>());
+    const SLOT_SIZE: usize = align_up(Self::VALUES_OFFSET + FANOUT * size_of::<V>(), SLOT_ALIGN);
+
+    fn slot(ptr: StoreID) -> Range<usize> {
+        let offset = ptr as usize * Self::SLOT_SIZE;
+        offset..offset + Self::SLOT_SIZE
+    }
+
+    fn read_u64(map: &[u8], offset: usize) -> u64 {
+        u64::from_le_bytes(map[offset..offset + 8].try_into().unwrap())
+    }
+
+    fn write_u64(map: &mut [u8], offset: usize, value: u64) {
+        map[offset..offset + 8].copy_from_slice(&value.to_le_bytes());
+    }
+
+    // Read-only sessions can neither rebuild nor re-stamp the map, so they only use a map which
+    // was sealed by the last writer and fall back to reading the pages otherwise
+    fn open_read_only(store: &GlobalStore, path: &Path) -> crate::Result<Option<MappedFile>> {
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+
+        if file.metadata()?.len() < Self::SLOT_SIZE as u64 {
+            return Ok(None);
+        }
+
+        // SAFETY: writers are locked out of the store while it is open read-only, and the
+        // private mapping never writes back to the file
+        let map = unsafe { MmapOptions::new().map_copy(&file)? };
+        let generation = store.generation();
+
+        if !Self::is_valid(&map, generation)
+            || Self::read_u64(&map, GENERATION_OFFSET) != generation
+        {
+            return Ok(None);
+        }
+
+        Ok(Some(MappedFile {
+            file,
+            map,
+            generation,
+            stale: false,
+            dirty: Cell::new(false),
+        }))
+    }
+
+    fn is_valid(map: &[u8], generation: u64) -> bool {
+        let sealed = Self::read_u64(map, GENERATION_OFFSET);
+
+        Self::read_u64(map, MAGIC_OFFSET) == MAGIC
+            && Self::read_u64(map, SLOT_SIZE_OFFSET) == Self::SLOT_SIZE as u64
+            && Self::read_u64(map, FANOUT_OFFSET) == FANOUT as u64
+            && sealed != DIRTY
+            // Sealed by the previous session, or earlier in this one
+            && (sealed == generation || sealed + 1 == generation)
+    }
+}
+
+impl MappedFile {
+    fn resize(&mut self, len: usize) -> crate::Result<()> {
+        self.file.set_len(len as u64)?;
+
+        // SAFETY: the map file is private to the layer which owns it
+        self.map = unsafe { MmapMut::map_mut(&self.file)? };
+        Ok(())
+    }
+
+    // Write the generation through the file, so that sealing does not need a mutable map
+    fn write_generation(&self, generation: u64) -> crate::Result<()> {
+        let mut file = &self.file;
+        file.seek(SeekFrom::Start(GENERATION_OFFSET as u64))?;
+        file.write_all(&generation.to_le_bytes())?;
+        file.sync_data()?;
+
+        Ok(())
+    }
+}
+
+impl<K: Pod + Ord, V: Pod, const FANOUT: usize> NodeMap<K, V, FANOUT>
+    for MappedNodes<K, V, FANOUT>
+{
+    fn open(store: &GlobalStore, ident: &str) -> crate::Result<Self> {
+        let Some(path) = store.path() else {
+            return Ok(Self {
+                inner: None,
+                _ph: PhantomData,
+            });
+        };
+
+        if store.is_encrypted() {
+            return Err(Error::Encryption(format!(
+                "Cannot map nodes of `{ident}`, the map would hold the pages of an encrypted \
+                 store in plaintext"
+            )));
+        }
+
+        if align_of::<K>() > SLOT_ALIGN || align_of::<V>() > SLOT_ALIGN {
+            return Err(Error::Layout(format!(
+                "Cannot map nodes of `{ident}`, keys and values must be aligned to at most \
+                 {SLOT_ALIGN} bytes"
+            )));
+        }
+
+        let path = path.join(format!("{ident}.map"));
+        if store.is_read_only() {
+            return Ok(Self {
+                inner: Self::open_read_only(store, &path)?,
+                _ph: PhantomData,
+            });
+        }
+
+        let file = File::options()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)?;
+
+        if file.metadata()?.len() < Self::SLOT_SIZE as u64 {
+            file.set_len(0)?;
+        }
+
+        // SAFETY: the map file is private to the layer which owns it
+        let map = unsafe { MmapMut::map_mut(&file)? };
+        let generation = store.generation();
+
+        let mut mapped = MappedFile {
+            stale: map.is_empty() || !Self::is_valid(&map, generation),
+            dirty: Cell::new(false),
+            file,
+            map,
+            generation,
+        };
+
+        if mapped.stale {
+            // Start over from an empty file, the layer writes every node back
+            mapped.resize(0)?;
+            mapped.resize(INITIAL_SLOTS * Self::SLOT_SIZE)?;
+
+            let header = &mut mapped.map[..HEADER_SIZE];
+            Self::write_u64(header, MAGIC_OFFSET, MAGIC);
+            Self::write_u64(header, GENERATION_OFFSET, DIRTY);
+            Self::write_u64(header, SLOT_SIZE_OFFSET, Self::SLOT_SIZE as u64);
+            Self::write_u64(header, FANOUT_OFFSET, FANOUT as u64);
+            mapped.dirty.set(true);
+        } else {
+            // Still matches the pages, which have not been modified in this session yet
+            mapped.write_generation(generation)?;
+        }
+
+        Ok(Self {
+            inner: Some(mapped),
+            _ph: PhantomData,
+        })
+    }
+
+    fn is_stale(&self) -> bool {
+        self.inner.as_ref().is_some_and(|mapped| mapped.stale)
+    }
+
+    fn is_dirty(&self) -> bool {
+        self.inner.as_ref().is_some_and(|mapped| mapped.dirty.get())
+    }
+
+    fn mark_dirty(&mut self) -> crate::Result<()> {
+        let Some(mapped) = self.inner.as_mut() else {
+            return Ok(());
+        };
+
+        if !mapped.dirty.get() {
+            Self::write_u64(&mut mapped.map, GENERATION_OFFSET, DIRTY);
+            mapped.map.flush_range(0, HEADER_SIZE)?;
+            mapped.dirty.set(true);
+        }
+
+        Ok(())
+    }
+
+    fn write<S>(&mut self, ptr: StoreID, node: &BTreeNode<K, V, FANOUT, S>) -> crate::Result<()> {
+        let Some(mapped) = self.inner.as_mut() else {
+            return Ok(());
+        };
+
+        debug_assert!(mapped.dirty.get());
+        let slot = Self::slot(ptr);
+
+        if slot.end > mapped.map.len() {
+            mapped.resize(slot.end.max(mapped.map.len() * 2))?;
+        }
+
+        let slot = &mut mapped.map[slot];
+        let entries = node.entries();
+        Self::write_u64(slot, 0, entries.len() as u64);
+
+        let keys: &mut [K] = bytemuck::cast_slice_mut(
+            &mut slot[Self::KEYS_OFFSET..Self::KEYS_OFFSET + FANOUT * size_of::<K>()],
+        );
+        for (key, entry) in keys.iter_mut().zip(entries) {
+            *key = entry.key;
+        }
+
+        let values: &mut [V] = bytemuck::cast_slice_mut(
+            &mut slot[Self::VALUES_OFFSET..Self::VALUES_OFFSET + FANOUT * size_of::<V>()],
+        );
+        for (value, entry) in values.iter_mut().zip(entries) {
+            *value = entry.value;
+        }
+
+        Ok(())
+    }
+
+    fn view<S>(&self, ptr: StoreID) -> Option<SortedSlice<'_, K, V, S>> {
+        let mapped = self.inner.as_ref()?;
+        let slot = mapped.map.get(Self::slot(ptr))?;
+
+        let len = Self::read_u64(slot, 0) as usize;
+        if len > FANOUT {
+            return None;
+        }
+
+        let keys: &[K] = bytemuck::try_cast_slice(
+            &slot[Self::KEYS_OFFSET..Self::KEYS_OFFSET + len * size_of::<K>()],
+        )
+        .ok()?;
+        let values: &[V] = bytemuck::try_cast_slice(
+            &slot[Self::VALUES_OFFSET..Self::VALUES_OFFSET + len * size_of::<V>()],
+        )
+        .ok()?;
+
+        Some(SortedSlice::with_search(keys, values))
+    }
+
+    fn seal(&self) -> crate::Result<()> {
+        let Some(mapped) = self.inner.as_ref() else {
+            return Ok(());
+        };
+
+        mapped.map.flush()?;
+        mapped.write_generation(mapped.generation)?;
+        mapped.dirty.set(false);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use slice_search::OptimalSearch;
+
+    type Nodes = MappedNodes<u64, u32, 8>;
+
+    fn node(entries: &[(u64, u32)]) -> BTreeNode<u64, u32, 8> {
+        let mut node = BTreeNode::empty();
+        for &(key, value) in entries {
+            node.insert(key, value);
+        }
+        node
+    }
+
+    #[test]
+    fn write_and_view() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = GlobalStore::load(dir.path()).unwrap();
+
+        let mut nodes = Nodes::open(&store, "test").unwrap();
+        assert!(nodes.is_stale());
+
+        nodes.mark_dirty().unwrap();
+        nodes.write(3, &node(&[(1, 10), (5, 50)])).unwrap();
+        nodes.write(1000, &node(&[(7, 70)])).unwrap();
+
+        let view = nodes.view::<OptimalSearch>(3).unwrap();
+        assert_eq!(view.get_exact(&5), Some(&50));
+        assert_eq!(view.get_lower_bound_always(&4), &10);
+        assert_eq!(nodes.view::<OptimalSearch>(1000).unwrap().keys(), &[7]);
+        assert!(nodes.view::<OptimalSearch>(5000).is_none());
+    }
+
+    #[test]
+    fn reopen_after_seal() {
+        let dir = tempfile::tempdir().unwrap();
+
+        {
+            let store = GlobalStore::load(dir.path()).unwrap();
+            let mut nodes = Nodes::open(&store, "test").unwrap();
+            nodes.mark_dirty().unwrap();
+            nodes.write(1, &node(&[(1, 10)])).unwrap();
+            nodes.seal().unwrap();
+        }
+
+        // Sealed by the previous session
+        let store = GlobalStore::load(dir.path()).unwrap();
+        let nodes = Nodes::open(&store, "test").unwrap();
+        assert!(!nodes.is_stale());
+        assert_eq!(
+            nodes.view::<OptimalSearch>(1).unwrap().get_exact(&1),
+            Some(&10)
+        );
+    }
+
+    #[test]
+    fn stale_without_seal() {
+        let dir = tempfile::tempdir().unwrap();
+
+        {
+            let store = GlobalStore::load(dir.path()).unwrap();
+            let mut nodes = Nodes::open(&store, "test").unwrap();
+            nodes.seal().unwrap();
+
+            // Modified without sealing again
+            nodes.mark_dirty().unwrap();
+            nodes.write(1, &node(&[(1, 10)])).unwrap();
+        }
+
+        let store = GlobalStore::load(dir.path()).unwrap();
+        assert!(Nodes::open(&store, "test").unwrap().is_stale());
+    }
+
+    #[test]
+    fn read_only_uses_sealed_map() {
+        let dir = tempfile::tempdir().unwrap();
+
+        {
+            let store = GlobalStore::load_with(dir.path(), crate::Backend::File).unwrap();
+            let mut nodes = Nodes::open(&store, "test").unwrap();
+            nodes.mark_dirty().unwrap();
+            nodes.write(1, &node(&[(1, 10)])).unwrap();
+            nodes.seal().unwrap();
+        }
+
+        let store = GlobalStore::load_read_only(dir.path(), crate::Backend::File).unwrap();
+        let nodes = Nodes::open(&store, "test").unwrap();
+        assert!(!nodes.is_stale());
+        assert_eq!(
+            nodes.view::<OptimalSearch>(1).unwrap().get_exact(&1),
+            Some(&10)
+        );
+        drop(store);
+
+        // Sealed before the last writer, which may have modified the pages without the map
+        drop(GlobalStore::load_with(dir.path(), crate::Backend::File).unwrap());
+
+        let store = GlobalStore::load_read_only(dir.path(), crate::Backend::File).unwrap();
+        let nodes = Nodes::open(&store, "test").unwrap();
+        assert!(!nodes.is_stale());
+        assert!(nodes.view::<OptimalSearch>(1).is_none());
+    }
+
+    #[test]
+    fn stale_after_unmapped_session() {
+        let dir = tempfile::tempdir().unwrap();
+
+        {
+            let store = GlobalStore::load(dir.path()).unwrap();
+            Nodes::open(&store, "test").unwrap().seal().unwrap();
+        }
+
+        // A session which did not open the map may have modified the pages
+        drop(GlobalStore::load(dir.path()).unwrap());
+
+        let store = GlobalStore::load(dir.path()).unwrap();
+        assert!(Nodes::open(&store, "test").unwrap().is_stale());
+    }
+
+    #[test]
+    fn memory_backend_is_unmapped() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = GlobalStore::load_with(dir.path(), crate::Backend::Memory).unwrap();
+
+        let mut nodes = Nodes::open(&store, "test").unwrap();
+        nodes.mark_dirty().unwrap();
+        nodes.write(1, &node(&[(1, 10)])).unwrap();
+        assert!(nodes.view::<OptimalSearch>(1).is_none());
+    }
+}
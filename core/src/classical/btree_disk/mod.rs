@@ -1,14 +1,21 @@
 use crate::{
-    common::storage::{GlobalStore, StoreID},
+    common::storage::{GlobalStore, LayerStats, StoreID},
+    describe::LayerSize,
     impl_node_layer, Address, BoundaryDiskBaseComponent, BoundaryDiskInternalComponent,
-    DeepDiskBaseComponent, DeepDiskInternalComponent, Key, NodeLayer, Persisted, PropagateInsert,
+    DeepDiskBaseComponent, DeepDiskInternalComponent, DiskComponent, Key, Measure, NodeLayer,
+    Persisted, PropagateInsert,
 };
 
+use slice_search::{OptimalSearch, Search};
+
 use self::boundary_layer::BoundaryDiskBTreeLayer;
 use self::deep_layer::DeepDiskBTreeLayer;
 
 mod boundary_layer;
 mod deep_layer;
+mod mapped;
+
+pub use self::mapped::{MappedNodes, NodeMap};
 
 // -------------------------------------------------------
 //                 Boundary Internal Component
@@ -16,39 +23,93 @@ mod deep_layer;
 
 pub type BoundaryDiskBTreeInternalAddress = StoreID;
 
-pub struct BoundaryDiskBTreeInternalComponent<K, X, const FANOUT: usize, BA, PA>
-where
+pub struct BoundaryDiskBTreeInternalComponent<
+    K,
+    X,
+    const FANOUT: usize,
+    BA,
+    PA,
+    M = (),
+    S = OptimalSearch,
+    const FILL: usize = 50,
+    const RIGHTMOST: bool = false,
+> where
     K: Persisted + Ord,
     BA: Persisted,
+    S: Search<K> + 'static,
 {
-    pub inner: BoundaryDiskBTreeLayer<K, BA, FANOUT, PA>,
+    pub inner: BoundaryDiskBTreeLayer<K, BA, FANOUT, PA, M, S>,
     _ph: std::marker::PhantomData<X>,
 }
 
-impl<K, X, const FANOUT: usize, BA, PA> NodeLayer<K, BoundaryDiskBTreeInternalAddress, PA>
-    for BoundaryDiskBTreeInternalComponent<K, X, FANOUT, BA, PA>
+impl<K, X, const FANOUT: usize, BA, PA, M, S, const FILL: usize, const RIGHTMOST: bool>
+    NodeLayer<K, BoundaryDiskBTreeInternalAddress, PA>
+    for BoundaryDiskBTreeInternalComponent<K, X, FANOUT, BA, PA, M, S, FILL, RIGHTMOST>
 where
     K: Persisted + Key,
     BA: Persisted + Address,
     PA: Address,
+    M: NodeMap<K, BA, FANOUT>,
+    S: Search<K> + 'static,
 {
     impl_node_layer!(StoreID, PA);
 }
 
-impl<K, X, BA, PA, B: NodeLayer<K, BA, BoundaryDiskBTreeInternalAddress>, const FANOUT: usize>
-    BoundaryDiskInternalComponent<K, B, BA, BoundaryDiskBTreeInternalAddress, PA>
-    for BoundaryDiskBTreeInternalComponent<K, X, FANOUT, BA, PA>
+impl<K, X, const FANOUT: usize, BA, PA, M, S, const FILL: usize, const RIGHTMOST: bool>
+    DiskComponent
+    for BoundaryDiskBTreeInternalComponent<K, X, FANOUT, BA, PA, M, S, FILL, RIGHTMOST>
+where
+    K: Persisted + Key,
+    BA: Persisted + Address,
+    PA: Address,
+    M: NodeMap<K, BA, FANOUT>,
+    S: Search<K> + 'static,
+{
+    fn flush(&self) -> crate::Result<()> {
+        self.inner.flush()
+    }
+
+    fn stats(&self) -> crate::Result<LayerStats> {
+        self.inner.stats()
+    }
+}
+
+impl<K, X, const FANOUT: usize, BA, PA, M, S, const FILL: usize, const RIGHTMOST: bool> Measure
+    for BoundaryDiskBTreeInternalComponent<K, X, FANOUT, BA, PA, M, S, FILL, RIGHTMOST>
 where
     K: Persisted + Key,
     BA: Persisted + Address,
     PA: Address,
+    M: NodeMap<K, BA, FANOUT>,
+    S: Search<K> + 'static,
+{
+    fn measure(&self) -> crate::Result<LayerSize> {
+        self.inner.measure()
+    }
+}
+
+impl<
+        K,
+        X,
+        BA,
+        PA,
+        B: NodeLayer<K, BA, BoundaryDiskBTreeInternalAddress>,
+        const FANOUT: usize,
+        M,
+        S,
+        const FILL: usize,
+        const RIGHTMOST: bool,
+    > BoundaryDiskInternalComponent<K, B, BA, BoundaryDiskBTreeInternalAddress, PA>
+    for BoundaryDiskBTreeInternalComponent<K, X, FANOUT, BA, PA, M, S, FILL, RIGHTMOST>
+where
+    K: Persisted + Key,
+    BA: Persisted + Address,
+    PA: Address,
+    M: NodeMap<K, BA, FANOUT>,
+    S: Search<K> + 'static,
 {
     fn search(&self, _: &B, ptr: BoundaryDiskBTreeInternalAddress, key: &K) -> crate::Result<BA> {
-        Ok(self
-            .inner
-            .get_node(ptr)?
-            .get_lower_bound_always(key)
-            .clone())
+        self.inner.search_lower_bound(ptr, key)
     }
 
     fn insert(
@@ -59,7 +120,7 @@ where
         Ok(match prop {
             PropagateInsert::Single(key, address, ptr) => self
                 .inner
-                .insert_with_parent(key, address, base, ptr)?
+                .insert_with_parent(key, address, base, ptr, RIGHTMOST)?
                 .map(|(key, address, parent)| PropagateInsert::Single(key, address, parent)),
             PropagateInsert::Replace { .. } => {
                 unimplemented!()
@@ -69,7 +130,7 @@ where
 
     fn load(base: &mut B, store: &mut GlobalStore, ident: impl ToString) -> crate::Result<Self> {
         let mut result = BoundaryDiskBTreeLayer::load(store, ident)?;
-        result.fill_with_parent(base)?;
+        result.fill_with_parent(base, FILL)?;
 
         Ok(Self {
             inner: result,
@@ -84,31 +145,77 @@ where
 
 pub type BoundaryDiskBTreeBaseAddress = StoreID;
 
-pub struct BoundaryDiskBTreeBaseComponent<K, V, const FANOUT: usize, PA>
-where
+pub struct BoundaryDiskBTreeBaseComponent<
+    K,
+    V,
+    const FANOUT: usize,
+    PA,
+    M = (),
+    S = OptimalSearch,
+    const FILL: usize = 50,
+    const RIGHTMOST: bool = false,
+> where
     K: Persisted + Ord,
     V: Persisted,
+    S: Search<K> + 'static,
 {
-    pub inner: BoundaryDiskBTreeLayer<K, V, FANOUT, PA>,
+    pub inner: BoundaryDiskBTreeLayer<K, V, FANOUT, PA, M, S>,
 }
 
-impl<K, V, const FANOUT: usize, PA> NodeLayer<K, BoundaryDiskBTreeBaseAddress, PA>
-    for BoundaryDiskBTreeBaseComponent<K, V, FANOUT, PA>
+impl<K, V, const FANOUT: usize, PA, M, S, const FILL: usize, const RIGHTMOST: bool>
+    NodeLayer<K, BoundaryDiskBTreeBaseAddress, PA>
+    for BoundaryDiskBTreeBaseComponent<K, V, FANOUT, PA, M, S, FILL, RIGHTMOST>
 where
     K: Persisted + Key,
     V: Persisted,
     PA: Address,
+    M: NodeMap<K, V, FANOUT>,
+    S: Search<K> + 'static,
 {
     impl_node_layer!(StoreID, PA);
 }
 
-impl<K, V, const FANOUT: usize, PA: 'static>
+impl<K, V, const FANOUT: usize, PA, M, S, const FILL: usize, const RIGHTMOST: bool> DiskComponent
+    for BoundaryDiskBTreeBaseComponent<K, V, FANOUT, PA, M, S, FILL, RIGHTMOST>
+where
+    K: Persisted + Key,
+    V: Persisted,
+    PA: Address,
+    M: NodeMap<K, V, FANOUT>,
+    S: Search<K> + 'static,
+{
+    fn flush(&self) -> crate::Result<()> {
+        self.inner.flush()
+    }
+
+    fn stats(&self) -> crate::Result<LayerStats> {
+        self.inner.stats()
+    }
+}
+
+impl<K, V, const FANOUT: usize, PA, M, S, const FILL: usize, const RIGHTMOST: bool> Measure
+    for BoundaryDiskBTreeBaseComponent<K, V, FANOUT, PA, M, S, FILL, RIGHTMOST>
+where
+    K: Persisted + Key,
+    V: Persisted,
+    PA: Address,
+    M: NodeMap<K, V, FANOUT>,
+    S: Search<K> + 'static,
+{
+    fn measure(&self) -> crate::Result<LayerSize> {
+        self.inner.measure()
+    }
+}
+
+impl<K, V, const FANOUT: usize, PA: 'static, M, S, const FILL: usize, const RIGHTMOST: bool>
     BoundaryDiskBaseComponent<K, V, BoundaryDiskBTreeBaseAddress, PA>
-    for BoundaryDiskBTreeBaseComponent<K, V, FANOUT, PA>
+    for BoundaryDiskBTreeBaseComponent<K, V, FANOUT, PA, M, S, FILL, RIGHTMOST>
 where
     K: Persisted + Key,
     V: Persisted,
     PA: Address,
+    M: NodeMap<K, V, FANOUT>,
+    S: Search<K> + 'static,
 {
     fn insert(
         &mut self,
@@ -116,7 +223,7 @@ where
         key: K,
         value: V,
     ) -> crate::Result<Option<PropagateInsert<K, BoundaryDiskBTreeBaseAddress, PA>>> {
-        if let Some((key, address, parent)) = self.inner.insert(key, value, ptr)? {
+        if let Some((key, address, parent)) = self.inner.insert(key, value, ptr, RIGHTMOST)? {
             Ok(Some(PropagateInsert::Single(key, address, parent)))
         } else {
             Ok(None)
@@ -124,7 +231,7 @@ where
     }
 
     fn search(&self, ptr: BoundaryDiskBTreeInternalAddress, key: &K) -> crate::Result<Option<V>> {
-        Ok(self.inner.get_node(ptr)?.get_exact(key).cloned())
+        self.inner.search_exact(ptr, key)
     }
 
     fn load(store: &mut GlobalStore, ident: impl ToString) -> crate::Result<Self> {
@@ -132,6 +239,21 @@ where
             inner: BoundaryDiskBTreeLayer::load(store, ident)?,
         })
     }
+
+    fn build(
+        store: &mut GlobalStore,
+        ident: impl ToString,
+        iter: impl Iterator<Item = (K, V)>,
+    ) -> crate::Result<Self> {
+        let mut inner = BoundaryDiskBTreeLayer::load(store, ident)?;
+        inner.fill(iter, FILL)?;
+
+        Ok(Self { inner })
+    }
+
+    fn entries(&self) -> impl Iterator<Item = crate::Result<(K, V)>> + '_ {
+        self.inner.entries()
+    }
 }
 
 // -------------------------------------------------------
@@ -140,40 +262,93 @@ where
 
 pub type DeepDiskBTreeInternalAddress = StoreID;
 
-pub struct DeepDiskBTreeInternalComponent<K, X, const FANOUT: usize, BA, PA>
-where
+pub struct DeepDiskBTreeInternalComponent<
+    K,
+    X,
+    const FANOUT: usize,
+    BA,
+    PA,
+    M = (),
+    S = OptimalSearch,
+    const FILL: usize = 50,
+    const RIGHTMOST: bool = false,
+> where
     K: Persisted + Ord,
     BA: Persisted + Eq,
     PA: Persisted + Eq,
+    S: Search<K> + 'static,
 {
-    pub inner: DeepDiskBTreeLayer<K, BA, FANOUT, PA>,
+    pub inner: DeepDiskBTreeLayer<K, BA, FANOUT, PA, M, S>,
     _ph: std::marker::PhantomData<X>,
 }
 
-impl<K, X, const FANOUT: usize, BA, PA> NodeLayer<K, DeepDiskBTreeInternalAddress, PA>
-    for DeepDiskBTreeInternalComponent<K, X, FANOUT, BA, PA>
+impl<K, X, const FANOUT: usize, BA, PA, M, S, const FILL: usize, const RIGHTMOST: bool>
+    NodeLayer<K, DeepDiskBTreeInternalAddress, PA>
+    for DeepDiskBTreeInternalComponent<K, X, FANOUT, BA, PA, M, S, FILL, RIGHTMOST>
 where
     K: Persisted + Key,
     BA: Persisted + Address,
     PA: Persisted + Address,
+    M: NodeMap<K, BA, FANOUT>,
+    S: Search<K> + 'static,
 {
     impl_node_layer!(StoreID, PA);
 }
 
-impl<K, X, BA, PA, B: NodeLayer<K, BA, DeepDiskBTreeInternalAddress>, const FANOUT: usize>
-    DeepDiskInternalComponent<K, B, BA, DeepDiskBTreeInternalAddress, PA>
-    for DeepDiskBTreeInternalComponent<K, X, FANOUT, BA, PA>
+impl<K, X, const FANOUT: usize, BA, PA, M, S, const FILL: usize, const RIGHTMOST: bool>
+    DiskComponent for DeepDiskBTreeInternalComponent<K, X, FANOUT, BA, PA, M, S, FILL, RIGHTMOST>
+where
+    K: Persisted + Key,
+    BA: Persisted + Address,
+    PA: Persisted + Address,
+    M: NodeMap<K, BA, FANOUT>,
+    S: Search<K> + 'static,
+{
+    fn flush(&self) -> crate::Result<()> {
+        self.inner.flush()
+    }
+
+    fn stats(&self) -> crate::Result<LayerStats> {
+        self.inner.stats()
+    }
+}
+
+impl<K, X, const FANOUT: usize, BA, PA, M, S, const FILL: usize, const RIGHTMOST: bool> Measure
+    for DeepDiskBTreeInternalComponent<K, X, FANOUT, BA, PA, M, S, FILL, RIGHTMOST>
 where
     K: Persisted + Key,
     BA: Persisted + Address,
     PA: Persisted + Address,
+    M: NodeMap<K, BA, FANOUT>,
+    S: Search<K> + 'static,
+{
+    fn measure(&self) -> crate::Result<LayerSize> {
+        self.inner.measure()
+    }
+}
+
+impl<
+        K,
+        X,
+        BA,
+        PA,
+        B: NodeLayer<K, BA, DeepDiskBTreeInternalAddress>,
+        const FANOUT: usize,
+        M,
+        S,
+        const FILL: usize,
+        const RIGHTMOST: bool,
+    > DeepDiskInternalComponent<K, B, BA, DeepDiskBTreeInternalAddress, PA>
+    for DeepDiskBTreeInternalComponent<K, X, FANOUT, BA, PA, M, S, FILL, RIGHTMOST>
+where
+    K: Persisted + Key,
+    BA: Persisted + Address,
+    PA: Persisted + Address,
+    M: NodeMap<K, BA, FANOUT>,
+    S: Search<K> + 'static,
 {
     fn search(&self, _: &B, ptr: DeepDiskBTreeInternalAddress, key: &K) -> crate::Result<BA> {
-        Ok(self
-            .inner
-            .get_node(ptr)?
-            .get_lower_bound_always(key)
-            .clone())
+        self.inner.search_lower_bound(ptr, key)
     }
 
     fn insert(
@@ -184,7 +359,7 @@ where
         Ok(match prop {
             PropagateInsert::Single(key, address, ptr) => self
                 .inner
-                .insert_with_parent(key, address, base, ptr)?
+                .insert_with_parent(key, address, base, ptr, RIGHTMOST)?
                 .map(|(key, address, parent)| PropagateInsert::Single(key, address, parent)),
             PropagateInsert::Replace { .. } => {
                 unimplemented!()
@@ -194,7 +369,7 @@ where
 
     fn load(base: &mut B, store: &mut GlobalStore, ident: impl ToString) -> crate::Result<Self> {
         let mut result = DeepDiskBTreeLayer::load(store, ident)?;
-        result.fill_with_parent(base)?;
+        result.fill_with_parent(base, FILL)?;
 
         Ok(Self {
             inner: result,
@@ -209,32 +384,78 @@ where
 
 pub type DeepDiskBTreeBaseAddress = StoreID;
 
-pub struct DeepDiskBTreeBaseComponent<K, V, const FANOUT: usize, PA>
-where
+pub struct DeepDiskBTreeBaseComponent<
+    K,
+    V,
+    const FANOUT: usize,
+    PA,
+    M = (),
+    S = OptimalSearch,
+    const FILL: usize = 50,
+    const RIGHTMOST: bool = false,
+> where
     K: Persisted + Ord,
     V: Persisted + Eq,
     PA: Persisted + Eq,
+    S: Search<K> + 'static,
 {
-    pub inner: DeepDiskBTreeLayer<K, V, FANOUT, PA>,
+    pub inner: DeepDiskBTreeLayer<K, V, FANOUT, PA, M, S>,
 }
 
-impl<K, V, const FANOUT: usize, PA: 'static> NodeLayer<K, DeepDiskBTreeBaseAddress, PA>
-    for DeepDiskBTreeBaseComponent<K, V, FANOUT, PA>
+impl<K, V, const FANOUT: usize, PA: 'static, M, S, const FILL: usize, const RIGHTMOST: bool>
+    NodeLayer<K, DeepDiskBTreeBaseAddress, PA>
+    for DeepDiskBTreeBaseComponent<K, V, FANOUT, PA, M, S, FILL, RIGHTMOST>
 where
     K: Persisted + Key,
     V: Persisted + Eq,
     PA: Persisted + Address,
+    M: NodeMap<K, V, FANOUT>,
+    S: Search<K> + 'static,
 {
     impl_node_layer!(StoreID, PA);
 }
 
-impl<K, V, const FANOUT: usize, PA: 'static>
+impl<K, V, const FANOUT: usize, PA: 'static, M, S, const FILL: usize, const RIGHTMOST: bool>
+    DiskComponent for DeepDiskBTreeBaseComponent<K, V, FANOUT, PA, M, S, FILL, RIGHTMOST>
+where
+    K: Persisted + Key,
+    V: Persisted + Eq,
+    PA: Persisted + Address,
+    M: NodeMap<K, V, FANOUT>,
+    S: Search<K> + 'static,
+{
+    fn flush(&self) -> crate::Result<()> {
+        self.inner.flush()
+    }
+
+    fn stats(&self) -> crate::Result<LayerStats> {
+        self.inner.stats()
+    }
+}
+
+impl<K, V, const FANOUT: usize, PA: 'static, M, S, const FILL: usize, const RIGHTMOST: bool> Measure
+    for DeepDiskBTreeBaseComponent<K, V, FANOUT, PA, M, S, FILL, RIGHTMOST>
+where
+    K: Persisted + Key,
+    V: Persisted + Eq,
+    PA: Persisted + Address,
+    M: NodeMap<K, V, FANOUT>,
+    S: Search<K> + 'static,
+{
+    fn measure(&self) -> crate::Result<LayerSize> {
+        self.inner.measure()
+    }
+}
+
+impl<K, V, const FANOUT: usize, PA: 'static, M, S, const FILL: usize, const RIGHTMOST: bool>
     DeepDiskBaseComponent<K, V, BoundaryDiskBTreeBaseAddress, PA>
-    for DeepDiskBTreeBaseComponent<K, V, FANOUT, PA>
+    for DeepDiskBTreeBaseComponent<K, V, FANOUT, PA, M, S, FILL, RIGHTMOST>
 where
     K: Persisted + Key,
     V: Persisted + Eq,
     PA: Persisted + Address,
+    M: NodeMap<K, V, FANOUT>,
+    S: Search<K> + 'static,
 {
     fn insert(
         &mut self,
@@ -242,7 +463,7 @@ where
         key: K,
         value: V,
     ) -> crate::Result<Option<PropagateInsert<K, BoundaryDiskBTreeBaseAddress, PA>>> {
-        if let Some((key, address, parent)) = self.inner.insert(key, value, ptr)? {
+        if let Some((key, address, parent)) = self.inner.insert(key, value, ptr, RIGHTMOST)? {
             Ok(Some(PropagateInsert::Single(key, address, parent)))
         } else {
             Ok(None)
@@ -250,7 +471,7 @@ where
     }
 
     fn search(&self, ptr: BoundaryDiskBTreeInternalAddress, key: &K) -> crate::Result<Option<V>> {
-        Ok(self.inner.get_node(ptr)?.get_exact(key).cloned())
+        self.inner.search_exact(ptr, key)
     }
 
     fn load(store: &mut GlobalStore, ident: impl ToString) -> crate::Result<Self> {
@@ -258,4 +479,19 @@ where
             inner: DeepDiskBTreeLayer::load(store, ident)?,
         })
     }
+
+    fn build(
+        store: &mut GlobalStore,
+        ident: impl ToString,
+        iter: impl Iterator<Item = (K, V)>,
+    ) -> crate::Result<Self> {
+        let mut inner = DeepDiskBTreeLayer::load(store, ident)?;
+        inner.fill(iter, FILL)?;
+
+        Ok(Self { inner })
+    }
+
+    fn entries(&self) -> impl Iterator<Item = crate::Result<(K, V)>> + '_ {
+        self.inner.entries()
+    }
 }
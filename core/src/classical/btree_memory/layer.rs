@@ -1,21 +1,30 @@
 use crate::classical::node::BTreeNode;
 use crate::common::list::memory::*;
+use crate::describe::LayerSize;
 use crate::node_layer::{impl_node_layer, NodeLayer};
 use crate::traits::{Address, KeyBounded};
 use crate::Key;
+use serde::{Deserialize, Serialize};
+use slice_search::Search;
 use std::ops::Bound;
 
 // ----------------------------------------
 // Layer Type
 // ----------------------------------------
 
-pub struct MemoryBTreeLayer<K: Ord, V, const FANOUT: usize, PA> {
-    inner: MemoryList<BTreeNode<K, V, FANOUT>, PA>,
+#[derive(Serialize, Deserialize)]
+pub struct MemoryBTreeLayer<K: Ord, V, const FANOUT: usize, PA, S> {
+    #[serde(bound(
+        serialize = "K: Serialize, V: Serialize, PA: Serialize",
+        deserialize = "K: Deserialize<'de>, V: Deserialize<'de>, PA: Deserialize<'de>, S: Search<K>"
+    ))]
+    inner: MemoryList<BTreeNode<K, V, FANOUT, S>, PA>,
 }
 
-impl<K, V, const FANOUT: usize, PA> MemoryBTreeLayer<K, V, FANOUT, PA>
+impl<K, V, const FANOUT: usize, PA, S> MemoryBTreeLayer<K, V, FANOUT, PA, S>
 where
     K: Key,
+    S: Search<K>,
 {
     pub fn empty() -> Self {
         Self {
@@ -23,13 +32,13 @@ where
         }
     }
 
-    pub fn fill(&mut self, iter: impl Iterator<Item = (K, V)>) {
+    pub fn fill(&mut self, iter: impl Iterator<Item = (K, V)>, fill: usize) {
         // Add empty cap node
         let mut ptr = self.inner.clear();
 
         for (key, address) in iter {
             // If node too full, carry over to next
-            if self.inner[ptr].is_half_full() {
+            if self.inner[ptr].is_filled_to(fill) {
                 ptr = self.inner.insert_after(BTreeNode::empty(), ptr);
             }
 
@@ -37,7 +46,20 @@ where
         }
     }
 
-    pub fn fill_with_parent<B: NodeLayer<K, V, ArenaID>>(&mut self, base: &mut B)
+    pub fn nodes(&self) -> impl Iterator<Item = &BTreeNode<K, V, FANOUT, S>> + '_ {
+        self.inner.nodes()
+    }
+
+    pub fn measure(&self) -> LayerSize {
+        LayerSize {
+            nodes: self.inner.len(),
+            keys: self.nodes().map(|node| node.len()).sum(),
+            memory_bytes: self.inner.memory_bytes(),
+            disk_bytes: 0,
+        }
+    }
+
+    pub fn fill_with_parent<B: NodeLayer<K, V, ArenaID>>(&mut self, base: &mut B, fill: usize)
     where
         V: Address,
     {
@@ -47,7 +69,7 @@ where
 
         while let Some((key, address, parent)) = iter.next() {
             // If node too full, carry over to next
-            if self.inner[ptr].is_half_full() {
+            if self.inner[ptr].is_filled_to(fill) {
                 ptr = self.inner.insert_after(BTreeNode::empty(), ptr);
             }
 
@@ -56,7 +78,27 @@ where
         }
     }
 
-    pub fn insert(&mut self, key: K, value: V, ptr: ArenaID) -> Option<(K, ArenaID, PA)>
+    // Split a full node before `key` is inserted. With `rightmost` set, keys appended past the end
+    // of the layer leave the node full and start a new empty node instead, so that monotonically
+    // increasing keys fill every node
+    fn split(&mut self, ptr: ArenaID, key: &K, rightmost: bool) -> (K, BTreeNode<K, V, FANOUT, S>)
+    where
+        PA: Address,
+    {
+        if rightmost && ptr == self.inner.last() && self.inner[ptr].is_appended(key) {
+            (*key, BTreeNode::empty())
+        } else {
+            self.inner[ptr].split()
+        }
+    }
+
+    pub fn insert(
+        &mut self,
+        key: K,
+        value: V,
+        ptr: ArenaID,
+        rightmost: bool,
+    ) -> Option<(K, ArenaID, PA)>
     where
         PA: Address,
     {
@@ -64,7 +106,7 @@ where
             let parent = self.inner.parent(ptr).unwrap();
 
             // Split
-            let (split_point, new_node) = self.inner[ptr].split();
+            let (split_point, new_node) = self.split(ptr, &key, rightmost);
             let new_node_ptr = self.inner.insert_after(new_node, ptr);
 
             // Insert into the right node
@@ -92,6 +134,7 @@ where
         value: V,
         base: &mut B,
         ptr: ArenaID,
+        rightmost: bool,
     ) -> Option<(K, ArenaID, PA)>
     where
         V: Address,
@@ -101,7 +144,7 @@ where
             let parent = self.inner.parent(ptr).unwrap();
 
             // Split
-            let (split_point, new_node) = self.inner[ptr].split();
+            let (split_point, new_node) = self.split(ptr, &key, rightmost);
             let new_node_ptr = self.inner.insert_after(new_node, ptr);
 
             // Update all of the parents for the split node
@@ -132,20 +175,22 @@ where
     }
 }
 
-impl<K: Ord, V, const FANOUT: usize, PA> core::ops::Index<ArenaID>
-    for MemoryBTreeLayer<K, V, FANOUT, PA>
+impl<K: Ord, V, const FANOUT: usize, PA, S> core::ops::Index<ArenaID>
+    for MemoryBTreeLayer<K, V, FANOUT, PA, S>
 {
-    type Output = BTreeNode<K, V, FANOUT>;
+    type Output = BTreeNode<K, V, FANOUT, S>;
 
     fn index(&self, index: ArenaID) -> &Self::Output {
         &self.inner[index]
     }
 }
 
-impl<K, V, const FANOUT: usize, PA> NodeLayer<K, ArenaID, PA> for MemoryBTreeLayer<K, V, FANOUT, PA>
+impl<K, V, const FANOUT: usize, PA, S> NodeLayer<K, ArenaID, PA>
+    for MemoryBTreeLayer<K, V, FANOUT, PA, S>
 where
     K: Key,
     PA: Address,
+    S: 'static,
 {
     impl_node_layer!(ArenaID, PA);
 }
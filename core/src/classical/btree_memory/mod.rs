@@ -1,10 +1,13 @@
 mod layer;
 
 use crate::common::list::memory::ArenaID;
+use crate::describe::LayerSize;
 use crate::node_layer::{impl_node_layer, NodeLayer};
 use crate::traits::Address;
 use crate::{component::*, Key, Value};
 use layer::*;
+use serde::{Deserialize, Serialize};
+use slice_search::{OptimalSearch, Search};
 
 // -------------------------------------------------------
 //                  Internal Component
@@ -12,13 +15,28 @@ use layer::*;
 
 pub type BTreeInternalAddress = ArenaID;
 
-pub struct BTreeInternalComponent<K: Key, X: 'static, const FANOUT: usize, BA, PA> {
-    inner: MemoryBTreeLayer<K, BA, FANOUT, PA>,
+#[derive(Serialize, Deserialize)]
+pub struct BTreeInternalComponent<
+    K: Key,
+    X: 'static,
+    const FANOUT: usize,
+    BA,
+    PA,
+    S: 'static = OptimalSearch,
+    const FILL: usize = 50,
+    const RIGHTMOST: bool = false,
+> {
+    #[serde(bound(
+        serialize = "K: Serialize, BA: Serialize, PA: Serialize",
+        deserialize = "K: Deserialize<'de>, BA: Deserialize<'de>, PA: Deserialize<'de>, S: Search<K>"
+    ))]
+    inner: MemoryBTreeLayer<K, BA, FANOUT, PA, S>,
     _ph: std::marker::PhantomData<X>,
 }
 
-impl<K, X, const FANOUT: usize, BA, PA> NodeLayer<K, BTreeInternalAddress, PA>
-    for BTreeInternalComponent<K, X, FANOUT, BA, PA>
+impl<K, X, const FANOUT: usize, BA, PA, S, const FILL: usize, const RIGHTMOST: bool>
+    NodeLayer<K, BTreeInternalAddress, PA>
+    for BTreeInternalComponent<K, X, FANOUT, BA, PA, S, FILL, RIGHTMOST>
 where
     K: Key,
     BA: Address,
@@ -27,13 +45,34 @@ where
     impl_node_layer!(ArenaID, PA);
 }
 
-impl<K, X, BA, PA, B: NodeLayer<K, BA, BTreeInternalAddress>, const FANOUT: usize>
-    InternalComponent<K, B, BA, BTreeInternalAddress, PA>
-    for BTreeInternalComponent<K, X, FANOUT, BA, PA>
+impl<K, X, const FANOUT: usize, BA, PA, S, const FILL: usize, const RIGHTMOST: bool> Measure
+    for BTreeInternalComponent<K, X, FANOUT, BA, PA, S, FILL, RIGHTMOST>
+where
+    K: Key,
+    S: Search<K>,
+{
+    fn measure(&self) -> crate::Result<LayerSize> {
+        Ok(self.inner.measure())
+    }
+}
+
+impl<
+        K,
+        X,
+        BA,
+        PA,
+        B: NodeLayer<K, BA, BTreeInternalAddress>,
+        const FANOUT: usize,
+        S,
+        const FILL: usize,
+        const RIGHTMOST: bool,
+    > InternalComponent<K, B, BA, BTreeInternalAddress, PA>
+    for BTreeInternalComponent<K, X, FANOUT, BA, PA, S, FILL, RIGHTMOST>
 where
     K: Key,
     BA: Address,
     PA: Address,
+    S: Search<K> + 'static,
 {
     fn search(&self, _: &B, ptr: BTreeInternalAddress, key: &K) -> BA {
         self.inner[ptr].get_lower_bound_always(key).clone()
@@ -47,7 +86,7 @@ where
         match prop {
             PropagateInsert::Single(key, address, ptr) => self
                 .inner
-                .insert_with_parent(key, address, base, ptr)
+                .insert_with_parent(key, address, base, ptr, RIGHTMOST)
                 .map(|(key, address, parent)| PropagateInsert::Single(key, address, parent)),
             PropagateInsert::Replace { .. } => {
                 unimplemented!()
@@ -57,7 +96,7 @@ where
 
     fn build(base: &mut B) -> Self {
         let mut result = MemoryBTreeLayer::empty();
-        result.fill_with_parent(base);
+        result.fill_with_parent(base, FILL);
 
         Self {
             inner: result,
@@ -72,12 +111,28 @@ where
 
 pub type BTreeBaseAddress = BTreeInternalAddress;
 
-pub struct BTreeBaseComponent<K: Ord, V, const FANOUT: usize, PA> {
-    inner: MemoryBTreeLayer<K, V, FANOUT, PA>,
+pub struct BTreeBaseComponent<
+    K: Ord,
+    V,
+    const FANOUT: usize,
+    PA,
+    S = OptimalSearch,
+    const FILL: usize = 50,
+    const RIGHTMOST: bool = false,
+> {
+    inner: MemoryBTreeLayer<K, V, FANOUT, PA, S>,
 }
 
-impl<K, V, const FANOUT: usize, PA: 'static> NodeLayer<K, BTreeBaseAddress, PA>
-    for BTreeBaseComponent<K, V, FANOUT, PA>
+impl<
+        K,
+        V,
+        const FANOUT: usize,
+        PA: 'static,
+        S: 'static,
+        const FILL: usize,
+        const RIGHTMOST: bool,
+    > NodeLayer<K, BTreeBaseAddress, PA>
+    for BTreeBaseComponent<K, V, FANOUT, PA, S, FILL, RIGHTMOST>
 where
     K: Key,
     V: Value,
@@ -86,12 +141,25 @@ where
     impl_node_layer!(ArenaID, PA);
 }
 
-impl<K, V, const FANOUT: usize, PA: 'static> BaseComponent<K, V, BTreeBaseAddress, PA>
-    for BTreeBaseComponent<K, V, FANOUT, PA>
+impl<K, V, const FANOUT: usize, PA, S, const FILL: usize, const RIGHTMOST: bool> Measure
+    for BTreeBaseComponent<K, V, FANOUT, PA, S, FILL, RIGHTMOST>
+where
+    K: Key,
+    S: Search<K>,
+{
+    fn measure(&self) -> crate::Result<LayerSize> {
+        Ok(self.inner.measure())
+    }
+}
+
+impl<K, V, const FANOUT: usize, PA: 'static, S, const FILL: usize, const RIGHTMOST: bool>
+    BaseComponent<K, V, BTreeBaseAddress, PA>
+    for BTreeBaseComponent<K, V, FANOUT, PA, S, FILL, RIGHTMOST>
 where
     K: Key,
     V: Value,
     PA: Address,
+    S: Search<K> + 'static,
 {
     fn insert(
         &mut self,
@@ -99,7 +167,7 @@ where
         key: K,
         value: V,
     ) -> Option<PropagateInsert<K, BTreeBaseAddress, PA>> {
-        if let Some((key, address, parent)) = self.inner.insert(key, value, ptr) {
+        if let Some((key, address, parent)) = self.inner.insert(key, value, ptr, RIGHTMOST) {
             Some(PropagateInsert::Single(key, address, parent))
         } else {
             None
@@ -118,8 +186,16 @@ where
 
     fn build(iter: impl Iterator<Item = (K, V)>) -> Self {
         let mut result = MemoryBTreeLayer::empty();
-        result.fill(iter);
+        result.fill(iter, FILL);
 
         Self { inner: result }
     }
+
+    fn entries(&self) -> impl Iterator<Item = (K, V)> + '_ {
+        self.inner.nodes().flat_map(|node| {
+            node.entries()
+                .iter()
+                .map(|entry| (entry.key, entry.value.clone()))
+        })
+    }
 }
@@ -1,17 +1,33 @@
-use crate::component::{PropagateInsert, TopComponent};
+use crate::component::{Measure, PropagateInsert, TopComponent};
+use crate::describe::LayerSize;
 use crate::node_layer::NodeLayer;
 use crate::traits::Address;
 use crate::Key;
+use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
 use std::ops::Bound;
 
 /// A `TopComponent` implementation built around the BTreeMap implementation in the Rust standard
 /// library.
+#[derive(Serialize, Deserialize)]
 pub struct BTreeTopComponent<K, X, A> {
+    #[serde(bound(deserialize = "K: Deserialize<'de> + Ord, A: Deserialize<'de>"))]
     pub inner: BTreeMap<K, A>,
     _ph: std::marker::PhantomData<X>,
 }
 
+impl<K, X, A> Measure for BTreeTopComponent<K, X, A> {
+    fn measure(&self) -> crate::Result<LayerSize> {
+        // The nodes of a `BTreeMap` are not exposed, so only its entries are counted
+        Ok(LayerSize {
+            nodes: 1,
+            keys: self.inner.len(),
+            memory_bytes: self.inner.len() * std::mem::size_of::<(K, A)>(),
+            disk_bytes: 0,
+        })
+    }
+}
+
 impl<K, X, Base, BA: Copy> TopComponent<K, Base, BA, ()> for BTreeTopComponent<K, X, BA>
 where
     Base: NodeLayer<K, BA, ()>,
@@ -1,42 +1,48 @@
 use crate::traits::KeyBounded;
 use crate::traits::StaticBounded;
 use serde::{Deserialize, Serialize};
+use slice_search::{OptimalSearch, Search};
 use sorted_array::SortedArray;
+use std::fmt::Debug;
 use std::ops::Deref;
 use std::ops::DerefMut;
 
-#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Debug)]
-pub struct BTreeNode<K: Ord, V, const FANOUT: usize> {
+/// Node of a B-tree layer, whose entries are searched with the strategy `S`
+#[derive(Serialize, Deserialize)]
+pub struct BTreeNode<K: Ord, V, const FANOUT: usize, S = OptimalSearch> {
     // Serde derive has some trouble introducing the right bounds here
     #[serde(bound(
-        deserialize = "K: Serialize + Deserialize<'de> + Ord, V: Serialize + Deserialize<'de>"
+        serialize = "K: Serialize, V: Serialize",
+        deserialize = "K: Deserialize<'de> + Ord, V: Deserialize<'de>, S: Search<K>"
     ))]
-    inner: SortedArray<K, V, FANOUT>,
+    inner: SortedArray<K, V, FANOUT, S>,
 }
 
-impl<K: Ord, V, const FANOUT: usize> Deref for BTreeNode<K, V, FANOUT> {
-    type Target = SortedArray<K, V, FANOUT>;
+impl<K: Ord, V, const FANOUT: usize, S> Deref for BTreeNode<K, V, FANOUT, S> {
+    type Target = SortedArray<K, V, FANOUT, S>;
 
     fn deref(&self) -> &Self::Target {
         &self.inner
     }
 }
 
-impl<K: Ord, V, const FANOUT: usize> DerefMut for BTreeNode<K, V, FANOUT> {
+impl<K: Ord, V, const FANOUT: usize, S> DerefMut for BTreeNode<K, V, FANOUT, S> {
     fn deref_mut(&mut self) -> &mut Self::Target {
         &mut self.inner
     }
 }
 
-impl<K: Ord, V, const FANOUT: usize> BTreeNode<K, V, FANOUT> {
+impl<K: Ord, V, const FANOUT: usize, S> BTreeNode<K, V, FANOUT, S> {
     pub fn empty() -> Self {
         Self {
             inner: SortedArray::empty(),
         }
     }
 
-    pub fn is_half_full(&self) -> bool {
-        self.inner.len() >= FANOUT / 2
+    /// Whether a bulk-built node holds `fill` percent of its fanout, and the next entry should
+    /// start a new node
+    pub fn is_filled_to(&self, fill: usize) -> bool {
+        self.inner.len() >= (FANOUT * fill / 100).max(1)
     }
 
     pub fn min(&self) -> &K
@@ -50,6 +56,14 @@ impl<K: Ord, V, const FANOUT: usize> BTreeNode<K, V, FANOUT> {
         }
     }
 
+    /// Whether `key` would be inserted past every entry of the node
+    pub fn is_appended(&self, key: &K) -> bool {
+        self.inner
+            .entries()
+            .last()
+            .is_none_or(|entry| entry.key < *key)
+    }
+
     pub fn split(&mut self) -> (K, Self)
     where
         K: Clone,
@@ -63,13 +77,39 @@ impl<K: Ord, V, const FANOUT: usize> BTreeNode<K, V, FANOUT> {
     }
 }
 
-impl<K: Ord, V, const FANOUT: usize> Default for BTreeNode<K, V, FANOUT> {
+impl<K: Ord, V, const FANOUT: usize, S> Default for BTreeNode<K, V, FANOUT, S> {
     fn default() -> Self {
         Self::empty()
     }
 }
 
-impl<K: StaticBounded, V, const FANOUT: usize> KeyBounded<K> for BTreeNode<K, V, FANOUT> {
+// Implemented by hand, since deriving would require the search strategy to implement them too
+
+impl<K: Ord + Clone, V: Clone, const FANOUT: usize, S> Clone for BTreeNode<K, V, FANOUT, S> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<K: Ord, V: PartialEq, const FANOUT: usize, S> PartialEq for BTreeNode<K, V, FANOUT, S> {
+    fn eq(&self, other: &Self) -> bool {
+        self.inner == other.inner
+    }
+}
+
+impl<K: Ord, V: Eq, const FANOUT: usize, S> Eq for BTreeNode<K, V, FANOUT, S> {}
+
+impl<K: Ord + Debug, V: Debug, const FANOUT: usize, S> Debug for BTreeNode<K, V, FANOUT, S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BTreeNode")
+            .field("inner", &self.inner)
+            .finish()
+    }
+}
+
+impl<K: StaticBounded, V, const FANOUT: usize, S> KeyBounded<K> for BTreeNode<K, V, FANOUT, S> {
     fn lower_bound(&self) -> &K {
         self.min()
     }
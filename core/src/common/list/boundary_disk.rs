@@ -1,7 +1,9 @@
+use crate::common::list::ErrorLatch;
 use crate::common::storage::*;
 use crate::node_layer::NodeLayer;
 use crate::traits::KeyBounded;
 use crate::traits::*;
+use crate::Error;
 
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -12,6 +14,13 @@ pub struct Link {
     prev: Option<StoreID>,
 }
 
+/// Page holding a single node of the list, along with its links
+#[derive(Default, Serialize, Deserialize, Clone, Debug)]
+pub struct BoundaryDiskListNode<N> {
+    inner: N,
+    link: Link,
+}
+
 #[derive(Default, Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
 pub enum BoundaryDiskListState {
     #[default]
@@ -19,26 +28,27 @@ pub enum BoundaryDiskListState {
     Initialized,
 }
 
+// Links are kept in the node pages, so that the catalog stays small regardless of the number of
+// nodes and flushing it is cheap
 #[derive(Default, Serialize, Deserialize, Clone, Debug)]
 pub struct BoundaryDiskListCatalogPage {
     first: StoreID,
     last: StoreID,
 
-    // Maps node to next and previous links
-    links: HashMap<StoreID, Link>,
-
     // Simple flag to mark the state of this list
     state: BoundaryDiskListState,
 }
 
 pub struct BoundaryDiskList<N: Persisted, PA> {
-    store: LocalStore<BoundaryDiskListCatalogPage, N>,
+    store: LocalStore<BoundaryDiskListCatalogPage, BoundaryDiskListNode<N>>,
 
     // We should only persist parents when we are in a deep persisted layer, in a boundary layer we
-    // keep them in transient memory
+    // keep them in transient memory. They are recorded while descending from the layer above, so
+    // opening a store from a checkpoint does not need to scan the layer to restore them. Without
+    // one, the layer above is rebuilt from a scan of this layer anyway, which records them too.
     parents: HashMap<StoreID, PA>,
 
-    _ph: std::marker::PhantomData<N>,
+    error: ErrorLatch,
 }
 
 impl<N, PA> BoundaryDiskList<N, PA>
@@ -46,29 +56,52 @@ where
     N: Persisted + Default + Eq,
 {
     pub fn load(store: &mut GlobalStore, ident: impl ToString) -> crate::Result<Self> {
-        let mut store: LocalStore<BoundaryDiskListCatalogPage, N> =
-            store.load_local_store(ident)?;
-        let parents = HashMap::new();
-
-        if store.catalog.state == BoundaryDiskListState::Uninitialized {
-            store.catalog.state = BoundaryDiskListState::Initialized;
-            let ptr = store.allocate_page();
-            store.write_page(&N::default(), ptr)?;
-            store.catalog.first = ptr;
-            store.catalog.last = ptr;
-            store.catalog.links.insert(ptr, Default::default());
+        let mut list = Self {
+            store: store.load_local_store(ident)?,
+            parents: HashMap::new(),
+            error: ErrorLatch::default(),
+        };
+
+        if list.store.catalog.state == BoundaryDiskListState::Uninitialized {
+            list.store.catalog.state = BoundaryDiskListState::Initialized;
+            list.reset()?;
         }
 
-        Ok(Self {
-            store,
-            parents,
-            _ph: std::marker::PhantomData,
-        })
+        Ok(list)
+    }
+
+    // Start over with a single empty node
+    fn reset(&mut self) -> crate::Result<StoreID> {
+        let ptr = self.store.allocate_page();
+        self.store
+            .write_page(&BoundaryDiskListNode::default(), ptr)?;
+        self.store.catalog.first = ptr;
+        self.store.catalog.last = ptr;
+
+        Ok(ptr)
+    }
+
+    pub fn flush(&self) -> crate::Result<()> {
+        self.store.flush()
+    }
+
+    pub fn sync(&self) -> crate::Result<()> {
+        self.store.sync()
+    }
+
+    /// Pages held by the list, including its catalog
+    pub fn stats(&self) -> crate::Result<LayerStats> {
+        self.store.stats()
+    }
+
+    /// Bytes held in memory by the parents recorded for the nodes of the list
+    pub fn memory_bytes(&self) -> usize {
+        self.parents.capacity() * std::mem::size_of::<(StoreID, PA)>()
     }
 
     pub fn is_empty(&self) -> crate::Result<Option<StoreID>> {
         if self.store.catalog.first == self.store.catalog.last
-            && self.get_node(self.store.catalog.first)?.unwrap() == N::default()
+            && self.read(self.store.catalog.first)?.inner == N::default()
         {
             return Ok(Some(self.store.catalog.first));
         }
@@ -76,45 +109,71 @@ where
         Ok(None)
     }
 
-    pub fn transform_node<T>(
+    fn read(&self, ptr: StoreID) -> crate::Result<BoundaryDiskListNode<N>> {
+        self.store.read_page(ptr)?.ok_or_else(|| {
+            Error::Catalog(format!(
+                "Node page {} of a boundary disk list is missing",
+                ptr
+            ))
+        })
+    }
+
+    // Apply a change to a node page and write it back
+    fn update<T>(
         &mut self,
         ptr: StoreID,
-        closure: impl Fn(&mut N) -> T,
+        closure: impl FnOnce(&mut BoundaryDiskListNode<N>) -> T,
     ) -> crate::Result<T> {
-        let mut node = self.get_node(ptr)?.unwrap();
+        let mut node = self.read(ptr)?;
         let result = closure(&mut node);
         self.store.write_page(&node, ptr)?;
 
         Ok(result)
     }
 
+    pub fn transform_node<T>(
+        &mut self,
+        ptr: StoreID,
+        closure: impl Fn(&mut N) -> T,
+    ) -> crate::Result<T> {
+        self.update(ptr, |node| closure(&mut node.inner))
+    }
+
     pub fn get_node(&self, ptr: StoreID) -> crate::Result<Option<N>> {
-        self.store.read_page(ptr)
+        Ok(self.store.read_page(ptr)?.map(|node| node.inner))
+    }
+
+    fn get_link(&self, ptr: StoreID) -> crate::Result<Link> {
+        Ok(self.read(ptr)?.link)
     }
 
-    fn get_next(&self, ptr: StoreID) -> Option<StoreID> {
-        self.store.catalog.links.get(&ptr).unwrap().next
+    /// The node after `ptr`, failing if a node page cannot be read unlike `NodeLayer::next`
+    pub fn try_next(&self, ptr: StoreID) -> crate::Result<Option<StoreID>> {
+        Ok(self.get_link(ptr)?.next)
     }
 
-    fn get_prev(&self, ptr: StoreID) -> Option<StoreID> {
-        self.store.catalog.links.get(&ptr).unwrap().prev
+    /// The node before `ptr`, failing if a node page cannot be read unlike `NodeLayer::prev`
+    pub fn try_prev(&self, ptr: StoreID) -> crate::Result<Option<StoreID>> {
+        Ok(self.get_link(ptr)?.prev)
     }
 
     pub fn insert_after(&mut self, inner: N, ptr: StoreID) -> crate::Result<StoreID> {
-        let next_ptr = self.get_next(ptr);
-        let new_link = Link {
-            next: next_ptr,
-            prev: Some(ptr),
+        let next_ptr = self.try_next(ptr)?;
+        let new_node = BoundaryDiskListNode {
+            inner,
+            link: Link {
+                next: next_ptr,
+                prev: Some(ptr),
+            },
         };
 
         let new_node_ptr = self.store.allocate_page();
 
-        self.store.write_page(&inner, new_node_ptr)?;
-        self.store.catalog.links.insert(new_node_ptr, new_link);
-        self.store.catalog.links.get_mut(&ptr).unwrap().next = Some(new_node_ptr);
+        self.store.write_page(&new_node, new_node_ptr)?;
+        self.update(ptr, |node| node.link.next = Some(new_node_ptr))?;
 
         if let Some(next_ptr) = next_ptr {
-            self.store.catalog.links.get_mut(&next_ptr).unwrap().prev = Some(new_node_ptr);
+            self.update(next_ptr, |node| node.link.prev = Some(new_node_ptr))?;
         } else {
             self.store.catalog.last = new_node_ptr;
         }
@@ -122,17 +181,17 @@ where
         Ok(new_node_ptr)
     }
 
-    #[allow(unused)]
+    #[cfg(test)]
     pub fn clear(&mut self) -> crate::Result<StoreID> {
-        self.store.clear()?;
-
-        let ptr = self.store.allocate_page();
-        self.store.write_page(&N::default(), ptr)?;
-        self.store.catalog.first = ptr;
-        self.store.catalog.last = ptr;
-        self.store.catalog.links.insert(ptr, Default::default());
+        // Page ids are shared by every store, so only the pages of this list may be freed
+        let mut next = Some(self.store.catalog.first);
+        while let Some(ptr) = next {
+            next = self.read(ptr)?.link.next;
+            self.store.free_page(ptr)?;
+        }
 
-        Ok(ptr)
+        self.parents.clear();
+        self.reset()
     }
     //
     //     #[allow(unused)]
@@ -194,15 +253,23 @@ where
     }
 
     fn lower_bound(&self, ptr: StoreID) -> K {
-        self.get_node(ptr).unwrap().unwrap().lower_bound().clone()
+        let node = self.read(ptr).map(|node| node.inner);
+        self.error
+            .unwrap_or(node, N::default())
+            .lower_bound()
+            .clone()
     }
 
     fn next(&self, ptr: StoreID) -> Option<StoreID> {
-        self.get_next(ptr)
+        self.error.unwrap_or(self.try_next(ptr), None)
     }
 
     fn prev(&self, ptr: StoreID) -> Option<StoreID> {
-        self.get_prev(ptr)
+        self.error.unwrap_or(self.try_prev(ptr), None)
+    }
+
+    fn take_error(&self) -> crate::Result<()> {
+        self.error.take()
     }
 }
 
@@ -232,10 +299,83 @@ mod tests {
         let first_ptr = list.first();
         let second_ptr = list.insert_after(2, first_ptr).unwrap();
 
-        assert_eq!(list.get_next(first_ptr), Some(second_ptr));
-        assert_eq!(list.get_prev(second_ptr), Some(first_ptr));
+        assert_eq!(list.try_next(first_ptr).unwrap(), Some(second_ptr));
+        assert_eq!(list.try_prev(second_ptr).unwrap(), Some(first_ptr));
         assert_eq!(list.last(), second_ptr);
     }
+
+    #[test]
+    fn catalog_size_is_constant() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = GlobalStore::load(&dir).unwrap();
+        let mut list: BoundaryDiskList<u32, ()> =
+            BoundaryDiskList::load(&mut store, "test").unwrap();
+
+        let size = bincode::serialized_size(&list.store.catalog).unwrap();
+
+        let mut ptr = list.first();
+        for i in 0..1000 {
+            ptr = list.insert_after(i, ptr).unwrap();
+        }
+        list.flush().unwrap();
+
+        assert_eq!(bincode::serialized_size(&list.store.catalog).unwrap(), size);
+        assert_eq!(list.get_node(list.last()).unwrap(), Some(999));
+
+        // Links survive in the node pages
+        let mut nodes = 1;
+        let mut ptr = list.first();
+        while let Some(next) = list.try_next(ptr).unwrap() {
+            assert_eq!(list.try_prev(next).unwrap(), Some(ptr));
+            ptr = next;
+            nodes += 1;
+        }
+        assert_eq!(nodes, 1001);
+    }
+
+    #[test]
+    fn clear_keeps_other_lists() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = GlobalStore::load(&dir).unwrap();
+        let mut first: BoundaryDiskList<u32, u32> =
+            BoundaryDiskList::load(&mut store, "first").unwrap();
+        let mut second: BoundaryDiskList<u32, u32> =
+            BoundaryDiskList::load(&mut store, "second").unwrap();
+
+        let ptr = first.insert_after(1, first.first()).unwrap();
+        first.set_parent(ptr, 10);
+        second.insert_after(2, second.first()).unwrap();
+
+        let ptr = first.clear().unwrap();
+        assert_eq!(first.parent(ptr), None);
+        first.insert_after(3, ptr).unwrap();
+        first.flush().unwrap();
+        second.flush().unwrap();
+
+        assert_eq!(second.get_node(second.last()).unwrap(), Some(2));
+        assert_eq!(first.get_node(first.last()).unwrap(), Some(3));
+        assert_eq!(first.stats().unwrap().pages, 3);
+        assert_eq!(second.stats().unwrap().pages, 3);
+    }
+
+    #[test]
+    fn missing_node_is_an_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = GlobalStore::load(&dir).unwrap();
+        let mut list: BoundaryDiskList<u32, ()> =
+            BoundaryDiskList::load(&mut store, "test").unwrap();
+
+        let ptr = list.insert_after(1, list.first()).unwrap();
+        list.store.free_page(ptr).unwrap();
+
+        assert!(list.try_next(ptr).is_err());
+        assert!(list.take_error().is_ok());
+
+        assert_eq!(list.next(ptr), None);
+        assert_eq!(list.prev(ptr), None);
+        assert!(matches!(list.take_error(), Err(Error::Catalog(_))));
+        assert!(list.take_error().is_ok());
+    }
     //
     //     #[test]
     //     fn linked_list_insert_before() {
@@ -1,4 +1,5 @@
 use generational_arena::Arena;
+use serde::{Deserialize, Serialize};
 
 use crate::{
     node_layer::NodeLayer,
@@ -7,13 +8,14 @@ use crate::{
 
 pub type ArenaID = generational_arena::Index;
 
+#[derive(Serialize, Deserialize)]
 pub struct MemoryList<N, PA> {
     arena: Arena<(MemoryNode<N>, Option<PA>)>,
     first: ArenaID,
     last: ArenaID,
 }
 
-#[derive(Default)]
+#[derive(Default, Serialize, Deserialize)]
 pub struct MemoryNode<N> {
     pub inner: N,
     next: Option<ArenaID>,
@@ -98,10 +100,25 @@ where
         ptr
     }
 
-    #[allow(unused)]
+    /// Iterate over the nodes in list order
+    pub fn nodes(&self) -> impl Iterator<Item = &N> + '_ {
+        let mut next = Some(self.first);
+
+        std::iter::from_fn(move || {
+            let (node, _) = &self.arena[next?];
+            next = node.next;
+            Some(&node.inner)
+        })
+    }
+
     pub fn len(&self) -> usize {
         self.arena.len()
     }
+
+    /// Bytes allocated for the nodes of the list, not counting any memory owned by the nodes
+    pub fn memory_bytes(&self) -> usize {
+        self.arena.capacity() * std::mem::size_of::<(MemoryNode<N>, Option<PA>)>()
+    }
 }
 
 // ----------------------------------------
@@ -181,7 +198,7 @@ mod tests {
         let list: MemoryList<i32, ()> = MemoryList::empty();
 
         assert_eq!(list.len(), 1);
-        assert_eq!(list[list.first], Default::default());
+        assert_eq!(list[list.first], i32::default());
         assert_eq!(list.first, list.last);
     }
 
@@ -219,7 +236,7 @@ mod tests {
         list.clear();
 
         assert_eq!(list.len(), 1);
-        assert_eq!(list[list.first], Default::default());
+        assert_eq!(list[list.first], i32::default());
         assert_eq!(list.first, list.last);
     }
 
@@ -1,3 +1,28 @@
 pub mod boundary_disk;
 pub mod deep_disk;
 pub mod memory;
+
+use crate::Error;
+use std::cell::RefCell;
+
+/// First error hit while using a disk list through `NodeLayer`, whose methods cannot fail. It is
+/// reported by `NodeLayer::take_error`.
+#[derive(Default)]
+pub struct ErrorLatch(RefCell<Option<Error>>);
+
+impl ErrorLatch {
+    /// The value of `result`, or `default` once its error is recorded, keeping the first error
+    pub fn unwrap_or<T>(&self, result: crate::Result<T>, default: T) -> T {
+        result.unwrap_or_else(|error| {
+            self.0.borrow_mut().get_or_insert(error);
+            default
+        })
+    }
+
+    pub fn take(&self) -> crate::Result<()> {
+        match self.0.borrow_mut().take() {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,220 @@
+//! Page encryption at rest.
+//!
+//! Every page is sealed with XChaCha20-Poly1305 before it reaches the inner backend, under a
+//! random nonce and with the page ID as associated data, so that pages can neither be read nor
+//! swapped for one another without the key:
+//!
+//! ```text
+//! +--------------+-------------------------------+
+//! | nonce (24 B) | ciphertext + tag (len + 16 B) |
+//! +--------------+-------------------------------+
+//! ```
+//!
+//! Since the checksum of a page is computed before encryption, a page which fails to decrypt
+//! is reported as `Error::Encryption` rather than as corruption.
+
+use super::{BackendStats, PageBackend};
+use crate::common::storage::StoreID;
+use crate::Error;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+
+const NONCE_SIZE: usize = 24;
+
+// Authentication tag appended to every ciphertext
+const TAG_SIZE: usize = 16;
+
+/// A 256-bit key used to encrypt the pages of a store
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct EncryptionKey([u8; 32]);
+
+impl EncryptionKey {
+    pub fn new(key: [u8; 32]) -> Self {
+        Self(key)
+    }
+
+    fn cipher(&self) -> XChaCha20Poly1305 {
+        XChaCha20Poly1305::new(&self.0.into())
+    }
+}
+
+// Keys must never end up in logs
+impl std::fmt::Debug for EncryptionKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("EncryptionKey(..)")
+    }
+}
+
+/// Encrypts pages on their way to `inner`, and decrypts them on their way back
+pub struct EncryptedBackend {
+    inner: Box<dyn PageBackend>,
+    cipher: XChaCha20Poly1305,
+
+    // Pages which fail to decrypt with `cipher` are retried with the keys they were written with
+    // before a rotation, until the rotation has rewritten all of them
+    previous: Vec<XChaCha20Poly1305>,
+}
+
+impl EncryptedBackend {
+    pub fn new(
+        inner: Box<dyn PageBackend>,
+        key: EncryptionKey,
+        previous: &[EncryptionKey],
+    ) -> Self {
+        Self {
+            inner,
+            cipher: key.cipher(),
+            previous: previous.iter().map(EncryptionKey::cipher).collect(),
+        }
+    }
+
+    fn seal(&self, id: StoreID, data: &[u8]) -> crate::Result<Vec<u8>> {
+        let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let payload = Payload {
+            msg: data,
+            aad: &id.to_le_bytes(),
+        };
+
+        let ciphertext = self
+            .cipher
+            .encrypt(&nonce, payload)
+            .map_err(|_| Error::Encryption(format!("Page {} could not be encrypted", id)))?;
+
+        let mut sealed = Vec::with_capacity(NONCE_SIZE + ciphertext.len());
+        sealed.extend_from_slice(&nonce);
+        sealed.extend_from_slice(&ciphertext);
+
+        Ok(sealed)
+    }
+
+    fn open(&self, id: StoreID, data: &[u8]) -> crate::Result<Vec<u8>> {
+        let failed = || {
+            Error::Encryption(format!(
+                "Page {} could not be decrypted, the key is wrong or the page is corrupted",
+                id
+            ))
+        };
+
+        if data.len() < NONCE_SIZE {
+            return Err(failed());
+        }
+
+        let (nonce, ciphertext) = data.split_at(NONCE_SIZE);
+        let aad = id.to_le_bytes();
+
+        std::iter::once(&self.cipher)
+            .chain(&self.previous)
+            .find_map(|cipher| {
+                let payload = Payload {
+                    msg: ciphertext,
+                    aad: &aad,
+                };
+                cipher.decrypt(XNonce::from_slice(nonce), payload).ok()
+            })
+            .ok_or_else(failed)
+    }
+}
+
+impl PageBackend for EncryptedBackend {
+    fn read(&self, id: StoreID) -> crate::Result<Option<Vec<u8>>> {
+        self.inner
+            .read(id)?
+            .map(|data| self.open(id, &data))
+            .transpose()
+    }
+
+    // Sizes are those of the decrypted pages, as returned by `read`
+    fn size(&self, id: StoreID) -> crate::Result<Option<u64>> {
+        let sealed = self.inner.size(id)?;
+        Ok(sealed.map(|size| size.saturating_sub((NONCE_SIZE + TAG_SIZE) as u64)))
+    }
+
+    fn write_batch(&mut self, batch: Vec<(StoreID, Option<Vec<u8>>)>) -> crate::Result<()> {
+        let batch = batch
+            .into_iter()
+            .map(|(id, data)| Ok((id, data.map(|data| self.seal(id, &data)).transpose()?)))
+            .collect::<crate::Result<Vec<_>>>()?;
+
+        self.inner.write_batch(batch)
+    }
+
+    fn sync(&mut self) -> crate::Result<()> {
+        self.inner.sync()
+    }
+
+    fn maintenance(&mut self) -> crate::Result<()> {
+        self.inner.maintenance()
+    }
+
+    fn stats(&self) -> BackendStats {
+        self.inner.stats()
+    }
+
+    fn set_key(&mut self, key: EncryptionKey, previous: &[EncryptionKey]) -> crate::Result<()> {
+        self.cipher = key.cipher();
+        self.previous = previous.iter().map(EncryptionKey::cipher).collect();
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::storage::backend::MemoryBackend;
+
+    fn encrypted(key: u8) -> EncryptedBackend {
+        let inner = Box::new(MemoryBackend::default());
+        EncryptedBackend::new(inner, EncryptionKey::new([key; 32]), &[])
+    }
+
+    #[test]
+    fn roundtrip() {
+        let mut pages = encrypted(1);
+        pages.write_batch(vec![(1, Some(vec![7; 100]))]).unwrap();
+
+        assert_eq!(pages.read(1).unwrap(), Some(vec![7; 100]));
+        assert_eq!(pages.read(2).unwrap(), None);
+
+        // The inner backend only ever sees ciphertext
+        let sealed = pages.inner.read(1).unwrap().unwrap();
+        assert_eq!(sealed.len(), NONCE_SIZE + 100 + 16);
+        assert!(!sealed.windows(8).any(|window| window == [7; 8]));
+    }
+
+    #[test]
+    fn wrong_key() {
+        let mut pages = encrypted(1);
+        pages.write_batch(vec![(1, Some(vec![7; 100]))]).unwrap();
+
+        pages.set_key(EncryptionKey::new([2; 32]), &[]).unwrap();
+        assert!(matches!(pages.read(1), Err(Error::Encryption(_))));
+    }
+
+    #[test]
+    fn swapped_pages() {
+        let mut pages = encrypted(1);
+        pages.write_batch(vec![(1, Some(vec![1; 100]))]).unwrap();
+
+        // A page moved to another ID fails authentication
+        let sealed = pages.inner.read(1).unwrap();
+        pages.inner.write_batch(vec![(2, sealed)]).unwrap();
+        assert!(matches!(pages.read(2), Err(Error::Encryption(_))));
+    }
+
+    #[test]
+    fn rotation_falls_back_to_previous_key() {
+        let (old, new) = (EncryptionKey::new([1; 32]), EncryptionKey::new([2; 32]));
+        let mut pages = encrypted(1);
+        pages.write_batch(vec![(1, Some(vec![1; 100]))]).unwrap();
+
+        pages.set_key(new, &[old]).unwrap();
+        pages.write_batch(vec![(2, Some(vec![2; 100]))]).unwrap();
+        assert_eq!(pages.read(1).unwrap(), Some(vec![1; 100]));
+        assert_eq!(pages.read(2).unwrap(), Some(vec![2; 100]));
+
+        pages.set_key(new, &[]).unwrap();
+        assert!(pages.read(1).is_err());
+        assert_eq!(pages.read(2).unwrap(), Some(vec![2; 100]));
+    }
+}
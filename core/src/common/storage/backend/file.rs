@@ -0,0 +1,359 @@
+//! A backend which keeps all pages in a single file of fixed-size blocks.
+//!
+//! Each page occupies an extent of consecutive blocks, starting with a header:
+//!
+//! ```text
+//! +-----------+------------+------------+---------------+---------+
+//! | id (u64)  | seq (u64)  | len (u32)  | blocks (u32)  | payload |
+//! +-----------+------------+------------+---------------+---------+
+//! ```
+//!
+//! Free extents carry the reserved `FREE_ID`. Overwritten pages are written to a new extent
+//! before the old one is freed, and the sequence number picks the newest copy if both survive a
+//! crash. The page index and free list are rebuilt by scanning the headers on open.
+
+use super::{BackendStats, PageBackend};
+use crate::common::storage::StoreID;
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs::File,
+    io::{Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+};
+
+const BLOCK_SIZE: u64 = 4096;
+const HEADER_SIZE: usize = 24;
+const FREE_ID: StoreID = StoreID::MAX;
+
+const PAGE_FILE: &str = "pages";
+
+#[derive(Clone, Copy)]
+struct Extent {
+    // Offset and size in blocks
+    offset: u64,
+    blocks: u64,
+    len: u32,
+}
+
+struct Header {
+    id: StoreID,
+    seq: u64,
+    len: u32,
+    blocks: u32,
+}
+
+impl Header {
+    fn free(blocks: u64) -> Self {
+        Self {
+            id: FREE_ID,
+            seq: 0,
+            len: 0,
+            blocks: blocks as u32,
+        }
+    }
+
+    fn to_bytes(&self) -> [u8; HEADER_SIZE] {
+        let mut data = [0; HEADER_SIZE];
+        data[0..8].copy_from_slice(&self.id.to_le_bytes());
+        data[8..16].copy_from_slice(&self.seq.to_le_bytes());
+        data[16..20].copy_from_slice(&self.len.to_le_bytes());
+        data[20..24].copy_from_slice(&self.blocks.to_le_bytes());
+        data
+    }
+
+    fn from_bytes(data: &[u8; HEADER_SIZE]) -> Self {
+        Self {
+            id: u64::from_le_bytes(data[0..8].try_into().unwrap()),
+            seq: u64::from_le_bytes(data[8..16].try_into().unwrap()),
+            len: u32::from_le_bytes(data[16..20].try_into().unwrap()),
+            blocks: u32::from_le_bytes(data[20..24].try_into().unwrap()),
+        }
+    }
+}
+
+pub struct FileBackend {
+    file: File,
+
+    index: HashMap<StoreID, (u64, Extent)>,
+
+    // Maps the offset of each free extent to its size in blocks
+    free: BTreeMap<u64, u64>,
+
+    // File size in blocks
+    end: u64,
+    next_seq: u64,
+}
+
+impl FileBackend {
+    fn file_path(path: impl AsRef<Path>) -> PathBuf {
+        path.as_ref().join(PAGE_FILE)
+    }
+
+    pub fn exists(path: impl AsRef<Path>) -> bool {
+        Self::file_path(path).is_file()
+    }
+
+    pub fn open(path: impl AsRef<Path>) -> crate::Result<Self> {
+        std::fs::create_dir_all(path.as_ref())?;
+
+        let file = File::options()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(Self::file_path(path))?;
+
+        Self::from_file(file)
+    }
+
+    /// Open an existing page file without write access, any write through it fails
+    pub fn open_read_only(path: impl AsRef<Path>) -> crate::Result<Self> {
+        Self::from_file(File::open(Self::file_path(path))?)
+    }
+
+    fn from_file(file: File) -> crate::Result<Self> {
+        let mut backend = Self {
+            end: file.metadata()?.len() / BLOCK_SIZE,
+            file,
+            index: HashMap::new(),
+            free: BTreeMap::new(),
+            next_seq: 0,
+        };
+
+        backend.scan()?;
+        Ok(backend)
+    }
+
+    // Rebuild the page index and free list from the extent headers
+    fn scan(&mut self) -> crate::Result<()> {
+        let mut offset = 0;
+
+        while offset < self.end {
+            let mut data = [0; HEADER_SIZE];
+            self.file.seek(SeekFrom::Start(offset * BLOCK_SIZE))?;
+            self.file.read_exact(&mut data)?;
+            let header = Header::from_bytes(&data);
+
+            let blocks = header.blocks as u64;
+            let fits = header.len as u64 + HEADER_SIZE as u64 <= blocks * BLOCK_SIZE;
+
+            // A torn header leaves the rest of the file unreadable, treat it as free space
+            if blocks == 0 || offset + blocks > self.end || !fits {
+                self.free.insert(offset, self.end - offset);
+                break;
+            }
+
+            let extent = Extent {
+                offset,
+                blocks,
+                len: header.len,
+            };
+
+            if header.id != FREE_ID {
+                self.next_seq = self.next_seq.max(header.seq + 1);
+
+                match self.index.get(&header.id) {
+                    Some(&(seq, _)) if seq > header.seq => {
+                        self.free.insert(offset, blocks);
+                    }
+                    _ => {
+                        if let Some((_, old)) = self.index.insert(header.id, (header.seq, extent)) {
+                            self.free.insert(old.offset, old.blocks);
+                        }
+                    }
+                }
+            } else {
+                self.free.insert(offset, blocks);
+            }
+
+            offset += blocks;
+        }
+
+        Ok(())
+    }
+
+    fn allocate(&mut self, blocks: u64) -> crate::Result<u64> {
+        let fit = self
+            .free
+            .iter()
+            .find(|(_, &size)| size >= blocks)
+            .map(|(&offset, &size)| (offset, size));
+
+        if let Some((offset, size)) = fit {
+            self.free.remove(&offset);
+
+            if size > blocks {
+                self.free_extent(offset + blocks, size - blocks)?;
+            }
+
+            return Ok(offset);
+        }
+
+        let offset = self.end;
+        self.end += blocks;
+        Ok(offset)
+    }
+
+    fn free_extent(&mut self, offset: u64, blocks: u64) -> crate::Result<()> {
+        self.file.seek(SeekFrom::Start(offset * BLOCK_SIZE))?;
+        self.file.write_all(&Header::free(blocks).to_bytes())?;
+        self.free.insert(offset, blocks);
+
+        Ok(())
+    }
+
+    fn write_page(&mut self, id: StoreID, data: &[u8]) -> crate::Result<()> {
+        let blocks = (HEADER_SIZE + data.len()).div_ceil(BLOCK_SIZE as usize) as u64;
+        let offset = self.allocate(blocks)?;
+
+        let header = Header {
+            id,
+            seq: self.next_seq,
+            len: data.len() as u32,
+            blocks: blocks as u32,
+        };
+        self.next_seq += 1;
+
+        let mut buffer = vec![0; (blocks * BLOCK_SIZE) as usize];
+        buffer[..HEADER_SIZE].copy_from_slice(&header.to_bytes());
+        buffer[HEADER_SIZE..HEADER_SIZE + data.len()].copy_from_slice(data);
+
+        self.file.seek(SeekFrom::Start(offset * BLOCK_SIZE))?;
+        self.file.write_all(&buffer)?;
+
+        let extent = Extent {
+            offset,
+            blocks,
+            len: header.len,
+        };
+
+        if let Some((_, old)) = self.index.insert(id, (header.seq, extent)) {
+            self.free_extent(old.offset, old.blocks)?;
+        }
+
+        Ok(())
+    }
+
+    fn delete_page(&mut self, id: StoreID) -> crate::Result<()> {
+        if let Some((_, old)) = self.index.remove(&id) {
+            self.free_extent(old.offset, old.blocks)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl PageBackend for FileBackend {
+    fn read(&self, id: StoreID) -> crate::Result<Option<Vec<u8>>> {
+        let Some(&(_, extent)) = self.index.get(&id) else {
+            return Ok(None);
+        };
+
+        let mut file = &self.file;
+        let mut data = vec![0; extent.len as usize];
+        file.seek(SeekFrom::Start(
+            extent.offset * BLOCK_SIZE + HEADER_SIZE as u64,
+        ))?;
+        file.read_exact(&mut data)?;
+
+        Ok(Some(data))
+    }
+
+    fn size(&self, id: StoreID) -> crate::Result<Option<u64>> {
+        Ok(self.index.get(&id).map(|&(_, extent)| extent.len as u64))
+    }
+
+    fn write_batch(&mut self, batch: Vec<(StoreID, Option<Vec<u8>>)>) -> crate::Result<()> {
+        for (id, data) in batch {
+            match data {
+                Some(data) => self.write_page(id, &data)?,
+                None => self.delete_page(id)?,
+            }
+        }
+
+        Ok(())
+    }
+
+    fn sync(&mut self) -> crate::Result<()> {
+        self.file.sync_all()?;
+        Ok(())
+    }
+
+    fn maintenance(&mut self) -> crate::Result<()> {
+        // Coalesce neighbouring free extents
+        let mut merged: Vec<(u64, u64)> = Vec::new();
+        for (&offset, &blocks) in self.free.iter() {
+            match merged.last_mut() {
+                Some((last, size)) if *last + *size == offset => *size += blocks,
+                _ => merged.push((offset, blocks)),
+            }
+        }
+
+        self.free.clear();
+
+        // Give trailing free space back to the file system
+        if let Some(&(offset, blocks)) = merged.last() {
+            if offset + blocks == self.end {
+                merged.pop();
+                self.end = offset;
+                self.file.set_len(self.end * BLOCK_SIZE)?;
+            }
+        }
+
+        for (offset, blocks) in merged {
+            self.free_extent(offset, blocks)?;
+        }
+
+        Ok(())
+    }
+
+    fn stats(&self) -> BackendStats {
+        BackendStats {
+            stored_pages: self.index.len() as u64,
+            total_bytes: self.end * BLOCK_SIZE,
+            live_bytes: self
+                .index
+                .values()
+                .map(|&(_, extent)| extent.blocks * BLOCK_SIZE)
+                .sum(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reuse_and_reclaim_space() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut pages = FileBackend::open(dir.path()).unwrap();
+
+        pages.write_batch(vec![(1, Some(vec![1; 10]))]).unwrap();
+        pages
+            .write_batch(vec![(2, Some(vec![2; 3 * 4096]))])
+            .unwrap();
+        pages.write_batch(vec![(3, Some(vec![3; 10]))]).unwrap();
+        assert_eq!(pages.stats().total_bytes, 6 * BLOCK_SIZE);
+
+        // The freed extent of page 2 is reused before the file grows
+        pages.delete(2).unwrap();
+        pages.write_batch(vec![(4, Some(vec![4; 10]))]).unwrap();
+        assert_eq!(pages.stats().total_bytes, 6 * BLOCK_SIZE);
+        assert_eq!(pages.stats().live_bytes, 3 * BLOCK_SIZE);
+        assert_eq!(pages.size(4).unwrap(), Some(10));
+
+        // Trailing free space is truncated by maintenance
+        pages.delete(3).unwrap();
+        pages.maintenance().unwrap();
+        assert_eq!(pages.stats().total_bytes, 2 * BLOCK_SIZE);
+        assert_eq!(pages.stats().stored_pages, 2);
+        assert_eq!(pages.stats().fragmentation(), 0.0);
+
+        drop(pages);
+        let pages = FileBackend::open(dir.path()).unwrap();
+        assert_eq!(pages.read(1).unwrap(), Some(vec![1; 10]));
+        assert_eq!(pages.read(4).unwrap(), Some(vec![4; 10]));
+        assert_eq!(pages.read(3).unwrap(), None);
+    }
+}
@@ -0,0 +1,51 @@
+use super::{BackendStats, PageBackend};
+use crate::common::storage::StoreID;
+use std::path::Path;
+
+pub struct MarbleBackend {
+    marble: marble::Marble,
+}
+
+impl MarbleBackend {
+    pub fn exists(path: impl AsRef<Path>) -> bool {
+        path.as_ref().join("heap").is_dir()
+    }
+
+    pub fn open(path: impl AsRef<Path>) -> crate::Result<Self> {
+        Ok(Self {
+            marble: marble::open(path.as_ref())?,
+        })
+    }
+}
+
+impl PageBackend for MarbleBackend {
+    fn read(&self, id: StoreID) -> crate::Result<Option<Vec<u8>>> {
+        Ok(self.marble.read(id)?.map(|data| data.to_vec()))
+    }
+
+    fn write_batch(&mut self, batch: Vec<(StoreID, Option<Vec<u8>>)>) -> crate::Result<()> {
+        self.marble.write_batch(batch)?;
+        Ok(())
+    }
+
+    fn sync(&mut self) -> crate::Result<()> {
+        self.marble.sync_all()?;
+        Ok(())
+    }
+
+    fn maintenance(&mut self) -> crate::Result<()> {
+        self.marble.maintenance()?;
+        Ok(())
+    }
+
+    fn stats(&self) -> BackendStats {
+        let stats = self.marble.stats();
+
+        BackendStats {
+            stored_pages: stats.live_objects,
+            total_bytes: stats.total_file_size,
+            // Marble only counts objects, so assume they are all of the same size
+            live_bytes: (stats.total_file_size as f64 * stats.live_ratio as f64) as u64,
+        }
+    }
+}
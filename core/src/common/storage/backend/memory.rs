@@ -0,0 +1,43 @@
+use super::{BackendStats, PageBackend};
+use crate::common::storage::StoreID;
+use std::collections::HashMap;
+
+#[derive(Default)]
+pub struct MemoryBackend {
+    pages: HashMap<StoreID, Vec<u8>>,
+}
+
+impl PageBackend for MemoryBackend {
+    fn read(&self, id: StoreID) -> crate::Result<Option<Vec<u8>>> {
+        Ok(self.pages.get(&id).cloned())
+    }
+
+    fn size(&self, id: StoreID) -> crate::Result<Option<u64>> {
+        Ok(self.pages.get(&id).map(|data| data.len() as u64))
+    }
+
+    fn write_batch(&mut self, batch: Vec<(StoreID, Option<Vec<u8>>)>) -> crate::Result<()> {
+        for (id, data) in batch {
+            match data {
+                Some(data) => self.pages.insert(id, data),
+                None => self.pages.remove(&id),
+            };
+        }
+
+        Ok(())
+    }
+
+    fn sync(&mut self) -> crate::Result<()> {
+        Ok(())
+    }
+
+    fn stats(&self) -> BackendStats {
+        let bytes = self.pages.values().map(|data| data.len() as u64).sum();
+
+        BackendStats {
+            stored_pages: self.pages.len() as u64,
+            total_bytes: bytes,
+            live_bytes: bytes,
+        }
+    }
+}
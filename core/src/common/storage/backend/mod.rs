@@ -0,0 +1,214 @@
+//! Page backends which the `GlobalStore` persists its pages to.
+//!
+//! A backend maps page IDs to opaque byte buffers. Framing, checksums and serialization are all
+//! handled above it, so backends only need to store and return the bytes they are given.
+
+use super::StoreID;
+use crate::Error;
+use std::path::Path;
+
+mod encrypted;
+mod file;
+mod marble;
+mod memory;
+
+pub use self::encrypted::{EncryptedBackend, EncryptionKey};
+pub use self::file::FileBackend;
+pub use self::marble::MarbleBackend;
+pub use self::memory::MemoryBackend;
+
+/// Storage statistics reported by a backend
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BackendStats {
+    /// Number of pages tracked by the backend. Deleted pages may still be counted until their
+    /// space is reclaimed.
+    pub stored_pages: u64,
+
+    /// Bytes occupied by the backend, including space which has not been reclaimed yet
+    pub total_bytes: u64,
+
+    /// Bytes occupied by live pages, estimated by backends which do not track it exactly
+    pub live_bytes: u64,
+}
+
+impl BackendStats {
+    /// Fraction of the occupied bytes which are not held by live pages, between 0 and 1
+    pub fn fragmentation(&self) -> f64 {
+        if self.total_bytes == 0 {
+            return 0.0;
+        }
+
+        1.0 - self.live_bytes.min(self.total_bytes) as f64 / self.total_bytes as f64
+    }
+}
+
+pub trait PageBackend {
+    fn read(&self, id: StoreID) -> crate::Result<Option<Vec<u8>>>;
+
+    /// Size in bytes of a page, without necessarily reading it
+    fn size(&self, id: StoreID) -> crate::Result<Option<u64>> {
+        Ok(self.read(id)?.map(|data| data.len() as u64))
+    }
+
+    /// Write or delete (when `None`) a batch of pages
+    fn write_batch(&mut self, batch: Vec<(StoreID, Option<Vec<u8>>)>) -> crate::Result<()>;
+
+    fn delete(&mut self, id: StoreID) -> crate::Result<()> {
+        self.write_batch(vec![(id, None)])
+    }
+
+    /// Force all written pages to durable storage
+    fn sync(&mut self) -> crate::Result<()>;
+
+    /// Reclaim space left behind by overwritten and deleted pages
+    fn maintenance(&mut self) -> crate::Result<()> {
+        Ok(())
+    }
+
+    fn stats(&self) -> BackendStats;
+
+    /// Encrypt pages written from now on with `key`, falling back to the `previous` keys for
+    /// pages which cannot be decrypted with it. Only supported by encrypting backends.
+    fn set_key(&mut self, _key: EncryptionKey, _previous: &[EncryptionKey]) -> crate::Result<()> {
+        Err(Error::Encryption("Store is not encrypted".to_string()))
+    }
+}
+
+/// Selects the `PageBackend` a store is opened with
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Backend {
+    /// Log-structured object store provided by `marble`
+    #[default]
+    Marble,
+
+    /// Pages are kept in memory and dropped with the store, mostly useful for tests
+    Memory,
+
+    /// All pages are kept in a single file of fixed-size blocks
+    File,
+}
+
+impl Backend {
+    /// Whether a store with this backend exists at `path`
+    pub fn exists(&self, path: impl AsRef<Path>) -> bool {
+        match self {
+            Backend::Marble => MarbleBackend::exists(path),
+            Backend::Memory => false,
+            Backend::File => FileBackend::exists(path),
+        }
+    }
+
+    pub fn open(&self, path: impl AsRef<Path>) -> crate::Result<Box<dyn PageBackend>> {
+        Ok(match self {
+            Backend::Marble => Box::new(MarbleBackend::open(path)?),
+            Backend::Memory => Box::new(MemoryBackend::default()),
+            Backend::File => Box::new(FileBackend::open(path)?),
+        })
+    }
+
+    /// Whether any number of readers may open a store with this backend at once. Marble
+    /// recovers and exclusively locks its directory on open, so it serves a single reader.
+    pub fn shares_readers(&self) -> bool {
+        match self {
+            Backend::Marble => false,
+            Backend::Memory | Backend::File => true,
+        }
+    }
+
+    /// Open an existing store without writing to it. Marble has no read-only mode and fails
+    /// with `Error::Unsupported`, so its readers open it with `open` under an exclusive lock.
+    pub fn open_read_only(&self, path: impl AsRef<Path>) -> crate::Result<Box<dyn PageBackend>> {
+        Ok(match self {
+            Backend::Marble => {
+                return Err(Error::Unsupported(format!(
+                    "Store at `{}` uses the marble backend, which cannot be opened read-only",
+                    path.as_ref().display()
+                )))
+            }
+            Backend::Memory => self.open(path)?,
+            Backend::File => Box::new(FileBackend::open_read_only(path)?),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn roundtrip(backend: Backend) {
+        let dir = tempfile::tempdir().unwrap();
+        let mut pages = backend.open(dir.path()).unwrap();
+
+        pages
+            .write_batch(vec![(1, Some(vec![1; 10])), (2, Some(vec![2; 10_000]))])
+            .unwrap();
+        assert_eq!(pages.read(1).unwrap(), Some(vec![1; 10]));
+        assert_eq!(pages.read(2).unwrap(), Some(vec![2; 10_000]));
+        assert_eq!(pages.read(3).unwrap(), None);
+
+        // Overwrite with a page of a different size, then delete
+        pages.write_batch(vec![(1, Some(vec![3; 5_000]))]).unwrap();
+        pages.delete(2).unwrap();
+        assert_eq!(pages.read(1).unwrap(), Some(vec![3; 5_000]));
+        assert_eq!(pages.read(2).unwrap(), None);
+
+        pages.maintenance().unwrap();
+        pages.sync().unwrap();
+        assert!(pages.stats().stored_pages >= 1);
+        assert_eq!(pages.read(1).unwrap(), Some(vec![3; 5_000]));
+    }
+
+    fn reopen(backend: Backend) {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(!backend.exists(dir.path()));
+
+        {
+            let mut pages = backend.open(dir.path()).unwrap();
+            pages.write_batch(vec![(1, Some(vec![1; 100]))]).unwrap();
+            pages.write_batch(vec![(1, Some(vec![2; 200]))]).unwrap();
+            pages.write_batch(vec![(2, Some(vec![3; 300]))]).unwrap();
+            pages.delete(2).unwrap();
+            pages.sync().unwrap();
+        }
+
+        assert!(backend.exists(dir.path()));
+        let pages = backend.open(dir.path()).unwrap();
+        assert_eq!(pages.read(1).unwrap(), Some(vec![2; 200]));
+        assert_eq!(pages.read(2).unwrap(), None);
+    }
+
+    #[test]
+    fn marble_backend() {
+        roundtrip(Backend::Marble);
+        reopen(Backend::Marble);
+    }
+
+    #[test]
+    fn memory_backend() {
+        roundtrip(Backend::Memory);
+    }
+
+    #[test]
+    fn file_backend() {
+        roundtrip(Backend::File);
+        reopen(Backend::File);
+    }
+
+    #[test]
+    fn file_backend_read_only() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(Backend::File.open_read_only(dir.path()).is_err());
+
+        {
+            let mut pages = Backend::File.open(dir.path()).unwrap();
+            pages.write_batch(vec![(1, Some(vec![1; 100]))]).unwrap();
+            pages.sync().unwrap();
+        }
+
+        let mut first = Backend::File.open_read_only(dir.path()).unwrap();
+        let second = Backend::File.open_read_only(dir.path()).unwrap();
+        assert_eq!(first.read(1).unwrap(), Some(vec![1; 100]));
+        assert_eq!(second.read(1).unwrap(), Some(vec![1; 100]));
+        assert!(first.write_batch(vec![(2, Some(vec![2; 100]))]).is_err());
+    }
+}
@@ -0,0 +1,211 @@
+//! Checkpoints of the in-memory layers of a store.
+//!
+//! In-memory layers are rebuilt from the topmost persisted layer on open, which reads every one
+//! of its pages. To avoid this, `close` writes the in-memory layers to a checkpoint file next to
+//! the pages, tagged with the generation of the session and the layout they belong to. The next
+//! open reuses them only if no other session touched the store in between and the layout is
+//! unchanged, and falls back to rebuilding them otherwise. Encrypted stores are never
+//! checkpointed, since the checkpoint would hold their keys in plaintext.
+
+use super::{page, GlobalStore, Manifest};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{ErrorKind, Write};
+
+const CHECKPOINT_FILE: &str = "checkpoint";
+
+const CHECKPOINT_TEMP_FILE: &str = "checkpoint.tmp";
+
+const CHECKPOINT_IDENT: &str = "checkpoint";
+
+#[derive(Serialize)]
+struct CheckpointRef<'a, T> {
+    generation: u64,
+    manifest: &'a Manifest,
+    layers: &'a T,
+}
+
+#[derive(Deserialize)]
+struct Checkpoint<T> {
+    generation: u64,
+    manifest: Manifest,
+    layers: T,
+}
+
+impl GlobalStore {
+    /// Write a checkpoint of the in-memory `layers` of a store with the layout `manifest`. The
+    /// store must be synced beforehand, since the checkpoint claims the pages are durable.
+    pub fn write_checkpoint<T: Serialize>(
+        &self,
+        manifest: &Manifest,
+        layers: &T,
+    ) -> crate::Result<()> {
+        let Some(path) = self
+            .path()
+            .filter(|_| !self.is_read_only() && !self.is_encrypted())
+        else {
+            return Ok(());
+        };
+
+        let data = page::encode(&CheckpointRef {
+            generation: self.generation(),
+            manifest,
+            layers,
+        })?;
+
+        // Replace the previous checkpoint atomically, so that a crash never leaves a torn one
+        let temp = path.join(CHECKPOINT_TEMP_FILE);
+        let mut file = File::create(&temp)?;
+        file.write_all(&data)?;
+        file.sync_all()?;
+        fs::rename(temp, path.join(CHECKPOINT_FILE))?;
+
+        Ok(())
+    }
+
+    /// Read the in-memory layers checkpointed at the end of the previous session, or `None` if
+    /// there is no checkpoint that can be trusted
+    pub fn read_checkpoint<T: DeserializeOwned>(
+        &self,
+        manifest: &Manifest,
+    ) -> crate::Result<Option<T>> {
+        let Some(path) = self.path().filter(|_| !self.is_encrypted()) else {
+            return Ok(None);
+        };
+
+        let data = match fs::read(path.join(CHECKPOINT_FILE)) {
+            Ok(data) => data,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+
+        // The checkpoint only caches what can be rebuilt, so a damaged one is not an error
+        let Ok(checkpoint) = page::decode::<Checkpoint<T>>(&data, CHECKPOINT_IDENT, 0) else {
+            return Ok(None);
+        };
+
+        // Any session since the last writer could have modified the persisted layers. Read-only
+        // sessions do not start a generation of their own.
+        let previous = match self.is_read_only() {
+            true => self.generation(),
+            false => self.generation() - 1,
+        };
+
+        if checkpoint.generation != previous || checkpoint.manifest != *manifest {
+            return Ok(None);
+        }
+
+        Ok(Some(checkpoint.layers))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::storage::{Backend, ManifestComponent};
+
+    fn test_manifest(fanout: usize) -> Manifest {
+        Manifest::new::<u64, u64>(vec![ManifestComponent::new(
+            format!("btree(fanout = {fanout})"),
+            false,
+        )])
+    }
+
+    #[test]
+    fn reuse_after_reopen() {
+        let dir = tempfile::tempdir().unwrap();
+        let layers = vec![(1u64, 2u64), (3, 4)];
+
+        let store = GlobalStore::load(dir.path()).unwrap();
+        store.write_checkpoint(&test_manifest(4), &layers).unwrap();
+        store.close().unwrap();
+
+        let store = GlobalStore::load(dir.path()).unwrap();
+        let read: Option<Vec<(u64, u64)>> = store.read_checkpoint(&test_manifest(4)).unwrap();
+        assert_eq!(read, Some(layers));
+    }
+
+    #[test]
+    fn stale_after_session() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let store = GlobalStore::load(dir.path()).unwrap();
+        store.write_checkpoint(&test_manifest(4), &5u64).unwrap();
+        store.close().unwrap();
+
+        // A session without a checkpoint could have modified the pages
+        GlobalStore::load(dir.path()).unwrap().close().unwrap();
+
+        let store = GlobalStore::load(dir.path()).unwrap();
+        assert_eq!(
+            store.read_checkpoint::<u64>(&test_manifest(4)).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn stale_after_layout_change() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let store = GlobalStore::load(dir.path()).unwrap();
+        store.write_checkpoint(&test_manifest(4), &5u64).unwrap();
+        store.close().unwrap();
+
+        let store = GlobalStore::load(dir.path()).unwrap();
+        assert_eq!(
+            store.read_checkpoint::<u64>(&test_manifest(8)).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn corrupted_checkpoint_is_ignored() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let store = GlobalStore::load(dir.path()).unwrap();
+        store.write_checkpoint(&test_manifest(4), &5u64).unwrap();
+        store.close().unwrap();
+
+        let path = dir.path().join(CHECKPOINT_FILE);
+        let mut data = fs::read(&path).unwrap();
+        let last = data.len() - 1;
+        data[last] ^= 0xff;
+        fs::write(&path, data).unwrap();
+
+        let store = GlobalStore::load(dir.path()).unwrap();
+        assert_eq!(
+            store.read_checkpoint::<u64>(&test_manifest(4)).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn reuse_read_only() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let store = GlobalStore::load_with(dir.path(), Backend::File).unwrap();
+        store.write_checkpoint(&test_manifest(4), &5u64).unwrap();
+        store.close().unwrap();
+
+        for _ in 0..2 {
+            let store = GlobalStore::load_read_only(dir.path(), Backend::File).unwrap();
+            let read = store.read_checkpoint::<u64>(&test_manifest(4)).unwrap();
+            assert_eq!(read, Some(5));
+            store.close().unwrap();
+        }
+    }
+
+    #[test]
+    fn memory_backend_has_no_checkpoint() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let store = GlobalStore::load_with(dir.path(), Backend::Memory).unwrap();
+        store.write_checkpoint(&test_manifest(4), &5u64).unwrap();
+        assert!(!dir.path().join(CHECKPOINT_FILE).exists());
+        assert_eq!(
+            store.read_checkpoint::<u64>(&test_manifest(4)).unwrap(),
+            None
+        );
+    }
+}
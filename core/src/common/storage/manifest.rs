@@ -0,0 +1,155 @@
+//! Description of the layout a persisted store was created with.
+//!
+//! The manifest is kept in the global catalog, so that opening an existing store with a different
+//! layout, key or value type fails instead of silently misinterpreting the pages on disk.
+
+use super::page::PAGE_FORMAT_VERSION;
+use crate::Error;
+use serde::{Deserialize, Serialize};
+
+/// A single layer of a layout, described the same way as in `create_kv_store!`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ManifestComponent {
+    pub description: String,
+    pub persisted: bool,
+}
+
+impl ManifestComponent {
+    pub fn new(description: impl ToString, persisted: bool) -> Self {
+        Self {
+            description: description.to_string(),
+            persisted,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Manifest {
+    pub format_version: u8,
+
+    // Components from the top layer down to the base layer
+    pub layout: Vec<ManifestComponent>,
+
+    pub key_type: String,
+    pub value_type: String,
+}
+
+impl Manifest {
+    pub fn new<K, V>(layout: Vec<ManifestComponent>) -> Self {
+        Self {
+            format_version: PAGE_FORMAT_VERSION,
+            layout,
+            key_type: std::any::type_name::<K>().to_string(),
+            value_type: std::any::type_name::<V>().to_string(),
+        }
+    }
+
+    /// Check that a store created with this manifest can be opened as `requested`. Only the
+    /// persisted layers have to match, in-memory layers are rebuilt on every open.
+    pub fn check(&self, requested: &Manifest) -> crate::Result<()> {
+        let mut differences = Vec::new();
+
+        if self.format_version != requested.format_version {
+            differences.push(format!(
+                "format version: store has {}, expected {}",
+                self.format_version, requested.format_version
+            ));
+        }
+
+        if self.persisted_layers() != requested.persisted_layers() {
+            differences.push(format!(
+                "persisted layers: store has [{}], expected [{}]",
+                self.persisted_layers().join(", "),
+                requested.persisted_layers().join(", ")
+            ));
+        }
+
+        if self.key_type != requested.key_type {
+            differences.push(format!(
+                "key type: store has `{}`, expected `{}`",
+                self.key_type, requested.key_type
+            ));
+        }
+
+        if self.value_type != requested.value_type {
+            differences.push(format!(
+                "value type: store has `{}`, expected `{}`",
+                self.value_type, requested.value_type
+            ));
+        }
+
+        if differences.is_empty() {
+            return Ok(());
+        }
+
+        Err(Error::Layout(format!(
+            "Store was created with a different layout:\n  {}",
+            differences.join("\n  ")
+        )))
+    }
+
+    fn persisted_layers(&self) -> Vec<&str> {
+        self.layout
+            .iter()
+            .filter(|component| component.persisted)
+            .map(|component| component.description.as_str())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn layout(base: &str) -> Vec<ManifestComponent> {
+        vec![
+            ManifestComponent::new("btree_top()", false),
+            ManifestComponent::new("btree(fanout = 8)", false),
+            ManifestComponent::new(base, true),
+        ]
+    }
+
+    #[test]
+    fn matching_manifest() {
+        let manifest = Manifest::new::<u64, u64>(layout("btree(fanout = 64, persist)"));
+        manifest.check(&manifest.clone()).unwrap();
+    }
+
+    #[test]
+    fn in_memory_layers_may_change() {
+        let manifest = Manifest::new::<u64, u64>(layout("btree(fanout = 64, persist)"));
+
+        let mut requested = manifest.clone();
+        requested.layout[1] = ManifestComponent::new("btree(fanout = 32)", false);
+
+        manifest.check(&requested).unwrap();
+    }
+
+    #[test]
+    fn persisted_layer_mismatch() {
+        let manifest = Manifest::new::<u64, u64>(layout("btree(fanout = 64, persist)"));
+        let requested = Manifest::new::<u64, u64>(layout("btree(fanout = 32, persist)"));
+
+        match manifest.check(&requested) {
+            Err(Error::Layout(message)) => {
+                assert!(message.contains("btree(fanout = 64, persist)"));
+                assert!(message.contains("btree(fanout = 32, persist)"));
+            }
+            result => panic!("Expected a layout error, got {result:?}"),
+        }
+    }
+
+    #[test]
+    fn type_mismatch() {
+        let manifest = Manifest::new::<u64, u64>(layout("btree(fanout = 64, persist)"));
+        let requested = Manifest::new::<u64, i32>(layout("btree(fanout = 64, persist)"));
+
+        match manifest.check(&requested) {
+            Err(Error::Layout(message)) => {
+                assert!(message.contains("value type"));
+                assert!(!message.contains("key type"));
+            }
+            result => panic!("Expected a layout error, got {result:?}"),
+        }
+    }
+}
@@ -1,5 +1,16 @@
+mod backend;
+mod checkpoint;
+mod manifest;
+mod options;
+mod page;
+mod stats;
 mod store;
 
+pub use backend::{Backend, BackendStats, EncryptionKey, PageBackend};
+pub use manifest::{Manifest, ManifestComponent};
+pub use options::{CompactionPolicy, OpenOptions};
+pub use stats::{LayerStats, StoreStats};
+
 pub use store::GlobalStore;
 pub use store::LocalStore;
 pub use store::ObjectStoreGeneric;
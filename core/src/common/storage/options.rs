@@ -0,0 +1,84 @@
+use super::{Backend, EncryptionKey};
+
+/// Controls when a persisted store reclaims the space left behind by overwritten and deleted
+/// pages, besides explicit calls to `compact`
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum CompactionPolicy {
+    /// Compact only when the store is closed
+    #[default]
+    OnClose,
+
+    /// Compact whenever the store is synced or closed while the fragmentation of its backend, as
+    /// reported by `BackendStats::fragmentation`, exceeds the given fraction
+    Threshold(f64),
+
+    /// Never compact implicitly, which keeps `close` from rewriting the store
+    Manual,
+}
+
+/// Options controlling how a persisted store is opened, mirroring `std::fs::OpenOptions`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OpenOptions {
+    pub(crate) backend: Backend,
+    pub(crate) create: bool,
+    pub(crate) create_new: bool,
+    pub(crate) read_only: bool,
+    pub(crate) compaction: CompactionPolicy,
+    pub(crate) encryption: Option<EncryptionKey>,
+    pub(crate) previous_encryption: Option<EncryptionKey>,
+}
+
+impl OpenOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Select the page backend, `Backend::Marble` by default
+    pub fn backend(mut self, backend: Backend) -> Self {
+        self.backend = backend;
+        self
+    }
+
+    /// Create the store if it does not exist yet
+    pub fn create(mut self, create: bool) -> Self {
+        self.create = create;
+        self
+    }
+
+    /// Create the store, failing if it already exists
+    pub fn create_new(mut self, create_new: bool) -> Self {
+        self.create_new = create_new;
+        self
+    }
+
+    /// Open the store without ever writing to it, sharing it with other read-only handles.
+    /// Cannot be combined with `create` or `create_new`. Stores with the marble backend admit a
+    /// single read-only handle at a time.
+    pub fn read_only(mut self, read_only: bool) -> Self {
+        self.read_only = read_only;
+        self
+    }
+
+    /// Select when the store reclaims dead space, `CompactionPolicy::OnClose` by default
+    pub fn compaction(mut self, compaction: CompactionPolicy) -> Self {
+        self.compaction = compaction;
+        self
+    }
+
+    /// Encrypt all pages of the store with `key`. A store has to be opened with the key it was
+    /// created with, opening it without one fails as if its pages were corrupted. Checkpoints
+    /// are not written and memory-mapped layers are not supported, since both would hold
+    /// plaintext on disk.
+    pub fn encryption(mut self, key: EncryptionKey) -> Self {
+        self.encryption = Some(key);
+        self
+    }
+
+    /// Key the store was encrypted with before an interrupted key rotation. Pages which cannot
+    /// be decrypted with the key given to `encryption` are retried with this one, until the
+    /// rotation is completed by rotating to the new key again.
+    pub fn previous_encryption(mut self, key: EncryptionKey) -> Self {
+        self.previous_encryption = Some(key);
+        self
+    }
+}
@@ -0,0 +1,119 @@
+//! Framing for pages written through the object store.
+//!
+//! Every page is prefixed with a small header holding a CRC32 checksum and the page format
+//! version, so that torn or bit-flipped pages are caught on read instead of being deserialized
+//! into wrong data.
+//!
+//! ```text
+//! +----------------+-----------+---------------------+
+//! | checksum (u32) | version   | payload (bincode)   |
+//! +----------------+-----------+---------------------+
+//! ```
+//!
+//! The checksum covers everything after itself, including the version byte.
+
+use super::StoreID;
+use crate::Error;
+use serde::{Deserialize, Serialize};
+
+/// Version of the page format, bumped whenever the framing or serialization changes
+pub const PAGE_FORMAT_VERSION: u8 = 2;
+
+const CHECKSUM_SIZE: usize = std::mem::size_of::<u32>();
+const HEADER_SIZE: usize = CHECKSUM_SIZE + 1;
+
+/// Serialize a page and prefix it with the page header
+pub fn encode<P>(page: &P) -> crate::Result<Vec<u8>>
+where
+    P: Serialize + ?Sized,
+{
+    let payload_size = bincode::serialized_size(page)? as usize;
+
+    let mut data = vec![0; HEADER_SIZE];
+    data.reserve(payload_size);
+    data[CHECKSUM_SIZE] = PAGE_FORMAT_VERSION;
+    bincode::serialize_into(&mut data, page)?;
+
+    let checksum = crc32fast::hash(&data[CHECKSUM_SIZE..]);
+    data[..CHECKSUM_SIZE].copy_from_slice(&checksum.to_le_bytes());
+
+    Ok(data)
+}
+
+/// Verify the page header and deserialize the payload
+pub fn decode<P>(data: &[u8], store: &str, page: StoreID) -> crate::Result<P>
+where
+    for<'de> P: Deserialize<'de>,
+{
+    let corruption = || Error::Corruption {
+        store: store.to_string(),
+        page,
+    };
+
+    if data.len() < HEADER_SIZE {
+        return Err(corruption());
+    }
+
+    let (checksum, data) = data.split_at(CHECKSUM_SIZE);
+    if u32::from_le_bytes(checksum.try_into().unwrap()) != crc32fast::hash(data) {
+        return Err(corruption());
+    }
+
+    let (version, payload) = (data[0], &data[1..]);
+    if version != PAGE_FORMAT_VERSION {
+        return Err(Error::UnsupportedVersion {
+            store: store.to_string(),
+            page,
+            version,
+        });
+    }
+
+    Ok(bincode::deserialize(payload)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encode_decode_roundtrip() {
+        let page = vec![1u64, 2, 3, 5, 8];
+        let data = encode(&page).unwrap();
+
+        let decoded: Vec<u64> = decode(&data, "test", 1).unwrap();
+        assert_eq!(decoded, page);
+    }
+
+    #[test]
+    fn detect_bit_flip() {
+        let mut data = encode(&"Some page contents").unwrap();
+        let last = data.len() - 1;
+        data[last] ^= 0b100;
+
+        let error = decode::<String>(&data, "test", 7).unwrap_err();
+        assert!(
+            matches!(error, Error::Corruption { ref store, page: 7 } if store == "test"),
+            "Expected a corruption error, got {error:?}"
+        );
+    }
+
+    #[test]
+    fn detect_truncated_page() {
+        let error = decode::<String>(&[1, 2], "test", 3).unwrap_err();
+        assert!(matches!(error, Error::Corruption { page: 3, .. }));
+    }
+
+    #[test]
+    fn reject_unknown_version() {
+        let mut data = encode(&42u32).unwrap();
+        data[CHECKSUM_SIZE] = PAGE_FORMAT_VERSION + 1;
+        let checksum = crc32fast::hash(&data[CHECKSUM_SIZE..]);
+        data[..CHECKSUM_SIZE].copy_from_slice(&checksum.to_le_bytes());
+
+        let error = decode::<u32>(&data, "test", 3).unwrap_err();
+        assert!(matches!(
+            error,
+            Error::UnsupportedVersion { page: 3, version, .. } if version == PAGE_FORMAT_VERSION + 1
+        ));
+    }
+}
@@ -0,0 +1,39 @@
+use super::BackendStats;
+
+/// Storage statistics of a single persisted layer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LayerStats {
+    /// Identifier of the local store holding the layer
+    pub ident: String,
+
+    /// Number of pages of the layer, including its catalog
+    pub pages: u64,
+
+    /// Bytes held by the pages of the layer, as encoded before any encryption
+    pub bytes: u64,
+}
+
+/// Storage statistics of a persisted store
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoreStats {
+    /// Number of allocated pages, including catalogs
+    pub live_pages: u64,
+
+    /// Number of freed page IDs waiting to be reused
+    pub freed_pages: u64,
+
+    /// Statistics reported by the page backend
+    pub backend: BackendStats,
+
+    /// Statistics of every persisted layer, from the base up. Only filled in by stores which
+    /// know their layout.
+    pub layers: Vec<LayerStats>,
+}
+
+impl StoreStats {
+    /// Fraction of the bytes occupied by the backend which are not held by live pages, and can
+    /// be reclaimed with `compact`
+    pub fn fragmentation(&self) -> f64 {
+        self.backend.fragmentation()
+    }
+}
@@ -1,11 +1,17 @@
-use super::StoreID;
-use core::panic;
+use super::backend::EncryptedBackend;
+use super::page;
+use super::{
+    Backend, CompactionPolicy, EncryptionKey, LayerStats, Manifest, OpenOptions, PageBackend,
+    StoreID, StoreStats,
+};
+use crate::Error;
 use id_allocator::IDAllocator;
 use serde::{Deserialize, Serialize};
 use std::{
     cell::{Ref, RefCell, RefMut},
     collections::{HashMap, HashSet},
-    path::Path,
+    fs::{File, TryLockError},
+    path::{Path, PathBuf},
     rc::Rc,
 };
 
@@ -13,12 +19,35 @@ use std::{
 struct GlobalStoreCatalog {
     ids: IDAllocator<StoreID>,
     registry: HashMap<String, StoreID>,
+    manifest: Option<Manifest>,
+
+    // Pages held by every local store, by the id of its catalog page
+    usage: HashMap<StoreID, PageUsage>,
+
+    // Incremented every time the store is loaded, so that files derived from the pages can tell
+    // whether the store was modified since they were last written
+    generation: u64,
+}
+
+// Pages and bytes held by a local store, counted as they are written and freed so that measuring
+// a store does not have to read it
+#[derive(Serialize, Deserialize, Clone, Copy, Default)]
+struct PageUsage {
+    pages: u64,
+    bytes: u64,
 }
 
 const CACHE_SIZE: usize = 4096 * 4096;
 
 const GLOBAL_STORE_CATALOG_ID: StoreID = 0;
 
+const GLOBAL_STORE_IDENT: &str = "global";
+
+const LOCK_FILE: &str = "lock";
+
+// Number of pages read before they are written back in one batch when rewriting a whole store
+const BATCH_SIZE: usize = 1024;
+
 impl Default for GlobalStoreCatalog {
     fn default() -> Self {
         let mut ids = IDAllocator::default();
@@ -29,62 +58,286 @@ impl Default for GlobalStoreCatalog {
         Self {
             ids,
             registry: Default::default(),
+            manifest: None,
+            usage: Default::default(),
+            generation: initial_generation(),
         }
     }
 }
 
+/// Start counting generations from the clock, so that derived files left behind by an earlier
+/// store at the same path can never match the generation of a new one
+fn initial_generation() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |time| time.as_nanos() as u64)
+}
+
+/// Lock the store at `path`, shared between readers or exclusive to a single writer. The lock is
+/// released when the returned file is dropped.
+fn lock(path: &Path, exclusive: bool) -> crate::Result<File> {
+    let file = File::options()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(path.join(LOCK_FILE))?;
+
+    let locked = if exclusive {
+        file.try_lock()
+    } else {
+        file.try_lock_shared()
+    };
+
+    match locked {
+        Ok(()) => Ok(file),
+        Err(TryLockError::WouldBlock) if exclusive => Err(Error::Locked(format!(
+            "Store at `{}` is already open",
+            path.display()
+        ))),
+        Err(TryLockError::WouldBlock) => Err(Error::Locked(format!(
+            "Store at `{}` is open for writing",
+            path.display()
+        ))),
+        Err(TryLockError::Error(err)) => Err(err.into()),
+    }
+}
+
+/// Open the pages of the store at `path`, encrypting them if `options` hold a key
+fn open_backend(
+    path: &Path,
+    options: &OpenOptions,
+    read_only: bool,
+) -> crate::Result<Box<dyn PageBackend>> {
+    let backend = match read_only {
+        true => options.backend.open_read_only(path)?,
+        false => options.backend.open(path)?,
+    };
+
+    Ok(match options.encryption {
+        Some(key) => Box::new(EncryptedBackend::new(
+            backend,
+            key,
+            options.previous_encryption.as_slice(),
+        )),
+        None => backend,
+    })
+}
+
 pub struct GlobalStore {
     inner: Rc<RefCell<GlobalStoreInner>>,
+
+    // Set once the store has been shut down through `close`
+    closed: bool,
 }
 
 struct GlobalStoreInner {
-    store: marble::Marble,
+    backend: Box<dyn PageBackend>,
+    kind: Backend,
+    path: Option<PathBuf>,
     active_stores: HashSet<String>,
     catalog: GlobalStoreCatalog,
+    read_only: bool,
+    compaction: CompactionPolicy,
+    encryption: Option<EncryptionKey>,
+    previous_encryption: Option<EncryptionKey>,
+
+    // Held for as long as the store is loaded, `None` for the memory backend
+    _lock: Option<File>,
 }
 
 impl GlobalStore {
     pub fn load(path: impl AsRef<Path>) -> crate::Result<Self> {
-        let store = marble::open(path.as_ref())?;
+        Self::load_with(path, Backend::default())
+    }
+
+    pub fn load_with(path: impl AsRef<Path>, backend: Backend) -> crate::Result<Self> {
+        Self::load_writer(path.as_ref(), &OpenOptions::new().backend(backend))
+    }
+
+    fn load_writer(path: &Path, options: &OpenOptions) -> crate::Result<Self> {
+        let backend = options.backend;
+
+        // Pages of the memory backend do not live on disk, so neither can anything derived
+        // from them
+        let file_path = (backend != Backend::Memory).then(|| path.to_path_buf());
+
+        let lock = match &file_path {
+            Some(path) => {
+                std::fs::create_dir_all(path)?;
+                Some(lock(path, true)?)
+            }
+            None => None,
+        };
+
+        let kind = backend;
+        let mut backend = open_backend(path, options, false)?;
 
         // Load catalog
-        let catalog = match store.read(GLOBAL_STORE_CATALOG_ID)? {
-            Some(data) => bincode::deserialize(&data)?,
-            None => {
-                let catalog = GlobalStoreCatalog::default();
-                let data = bincode::serialize(&catalog).unwrap();
+        let mut catalog = match backend.read(GLOBAL_STORE_CATALOG_ID)? {
+            Some(data) => page::decode(&data, GLOBAL_STORE_IDENT, GLOBAL_STORE_CATALOG_ID)?,
+            None => GlobalStoreCatalog::default(),
+        };
+
+        // The new generation has to be durable before any page is written under it
+        catalog.generation += 1;
+        let data = page::encode(&catalog)?;
+        backend.write_batch(vec![(GLOBAL_STORE_CATALOG_ID, Some(data))])?;
+        backend.sync()?;
+
+        Ok(GlobalStore {
+            inner: Rc::new(RefCell::new(GlobalStoreInner {
+                backend,
+                kind,
+                path: file_path,
+                catalog,
+                active_stores: HashSet::new(),
+                read_only: false,
+                compaction: options.compaction,
+                encryption: options.encryption,
+                previous_encryption: options.previous_encryption,
+                _lock: lock,
+            })),
+            closed: false,
+        })
+    }
 
-                store.write_batch([(GLOBAL_STORE_CATALOG_ID, Some(&data))])?;
-                catalog
+    /// Load an existing store without ever writing to it. Any number of read-only handles may
+    /// share a store, but not with a writer. Stores with a backend which does not share its
+    /// readers, such as marble, admit a single read-only handle at a time.
+    pub fn load_read_only(path: impl AsRef<Path>, backend: Backend) -> crate::Result<Self> {
+        Self::load_reader(path.as_ref(), &OpenOptions::new().backend(backend), false)
+    }
+
+    // Readers share the store unless `exclusive` is set or the backend cannot share it, which
+    // also allows opening backends without a read-only mode. Pages are never written either way.
+    fn load_reader(path: &Path, options: &OpenOptions, exclusive: bool) -> crate::Result<Self> {
+        let backend = options.backend;
+        let exclusive = exclusive || !backend.shares_readers();
+
+        let file_path = (backend != Backend::Memory).then(|| path.to_path_buf());
+        let lock = file_path
+            .as_deref()
+            .map(|path| lock(path, exclusive))
+            .transpose()?;
+
+        let kind = backend;
+        let backend = open_backend(path, options, !exclusive)?;
+
+        // Stays at the generation of the last writer, since nothing is modified
+        let catalog = match backend.read(GLOBAL_STORE_CATALOG_ID)? {
+            Some(data) => page::decode(&data, GLOBAL_STORE_IDENT, GLOBAL_STORE_CATALOG_ID)?,
+            None => {
+                return Err(Error::Catalog(format!(
+                    "Store at `{}` has no global catalog",
+                    path.display()
+                )))
             }
         };
 
         Ok(GlobalStore {
             inner: Rc::new(RefCell::new(GlobalStoreInner {
-                store,
+                backend,
+                kind,
+                path: file_path,
                 catalog,
                 active_stores: HashSet::new(),
+                read_only: true,
+                compaction: CompactionPolicy::default(),
+                encryption: options.encryption,
+                previous_encryption: options.previous_encryption,
+                _lock: lock,
             })),
+            closed: false,
         })
     }
 
+    /// Open the store at `path` according to `options`, failing if it was created with a layout
+    /// different from `manifest`
+    pub fn open(
+        path: impl AsRef<Path>,
+        manifest: Manifest,
+        options: OpenOptions,
+    ) -> crate::Result<Self> {
+        let path = path.as_ref();
+        let exists = options.backend.exists(path);
+
+        if options.read_only && (options.create || options.create_new) {
+            return Err(Error::Io(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "A store cannot be created in read-only mode",
+            )));
+        }
+
+        if !(exists || options.create || options.create_new) {
+            return Err(Error::Io(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("No store exists at `{}`", path.display()),
+            )));
+        }
+
+        if exists && options.create_new {
+            return Err(Error::Io(std::io::Error::new(
+                std::io::ErrorKind::AlreadyExists,
+                format!("A store already exists at `{}`", path.display()),
+            )));
+        }
+
+        let mut store = if options.read_only {
+            Self::load_reader(path, &options, false)?
+        } else {
+            Self::load_writer(path, &options)?
+        };
+
+        let existing = store.inner_ref().catalog.manifest.clone();
+        match existing {
+            Some(existing) => existing.check(&manifest)?,
+            None if !store.inner_ref().catalog.registry.is_empty() => {
+                return Err(Error::Layout(
+                    "Store was created without a manifest, its layout is unknown".to_string(),
+                ));
+            }
+            None => (),
+        }
+
+        if options.read_only {
+            return Ok(store);
+        }
+
+        // Record the requested layout, in-memory layers are allowed to change between opens
+        store.inner_ref_mut().catalog.manifest = Some(manifest);
+        store.flush()?;
+
+        Ok(store)
+    }
+
     fn write_page<P>(&self, page: &P, id: StoreID) -> crate::Result<()>
     where
         P: Serialize,
     {
+        self.check_writable()?;
         self.inner_ref_mut()
-            .store
-            .write_batch([(id, Some(bincode::serialize(page)?))])?;
+            .backend
+            .write_batch(vec![(id, Some(page::encode(page)?))])?;
 
         Ok(())
     }
 
+    #[cfg(test)]
     fn read_page<P>(&self, id: StoreID) -> crate::Result<Option<P>>
     where
         for<'de> P: Deserialize<'de>,
     {
-        if let Some(data) = self.inner_ref().store.read(id)? {
-            return Ok(Some(bincode::deserialize(data.as_ref())?));
+        self.read_page_as(id, GLOBAL_STORE_IDENT)
+    }
+
+    // Read a page, attributing any corruption to the store `ident`
+    fn read_page_as<P>(&self, id: StoreID, ident: &str) -> crate::Result<Option<P>>
+    where
+        for<'de> P: Deserialize<'de>,
+    {
+        if let Some(data) = self.inner_ref().backend.read(id)? {
+            return Ok(Some(page::decode(&data, ident, id)?));
         }
 
         Ok(None)
@@ -99,7 +352,10 @@ impl GlobalStore {
         P: Serialize + for<'de> Deserialize<'de> + Clone,
     {
         if self.inner_ref().active_stores.contains(&ident.to_string()) {
-            panic!("Catalog `{}` has already been loaded!", ident.to_string());
+            return Err(Error::Catalog(format!(
+                "Catalog `{}` has already been loaded",
+                ident.to_string()
+            )));
         }
 
         let registry = self
@@ -109,21 +365,39 @@ impl GlobalStore {
             .get(&ident.to_string())
             .copied();
 
-        let id = registry.unwrap_or_else(|| {
-            let id = self.allocate_page();
-            self.inner_ref_mut()
-                .catalog
-                .registry
-                .insert(ident.to_string(), id);
-            id
-        });
-
-        let catalog = match self.read_page::<C>(id)? {
-            Some(catalog) => catalog,
+        // Size of the catalog page as accounted for, unknown until it is flushed
+        let mut sizes = HashMap::new();
+
+        let (id, catalog) = match registry {
+            Some(id) => match self.read_page_as::<C>(id, &ident.to_string())? {
+                Some(catalog) => (id, catalog),
+                None => {
+                    return Err(Error::Catalog(format!(
+                        "Catalog page {} of store `{}` is missing",
+                        id,
+                        ident.to_string()
+                    )))
+                }
+            },
+            None if self.is_read_only() => {
+                return Err(Error::Catalog(format!(
+                    "Catalog of store `{}` is missing",
+                    ident.to_string()
+                )))
+            }
             None => {
+                let id = self.allocate_page();
+                self.inner_ref_mut()
+                    .catalog
+                    .registry
+                    .insert(ident.to_string(), id);
+
                 let catalog = C::default();
                 self.write_page(&catalog, id)?;
-                catalog
+
+                // Written past the local store, so accounted for on its first flush
+                sizes.insert(id, None);
+                (id, catalog)
             }
         };
 
@@ -135,35 +409,251 @@ impl GlobalStore {
             id,
             ident: ident.to_string(),
             cache: Rc::new(RefCell::new(HashMap::new())),
+            sizes: RefCell::new(sizes),
         })
     }
 
     pub fn flush(&mut self) -> crate::Result<()> {
+        if self.is_read_only() {
+            return Ok(());
+        }
+
         let catalog = self.inner_ref_mut().catalog.clone();
         self.write_page(&catalog, GLOBAL_STORE_CATALOG_ID)?;
 
         Ok(())
     }
 
-    pub fn stats(&self) -> marble::Stats {
-        self.inner_ref().store.stats()
+    /// Flush the global catalog and force all written pages to durable storage. Local stores
+    /// must be flushed beforehand for their pages to be included.
+    pub fn sync(&mut self) -> crate::Result<()> {
+        if self.is_read_only() {
+            return Ok(());
+        }
+
+        self.flush()?;
+        self.inner_ref_mut().backend.sync()?;
+
+        let compaction = self.inner_ref().compaction;
+        if let CompactionPolicy::Threshold(threshold) = compaction {
+            if self.inner_ref().backend.stats().fragmentation() > threshold {
+                self.inner_ref_mut().backend.maintenance()?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Sync the store and reclaim the space left behind by overwritten and deleted pages. Local
+    /// stores must be flushed beforehand for their pages to be included.
+    pub fn compact(&mut self) -> crate::Result<()> {
+        self.check_writable()?;
+        self.sync()?;
+        self.inner_ref_mut().backend.maintenance()?;
+
+        Ok(())
+    }
+
+    /// Re-encrypt every page with `key`, then compact the store to reclaim the pages encrypted
+    /// with the previous key. Local stores must be flushed beforehand for their pages to be
+    /// included. If the rotation is interrupted, open the store with both keys through
+    /// `OpenOptions::previous_encryption` and rotate again.
+    pub fn rotate_key(&mut self, key: EncryptionKey) -> crate::Result<()> {
+        self.check_writable()?;
+
+        let Some(current) = self.inner_ref().encryption else {
+            return Err(Error::Encryption("Store is not encrypted".to_string()));
+        };
+
+        // Pages may still be encrypted with any key the store was opened with
+        let previous: Vec<EncryptionKey> = [Some(current), self.inner_ref().previous_encryption]
+            .into_iter()
+            .flatten()
+            .filter(|&previous| previous != key)
+            .collect();
+
+        self.inner_ref_mut().backend.set_key(key, &previous)?;
+        self.inner_ref_mut().encryption = Some(key);
+        self.inner_ref_mut().previous_encryption = None;
+
+        let ids: Vec<StoreID> = self.inner_ref().catalog.ids.iter().collect();
+        for chunk in ids.chunks(BATCH_SIZE) {
+            let mut batch = Vec::with_capacity(chunk.len());
+            for &id in chunk {
+                if let Some(data) = self.inner_ref().backend.read(id)? {
+                    batch.push((id, Some(data)));
+                }
+            }
+
+            self.inner_ref_mut().backend.write_batch(batch)?;
+        }
+
+        self.compact()?;
+        self.inner_ref_mut().backend.set_key(key, &[])
+    }
+
+    /// Sync the store, compacting it if its `CompactionPolicy` asks for it, then shut it down.
+    /// Fails if any local store loaded from this store is still alive, since its pages could not
+    /// be written afterwards.
+    pub fn close(mut self) -> crate::Result<()> {
+        let active = Rc::strong_count(&self.inner) - 1;
+        if active > 0 {
+            return Err(Error::Catalog(format!(
+                "Cannot close global object store, {} local object store(s) are still loaded",
+                active
+            )));
+        }
+
+        let compaction = self.inner_ref().compaction;
+        match compaction {
+            CompactionPolicy::OnClose if !self.is_read_only() => self.compact()?,
+            _ => self.sync()?,
+        }
+        self.closed = true;
+
+        Ok(())
+    }
+
+    /// Copy every page to a new store at `path`, using the same backend unless this store is
+    /// kept in memory. Local stores must be flushed beforehand for their pages to be included.
+    pub fn backup_to(&self, path: impl AsRef<Path>) -> crate::Result<()> {
+        let path = path.as_ref();
+
+        let backend = match self.inner_ref().kind {
+            Backend::Memory => Backend::default(),
+            kind => kind,
+        };
+
+        if backend.exists(path) {
+            return Err(Error::Io(std::io::Error::new(
+                std::io::ErrorKind::AlreadyExists,
+                format!("A store already exists at `{}`", path.display()),
+            )));
+        }
+
+        // Backups are encrypted with the key of the store
+        let mut options = OpenOptions::new().backend(backend);
+        options.encryption = self.inner_ref().encryption;
+
+        self.copy_into(Self::load_writer(path, &options)?)
+    }
+
+    /// Replace the store at `path` with the backup at `backup`, creating it if needed. Fails
+    /// without touching `path` if the backup was created with a layout different from
+    /// `manifest`.
+    pub fn restore(
+        backup: impl AsRef<Path>,
+        path: impl AsRef<Path>,
+        manifest: Manifest,
+        options: OpenOptions,
+    ) -> crate::Result<()> {
+        let backup = backup.as_ref();
+
+        if !options.backend.exists(backup) {
+            return Err(Error::Io(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("No backup exists at `{}`", backup.display()),
+            )));
+        }
+
+        // Locked exclusively, so that backups made with the marble backend can be read
+        let source = Self::load_reader(backup, &options, true)?;
+        match &source.inner_ref().catalog.manifest {
+            Some(existing) => existing.check(&manifest)?,
+            None => {
+                return Err(Error::Layout(
+                    "Backup was created without a manifest, its layout is unknown".to_string(),
+                ))
+            }
+        }
+
+        source.copy_into(Self::load_writer(path.as_ref(), &options)?)
+    }
+
+    // Replace all pages of `target` with the pages of this store, then close it
+    fn copy_into(&self, target: GlobalStore) -> crate::Result<()> {
+        let stale = target
+            .inner_ref()
+            .catalog
+            .ids
+            .iter()
+            .filter(|&id| id != GLOBAL_STORE_CATALOG_ID)
+            .map(|id| (id, None))
+            .collect();
+        target.inner_ref_mut().backend.write_batch(stale)?;
+
+        let ids: Vec<StoreID> = self
+            .inner_ref()
+            .catalog
+            .ids
+            .iter()
+            .filter(|&id| id != GLOBAL_STORE_CATALOG_ID)
+            .collect();
+
+        // Pages are copied as they are, without decoding them
+        for chunk in ids.chunks(BATCH_SIZE) {
+            let mut batch = Vec::with_capacity(chunk.len());
+            for &id in chunk {
+                if let Some(data) = self.inner_ref().backend.read(id)? {
+                    batch.push((id, Some(data)));
+                }
+            }
+
+            target.inner_ref_mut().backend.write_batch(batch)?;
+        }
+
+        // Keep the generation of the target, so that files derived from its previous pages are
+        // never trusted again
+        let mut catalog = self.inner_ref().catalog.clone();
+        catalog.generation = target.generation();
+        target.inner_ref_mut().catalog = catalog;
+
+        target.close()
+    }
+
+    /// Page and space statistics of the whole store, without the statistics of its layers
+    pub fn stats(&self) -> StoreStats {
+        let inner = self.inner_ref();
+
+        StoreStats {
+            live_pages: inner.catalog.ids.allocated() as u64,
+            freed_pages: inner.catalog.ids.freed() as u64,
+            backend: inner.backend.stats(),
+            layers: Vec::new(),
+        }
+    }
+
+    /// Directory the store was opened from, or `None` if its pages are not kept on disk
+    pub fn path(&self) -> Option<PathBuf> {
+        self.inner_ref().path.clone()
+    }
+
+    /// Generation of the current session, incremented every time the store is loaded for
+    /// writing. Read-only sessions share the generation of the last writer.
+    pub fn generation(&self) -> u64 {
+        self.inner_ref().catalog.generation
+    }
+
+    /// Whether the store was loaded with `load_read_only`
+    pub fn is_read_only(&self) -> bool {
+        self.inner_ref().read_only
+    }
+
+    /// Whether the pages of the store are encrypted
+    pub fn is_encrypted(&self) -> bool {
+        self.inner_ref().encryption.is_some()
     }
 }
 
 impl Drop for GlobalStore {
     fn drop(&mut self) {
-        assert_eq!(
-            Rc::strong_count(&self.inner),
-            1,
-            "Shutting down global object store, but not all local object stores have been freed!"
-        );
-
-        self.flush().expect("Failed to flush GlobalStore to disk!");
+        if self.closed {
+            return;
+        }
 
-        self.inner_ref_mut()
-            .store
-            .maintenance()
-            .expect("Defragmentation failed!");
+        // Best effort only, errors cannot be reported from here and panicking while unwinding
+        // aborts the process. Use `close` to observe shutdown failures.
+        let _ = self.sync();
     }
 }
 
@@ -178,6 +668,9 @@ where
     ident: String,
 
     cache: Rc<RefCell<HashMap<StoreID, Option<P>>>>,
+
+    // Sizes of cached pages and of the catalog as stored, `None` for pages not stored yet
+    sizes: RefCell<HashMap<StoreID, Option<u64>>>,
 }
 
 impl<C, P> LocalStore<C, P>
@@ -186,32 +679,80 @@ where
     P: Serialize + for<'de> Deserialize<'de> + Clone,
 {
     pub fn flush(&self) -> crate::Result<()> {
+        // Pages cached by a read-only store were only ever read
+        if self.inner_ref().read_only {
+            return Ok(());
+        }
+
         let catalog = self.catalog.clone();
 
         // Serialize the cache
-        let mut write_batch: Vec<(StoreID, Option<Vec<u8>>)> = self
+        let mut write_batch = self
             .cache
             .as_ref()
-            .borrow_mut()
+            .borrow()
             .iter()
-            .map_while(|(&id, page)| {
-                if let Some(page) = page {
-                    let data = bincode::serialize(page).ok()?;
-                    return Some((id, Some(data)));
-                }
-
-                Some((id, None))
+            .map(|(&id, data)| match data {
+                Some(data) => Ok((id, Some(page::encode(data)?))),
+                None => Ok((id, None)),
             })
-            .collect();
+            .collect::<crate::Result<Vec<(StoreID, Option<Vec<u8>>)>>>()?;
+
+        write_batch.push((self.id, Some(page::encode(&catalog)?)));
+
+        // Freed pages were accounted for when they were freed, others once they are written
+        let resized = write_batch
+            .iter()
+            .filter_map(|(id, data)| Some((*id, data.as_ref()?.len() as u64)))
+            .map(|(id, size)| Ok((id, self.stored_size(id)?, size)))
+            .collect::<crate::Result<Vec<_>>>()?;
+
+        self.inner_ref_mut().backend.write_batch(write_batch)?;
+
+        for (id, old, new) in resized {
+            self.account(id, old, Some(new));
+        }
+
+        // Dirty pages are only dropped once written, so that a failed flush can be retried
         self.cache.as_ref().borrow_mut().clear();
+        self.sizes.borrow_mut().retain(|&id, _| id == self.id);
+        Ok(())
+    }
 
-        write_batch.push((self.id, Some(bincode::serialize(&catalog)?)));
+    // Size of a page as stored, asking the backend unless the page is cached
+    fn stored_size(&self, id: StoreID) -> crate::Result<Option<u64>> {
+        if let Some(&size) = self.sizes.borrow().get(&id) {
+            return Ok(size);
+        }
+
+        self.inner_ref().backend.size(id)
+    }
+
+    // Account for a page of this store going from `old` to `new` bytes, `None` if not stored
+    fn account(&self, id: StoreID, old: Option<u64>, new: Option<u64>) {
+        let mut inner = self.inner_ref_mut();
+        let usage = inner.catalog.usage.entry(self.id).or_default();
+
+        usage.pages = (usage.pages + new.is_some() as u64).saturating_sub(old.is_some() as u64);
+        usage.bytes = (usage.bytes + new.unwrap_or(0)).saturating_sub(old.unwrap_or(0));
+
+        self.sizes.borrow_mut().insert(id, new);
+    }
+
+    /// Flush this store and force all pages written so far to durable storage
+    pub fn sync(&self) -> crate::Result<()> {
+        if self.inner_ref().read_only {
+            return Ok(());
+        }
+
+        self.flush()?;
+        self.inner_ref_mut().backend.sync()?;
 
-        self.inner_ref_mut().store.write_batch(write_batch)?;
         Ok(())
     }
 
     pub fn write_page(&self, page: &P, id: StoreID) -> crate::Result<()> {
+        self.check_writable()?;
         self.cache
             .as_ref()
             .borrow_mut()
@@ -230,8 +771,10 @@ where
             return Ok(data.clone());
         }
 
-        if let Some(data) = self.inner_ref().store.read(id)? {
-            let data: P = bincode::deserialize(data.as_ref())?;
+        if let Some(data) = self.inner_ref().backend.read(id)? {
+            self.sizes.borrow_mut().insert(id, Some(data.len() as u64));
+
+            let data: P = page::decode(&data, &self.ident, id)?;
             self.cache
                 .as_ref()
                 .borrow_mut()
@@ -242,6 +785,26 @@ where
 
         Ok(None)
     }
+
+    /// Flush this store and report the pages it holds, including its catalog. Pages are counted
+    /// as they are written, so nothing is read.
+    pub fn stats(&self) -> crate::Result<LayerStats> {
+        self.flush()?;
+
+        let inner = self.inner_ref();
+        let usage = inner
+            .catalog
+            .usage
+            .get(&self.id)
+            .copied()
+            .unwrap_or_default();
+
+        Ok(LayerStats {
+            ident: self.ident.clone(),
+            pages: usage.pages,
+            bytes: usage.bytes,
+        })
+    }
 }
 
 impl<C, P> Drop for LocalStore<C, P>
@@ -251,14 +814,15 @@ where
 {
     fn drop(&mut self) {
         self.inner_ref_mut().active_stores.remove(&self.ident);
-        self.flush().expect("Failed to flush GlobalStore to disk!");
+
+        // Best effort, see `GlobalStore::drop`
+        let _ = self.flush();
     }
 }
 
 pub trait ObjectStoreGeneric {
     fn allocate_page(&mut self) -> StoreID;
     fn free_page(&mut self, id: StoreID) -> crate::Result<bool>;
-    fn clear(&mut self) -> crate::Result<()>;
 }
 
 impl<T> ObjectStoreGeneric for T
@@ -270,37 +834,35 @@ where
     }
 
     fn free_page(&mut self, id: StoreID) -> crate::Result<bool> {
+        self.check_writable()?;
+
         if self.inner_ref_mut().catalog.ids.free(id) {
-            self.remove_page(id);
+            self.remove_page(id)?;
 
-            let empty_page: Option<[u8; 1]> = None;
-            self.inner_ref_mut().store.write_batch([(id, empty_page)])?;
+            self.inner_ref_mut().backend.delete(id)?;
             return Ok(true);
         }
 
         Ok(false)
     }
-
-    fn clear(&mut self) -> crate::Result<()> {
-        let mut clear_batch: Vec<(StoreID, Option<[u8; 1]>)> = vec![];
-
-        for id in self.inner_ref().catalog.ids.iter() {
-            clear_batch.push((id, None));
-        }
-
-        self.inner_ref_mut().store.write_batch(clear_batch)?;
-        self.inner_ref_mut().catalog.ids.clear();
-
-        Ok(())
-    }
 }
 
 trait ObjectStoreInner {
     fn inner_ref(&self) -> Ref<GlobalStoreInner>;
     fn inner_ref_mut(&self) -> RefMut<GlobalStoreInner>;
 
-    // Callback for removing a page
-    fn remove_page(&self, _id: StoreID) -> () {}
+    // Callback for removing a page, before it is deleted from the backend
+    fn remove_page(&self, _id: StoreID) -> crate::Result<()> {
+        Ok(())
+    }
+
+    fn check_writable(&self) -> crate::Result<()> {
+        if self.inner_ref().read_only {
+            return Err(Error::ReadOnly);
+        }
+
+        Ok(())
+    }
 }
 
 impl<C, P> ObjectStoreInner for LocalStore<C, P>
@@ -316,8 +878,12 @@ where
         self.root.as_ref().borrow_mut()
     }
 
-    fn remove_page(&self, id: StoreID) -> () {
+    fn remove_page(&self, id: StoreID) -> crate::Result<()> {
+        let size = self.stored_size(id)?;
+        self.account(id, size, None);
         self.cache.as_ref().borrow_mut().insert(id, None);
+
+        Ok(())
     }
 }
 
@@ -333,7 +899,9 @@ impl ObjectStoreInner for GlobalStore {
 
 #[cfg(test)]
 mod tests {
+    use super::super::{backend::MemoryBackend, BackendStats};
     use super::*;
+    use std::cell::Cell;
 
     #[test]
     fn global_load() {
@@ -433,6 +1001,47 @@ mod tests {
         );
     }
 
+    fn test_manifest(fanout: usize) -> Manifest {
+        use super::super::ManifestComponent;
+
+        Manifest::new::<u64, u64>(vec![
+            ManifestComponent::new("btree_top()", false),
+            ManifestComponent::new(format!("btree(fanout = {fanout}, persist)"), true),
+        ])
+    }
+
+    #[test]
+    fn open_missing_store() {
+        let dir = tempfile::tempdir().unwrap();
+
+        assert!(matches!(
+            GlobalStore::open(dir.path(), test_manifest(32), OpenOptions::new()),
+            Err(Error::Io(error)) if error.kind() == std::io::ErrorKind::NotFound
+        ));
+    }
+
+    #[test]
+    fn open_with_layout_mismatch() {
+        let dir = tempfile::tempdir().unwrap();
+
+        {
+            let mut store = GlobalStore::open(
+                dir.path(),
+                test_manifest(32),
+                OpenOptions::new().create(true),
+            )
+            .unwrap();
+            let _local_store: LocalStore<TestCatalog, i32> =
+                store.load_local_store("test").unwrap();
+        }
+
+        GlobalStore::open(dir.path(), test_manifest(32), OpenOptions::new()).unwrap();
+        assert!(matches!(
+            GlobalStore::open(dir.path(), test_manifest(64), OpenOptions::new()),
+            Err(Error::Layout(_))
+        ));
+    }
+
     #[test]
     fn load_local_store() {
         let dir = tempfile::tempdir().unwrap();
@@ -475,28 +1084,463 @@ mod tests {
     }
 
     #[test]
-    #[should_panic(
-        expected = "Shutting down global object store, but not all local object stores have been freed!"
-    )]
-    fn drop_global_with_active_references() {
+    fn close_global_with_active_references() {
         let dir = tempfile::tempdir().unwrap();
         let mut store = GlobalStore::load(dir.path()).unwrap();
 
         let _local_store: LocalStore<TestCatalog, i32> = store.load_local_store("test").unwrap();
 
-        // Should panic
+        assert!(matches!(store.close(), Err(Error::Catalog(_))));
+    }
+
+    #[test]
+    fn drop_global_with_active_references() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = GlobalStore::load(dir.path()).unwrap();
+
+        let mut local_store: LocalStore<TestCatalog, i32> = store.load_local_store("test").unwrap();
+        local_store.catalog.id = local_store.allocate_page();
+
+        // Should not panic, the local store keeps the underlying store alive
         drop(store);
+        drop(local_store);
+    }
+
+    #[test]
+    fn close_and_reload() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let id = {
+            let mut store = GlobalStore::load(dir.path()).unwrap();
+            let id = store.allocate_page();
+            store.write_page(&"Closed cleanly", id).unwrap();
+            store.close().unwrap();
+            id
+        };
+
+        let store = GlobalStore::load(dir.path()).unwrap();
+        let data: Option<String> = store.read_page(id).unwrap();
+        assert_eq!(data.as_deref(), Some("Closed cleanly"));
+    }
+
+    #[test]
+    fn generation_increases_on_load() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let generation = GlobalStore::load(dir.path()).unwrap().generation();
+        assert_eq!(
+            GlobalStore::load(dir.path()).unwrap().generation(),
+            generation + 1
+        );
+
+        let store = GlobalStore::load_with(dir.path(), Backend::Memory).unwrap();
+        assert_eq!(store.path(), None);
+    }
+
+    #[test]
+    fn backup_and_restore() {
+        let dir = tempfile::tempdir().unwrap();
+        let (path, backup, restored) = (
+            dir.path().join("store"),
+            dir.path().join("backup"),
+            dir.path().join("restored"),
+        );
+
+        let mut store =
+            GlobalStore::open(&path, test_manifest(8), OpenOptions::new().create(true)).unwrap();
+        let id = store.allocate_page();
+        store.write_page(&"Before backup", id).unwrap();
+
+        store.backup_to(&backup).unwrap();
+        assert!(matches!(store.backup_to(&backup), Err(Error::Io(_))));
+
+        // The store remains usable after the backup
+        store.write_page(&"After backup", id).unwrap();
+        store.close().unwrap();
+
+        let options = OpenOptions::new();
+        assert!(matches!(
+            GlobalStore::restore(&backup, &path, test_manifest(16), options),
+            Err(Error::Layout(_))
+        ));
+
+        for target in [&path, &restored] {
+            GlobalStore::restore(&backup, target, test_manifest(8), options).unwrap();
+
+            let store = GlobalStore::open(target, test_manifest(8), options).unwrap();
+            let data: Option<String> = store.read_page(id).unwrap();
+            assert_eq!(data.as_deref(), Some("Before backup"));
+        }
+    }
+
+    #[test]
+    fn local_store_usage() {
+        let dir = tempfile::tempdir().unwrap();
+        let measure = |store: &GlobalStore, ids: &[StoreID]| -> u64 {
+            let inner = store.inner_ref();
+            ids.iter()
+                .map(|&id| inner.backend.size(id).unwrap().unwrap())
+                .sum()
+        };
+
+        let (catalog, ids) = {
+            let mut store = GlobalStore::load_with(dir.path(), Backend::File).unwrap();
+            let mut local_store: LocalStore<TestCatalog, Vec<u8>> =
+                store.load_local_store("test").unwrap();
+
+            let ids: Vec<StoreID> = (0..4).map(|_| local_store.allocate_page()).collect();
+            for (index, &id) in ids.iter().enumerate() {
+                local_store.write_page(&vec![1; 100 * index], id).unwrap();
+            }
+
+            let stats = local_store.stats().unwrap();
+            assert_eq!(stats.pages, 5);
+            assert_eq!(
+                stats.bytes,
+                measure(&store, &[&[local_store.id], &ids[..]].concat())
+            );
+
+            // Overwritten pages are counted once, freed pages not at all
+            local_store.write_page(&vec![2; 1000], ids[0]).unwrap();
+            local_store.flush().unwrap();
+            local_store.write_page(&vec![3; 10], ids[0]).unwrap();
+            assert!(local_store.free_page(ids[3]).unwrap());
+
+            let stats = local_store.stats().unwrap();
+            assert_eq!(stats.pages, 4);
+            assert_eq!(
+                stats.bytes,
+                measure(&store, &[&[local_store.id], &ids[..3]].concat())
+            );
+
+            let catalog = local_store.id;
+            drop(local_store);
+            store.close().unwrap();
+            (catalog, ids)
+        };
+
+        // Counts are kept in the global catalog across sessions
+        let mut store = GlobalStore::load_read_only(dir.path(), Backend::File).unwrap();
+        let local_store: LocalStore<TestCatalog, Vec<u8>> = store.load_local_store("test").unwrap();
+        let stats = local_store.stats().unwrap();
+        assert_eq!(stats.pages, 4);
+        assert_eq!(
+            stats.bytes,
+            measure(&store, &[&[catalog], &ids[..3]].concat())
+        );
+        assert_eq!(store.stats().live_pages, 5);
+        assert_eq!(store.stats().freed_pages, 1);
+    }
+
+    #[test]
+    fn compact_and_stats() {
+        let dir = tempfile::tempdir().unwrap();
+        let options = OpenOptions::new().backend(Backend::File).create(true);
+
+        let mut store = GlobalStore::open(dir.path(), test_manifest(8), options).unwrap();
+        let ids: Vec<StoreID> = (0..8).map(|_| store.allocate_page()).collect();
+        for &id in &ids {
+            store.write_page(&vec![id; 1024], id).unwrap();
+        }
+        for &id in &ids[4..] {
+            assert!(store.free_page(id).unwrap());
+        }
+        store.sync().unwrap();
+
+        let stats = store.stats();
+        assert_eq!(stats.live_pages, 5);
+        assert_eq!(stats.freed_pages, 4);
+        assert!(stats.fragmentation() > 0.0);
+
+        store.compact().unwrap();
+        let compacted = store.stats();
+        assert!(compacted.backend.total_bytes < stats.backend.total_bytes);
+        assert!(compacted.fragmentation() < stats.fragmentation());
+        store.close().unwrap();
+
+        let mut store = GlobalStore::load_read_only(dir.path(), Backend::File).unwrap();
+        assert!(matches!(store.compact(), Err(Error::ReadOnly)));
+    }
+
+    #[test]
+    fn compact_past_threshold() {
+        let dir = tempfile::tempdir().unwrap();
+        let options = OpenOptions::new()
+            .backend(Backend::File)
+            .create(true)
+            .compaction(CompactionPolicy::Threshold(0.1));
+
+        let mut store = GlobalStore::open(dir.path(), test_manifest(8), options).unwrap();
+        let ids: Vec<StoreID> = (0..8).map(|_| store.allocate_page()).collect();
+        for &id in &ids {
+            store.write_page(&vec![id; 1024], id).unwrap();
+        }
+        store.sync().unwrap();
+        let before = store.stats().backend.total_bytes;
+
+        // Freeing the last pages leaves trailing dead space, which the sync reclaims
+        for &id in &ids[4..] {
+            store.free_page(id).unwrap();
+        }
+        store.sync().unwrap();
+        assert!(store.stats().backend.total_bytes < before);
+    }
+
+    #[test]
+    fn compact_on_close_by_policy() {
+        let dir = tempfile::tempdir().unwrap();
+
+        // Size of the store after writing pages, freeing half of them and closing it
+        let closed_size = |compaction: CompactionPolicy| {
+            let path = dir.path().join(format!("{compaction:?}"));
+            let options = OpenOptions::new()
+                .backend(Backend::File)
+                .create(true)
+                .compaction(compaction);
+
+            let mut store = GlobalStore::open(&path, test_manifest(8), options).unwrap();
+            let ids: Vec<StoreID> = (0..8).map(|_| store.allocate_page()).collect();
+            for &id in &ids {
+                store.write_page(&vec![id; 1024], id).unwrap();
+            }
+            for &id in &ids[4..] {
+                store.free_page(id).unwrap();
+            }
+            store.close().unwrap();
+
+            let store = GlobalStore::load_read_only(&path, Backend::File).unwrap();
+            store.stats().backend.total_bytes
+        };
+
+        let compacted = closed_size(CompactionPolicy::OnClose);
+        assert!(closed_size(CompactionPolicy::Manual) > compacted);
+        assert!(closed_size(CompactionPolicy::Threshold(0.99)) > compacted);
+        assert_eq!(closed_size(CompactionPolicy::Threshold(0.01)), compacted);
+    }
+
+    #[test]
+    fn encrypted_store() {
+        let dir = tempfile::tempdir().unwrap();
+        let (first, second) = (EncryptionKey::new([1; 32]), EncryptionKey::new([2; 32]));
+        let options = OpenOptions::new().backend(Backend::File);
+        let open = |options: OpenOptions| GlobalStore::open(dir.path(), test_manifest(8), options);
+
+        let mut store = open(options.encryption(first).create(true)).unwrap();
+        let id = store.allocate_page();
+        store.write_page(&"Plaintext marker", id).unwrap();
+        store.close().unwrap();
+
+        for entry in std::fs::read_dir(dir.path()).unwrap() {
+            let data = std::fs::read(entry.unwrap().path()).unwrap();
+            assert!(!data.windows(16).any(|window| window == b"Plaintext marker"));
+        }
+
+        assert!(matches!(open(options), Err(Error::Corruption { .. })));
+        assert!(matches!(
+            open(options.encryption(second)),
+            Err(Error::Encryption(_))
+        ));
+
+        let mut store = open(options.encryption(first)).unwrap();
+        store.rotate_key(second).unwrap();
+        store.close().unwrap();
+
+        assert!(matches!(
+            open(options.encryption(first)),
+            Err(Error::Encryption(_))
+        ));
+        let store = open(options.encryption(second)).unwrap();
+        let data: Option<String> = store.read_page(id).unwrap();
+        assert_eq!(data.as_deref(), Some("Plaintext marker"));
+        store.close().unwrap();
+
+        // An interrupted rotation is completed by rotating again with both keys
+        let third = EncryptionKey::new([3; 32]);
+        let mut store = open(options.encryption(third).previous_encryption(second)).unwrap();
+        store.rotate_key(third).unwrap();
+        store.close().unwrap();
+
+        let store = open(options.encryption(third)).unwrap();
+        let data: Option<String> = store.read_page(id).unwrap();
+        assert_eq!(data.as_deref(), Some("Plaintext marker"));
+    }
+
+    #[test]
+    fn rotate_unencrypted_store() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = GlobalStore::load(dir.path()).unwrap();
+        assert!(matches!(
+            store.rotate_key(EncryptionKey::new([1; 32])),
+            Err(Error::Encryption(_))
+        ));
+    }
+
+    // Memory backend whose writes fail with a transient error while `failing` is set
+    struct FlakyBackend {
+        pages: MemoryBackend,
+        failing: Rc<Cell<bool>>,
+    }
+
+    impl PageBackend for FlakyBackend {
+        fn read(&self, id: StoreID) -> crate::Result<Option<Vec<u8>>> {
+            self.pages.read(id)
+        }
+
+        fn write_batch(&mut self, batch: Vec<(StoreID, Option<Vec<u8>>)>) -> crate::Result<()> {
+            if self.failing.get() {
+                return Err(std::io::Error::from(std::io::ErrorKind::Interrupted).into());
+            }
+
+            self.pages.write_batch(batch)
+        }
+
+        fn sync(&mut self) -> crate::Result<()> {
+            Ok(())
+        }
+
+        fn stats(&self) -> BackendStats {
+            self.pages.stats()
+        }
+    }
+
+    // Store kept in memory by `backend`, bypassing the backends selected through `Backend`
+    fn store_with(backend: impl PageBackend + 'static) -> GlobalStore {
+        GlobalStore {
+            inner: Rc::new(RefCell::new(GlobalStoreInner {
+                backend: Box::new(backend),
+                kind: Backend::Memory,
+                path: None,
+                active_stores: HashSet::new(),
+                catalog: GlobalStoreCatalog::default(),
+                read_only: false,
+                compaction: CompactionPolicy::default(),
+                encryption: None,
+                previous_encryption: None,
+                _lock: None,
+            })),
+            closed: false,
+        }
+    }
+
+    #[test]
+    fn failed_flush_keeps_dirty_pages() {
+        let failing = Rc::new(Cell::new(false));
+        let mut store = store_with(FlakyBackend {
+            pages: Default::default(),
+            failing: failing.clone(),
+        });
+
+        let mut local_store: LocalStore<TestCatalog, i32> = store.load_local_store("test").unwrap();
+        let page_id = local_store.allocate_page();
+        local_store.write_page(&42, page_id).unwrap();
+
+        failing.set(true);
+        assert!(local_store.sync().unwrap_err().is_transient());
+
+        // The retry writes the page which the failed sync could not
+        failing.set(false);
+        local_store.sync().unwrap();
+
+        let data = store.inner_ref().backend.read(page_id).unwrap().unwrap();
+        assert_eq!(page::decode::<i32>(&data, "test", page_id).unwrap(), 42);
     }
 
     #[test]
-    #[should_panic(expected = "Catalog `test` has already been loaded!")]
     fn no_multiple_local_stores() {
         let dir = tempfile::tempdir().unwrap();
         let mut store = GlobalStore::load(dir.path()).unwrap();
 
         let _local_store_1: LocalStore<TestCatalog, i32> = store.load_local_store("test").unwrap();
-        // Should panic
-        let _local_store_2: LocalStore<TestCatalog, i32> = store.load_local_store("test").unwrap();
+        let local_store_2 = store.load_local_store::<TestCatalog, i32>("test");
+        assert!(matches!(local_store_2, Err(Error::Catalog(_))));
+    }
+
+    #[test]
+    fn exclusive_lock() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = GlobalStore::load(dir.path()).unwrap();
+
+        assert!(matches!(
+            GlobalStore::load(dir.path()),
+            Err(Error::Locked(_))
+        ));
+        assert!(matches!(
+            GlobalStore::load_read_only(dir.path(), Backend::default()),
+            Err(Error::Locked(_))
+        ));
+
+        store.close().unwrap();
+        GlobalStore::load(dir.path()).unwrap();
+    }
+
+    #[test]
+    fn default_backend_readers() {
+        let dir = tempfile::tempdir().unwrap();
+        GlobalStore::load(dir.path()).unwrap().close().unwrap();
+
+        // Marble cannot share its directory, so a second reader waits for the first one
+        let first = GlobalStore::load_read_only(dir.path(), Backend::default()).unwrap();
+        let generation = first.generation();
+        assert!(first.is_read_only());
+        assert!(matches!(
+            GlobalStore::load_read_only(dir.path(), Backend::default()),
+            Err(Error::Locked(_))
+        ));
+        assert!(matches!(
+            GlobalStore::load(dir.path()),
+            Err(Error::Locked(_))
+        ));
+
+        drop(first);
+        let second = GlobalStore::load_read_only(dir.path(), Backend::default()).unwrap();
+        assert_eq!(second.generation(), generation);
+        drop(second);
+
+        GlobalStore::load(dir.path()).unwrap().close().unwrap();
+    }
+
+    #[test]
+    fn multiple_readers() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let id = {
+            let mut store = GlobalStore::load_with(dir.path(), Backend::File).unwrap();
+            let id = store.allocate_page();
+            store.write_page(&"Shared", id).unwrap();
+            store.close().unwrap();
+            id
+        };
+
+        let generation = GlobalStore::load_read_only(dir.path(), Backend::File)
+            .unwrap()
+            .generation();
+
+        let mut first = GlobalStore::load_read_only(dir.path(), Backend::File).unwrap();
+        let second = GlobalStore::load_read_only(dir.path(), Backend::File).unwrap();
+        assert!(matches!(
+            GlobalStore::load(dir.path()),
+            Err(Error::Locked(_))
+        ));
+
+        for store in [&first, &second] {
+            let data: Option<String> = store.read_page(id).unwrap();
+            assert_eq!(data.as_deref(), Some("Shared"));
+            assert_eq!(store.generation(), generation);
+        }
+
+        let id = first.allocate_page();
+        assert!(matches!(
+            first.write_page(&"Written", id),
+            Err(Error::ReadOnly)
+        ));
+        assert!(matches!(
+            first.load_local_store::<TestCatalog, i32>("missing"),
+            Err(Error::Catalog(_))
+        ));
+
+        first.sync().unwrap();
+        first.close().unwrap();
+        second.close().unwrap();
     }
 
     #[test]
@@ -551,9 +1595,62 @@ mod tests {
             // Attempt to read the corrupted data as a TestCatalog
             let read_result: crate::Result<Option<TestCatalog>> = store.read_page(page_id);
             assert!(
-                read_result.is_err(),
+                matches!(read_result, Err(Error::Serialization(_))),
                 "Reading corrupted data should result in an error"
             );
         }
     }
+
+    #[test]
+    fn checksum_mismatch_detected() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = GlobalStore::load(dir.path()).unwrap();
+
+        let page_id = store.allocate_page();
+        store.write_page(&"Some valid data", page_id).unwrap();
+
+        // Flip a bit of the page on disk behind the store's back
+        let mut data = store.inner_ref().backend.read(page_id).unwrap().unwrap();
+        let last = data.len() - 1;
+        data[last] ^= 1;
+        store
+            .inner_ref_mut()
+            .backend
+            .write_batch(vec![(page_id, Some(data))])
+            .unwrap();
+
+        let error = store.read_page::<String>(page_id).unwrap_err();
+        assert!(
+            matches!(error, Error::Corruption { ref store, page } if store == GLOBAL_STORE_IDENT && page == page_id),
+            "Expected a corruption error, got {error:?}"
+        );
+    }
+
+    #[test]
+    fn local_checksum_mismatch_detected() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = GlobalStore::load(dir.path()).unwrap();
+
+        let page_id = {
+            let mut local_store: LocalStore<TestCatalog, i32> =
+                store.load_local_store("test").unwrap();
+            let page_id = local_store.allocate_page();
+            local_store.write_page(&42, page_id).unwrap();
+            page_id
+        };
+
+        // Truncate the page so that the header is no longer intact
+        store
+            .inner_ref_mut()
+            .backend
+            .write_batch(vec![(page_id, Some(vec![0u8; 2]))])
+            .unwrap();
+
+        let local_store: LocalStore<TestCatalog, i32> = store.load_local_store("test").unwrap();
+        let error = local_store.read_page(page_id).unwrap_err();
+        assert!(
+            matches!(error, Error::Corruption { ref store, page } if store == "test" && page == page_id),
+            "Expected a corruption error, got {error:?}"
+        );
+    }
 }
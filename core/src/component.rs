@@ -1,4 +1,5 @@
-use crate::common::storage::GlobalStore;
+use crate::common::storage::{GlobalStore, LayerStats};
+use crate::describe::LayerSize;
 use crate::node_layer::NodeLayer;
 use crate::traits::*;
 
@@ -44,9 +45,25 @@ where
     fn build(base: &mut Base) -> Self;
 }
 
+/// Behaviour shared by all components which persist their nodes to a `GlobalStore`
+pub trait DiskComponent {
+    /// Write all buffered pages and the component catalog to the underlying store
+    fn flush(&self) -> crate::Result<()>;
+
+    /// Flush the component and measure the pages it occupies
+    fn stats(&self) -> crate::Result<LayerStats>;
+}
+
+/// Behaviour shared by all components, so that generated stores can `describe` their layers
+pub trait Measure {
+    /// Count the nodes and keys of the component and the bytes it occupies. Persisted
+    /// components read every one of their nodes.
+    fn measure(&self) -> crate::Result<LayerSize>;
+}
+
 pub trait BoundaryDiskInternalComponent<K, Base, BA, SA, PA>
 where
-    Self: NodeLayer<K, SA, PA> + Sized,
+    Self: NodeLayer<K, SA, PA> + DiskComponent + Sized,
     Base: NodeLayer<K, BA, SA>,
     BA: Persisted + Address,
     SA: Persisted + Address,
@@ -66,7 +83,7 @@ where
 
 pub trait DeepDiskInternalComponent<K, Base, BA, SA, PA>
 where
-    Self: NodeLayer<K, SA, PA> + Sized,
+    Self: NodeLayer<K, SA, PA> + DiskComponent + Sized,
     Base: NodeLayer<K, BA, SA>,
     BA: Persisted + Address,
     SA: Persisted + Address,
@@ -98,11 +115,13 @@ where
     fn empty() -> Self;
 
     fn build(iter: impl Iterator<Item = (K, V)>) -> Self;
+
+    fn entries(&self) -> impl Iterator<Item = (K, V)> + '_;
 }
 
 pub trait BoundaryDiskBaseComponent<K, V, SA, PA>
 where
-    Self: NodeLayer<K, SA, PA> + Sized,
+    Self: NodeLayer<K, SA, PA> + DiskComponent + Sized,
     SA: Persisted + Address,
     PA: Address,
     K: Key,
@@ -117,11 +136,19 @@ where
     fn search(&self, ptr: SA, key: &K) -> crate::Result<Option<V>>;
 
     fn load(store: &mut GlobalStore, ident: impl ToString) -> crate::Result<Self>;
+
+    fn build(
+        store: &mut GlobalStore,
+        ident: impl ToString,
+        iter: impl Iterator<Item = (K, V)>,
+    ) -> crate::Result<Self>;
+
+    fn entries(&self) -> impl Iterator<Item = crate::Result<(K, V)>> + '_;
 }
 
 pub trait DeepDiskBaseComponent<K, V, SA, PA>
 where
-    Self: NodeLayer<K, SA, PA> + Sized,
+    Self: NodeLayer<K, SA, PA> + DiskComponent + Sized,
     SA: Persisted + Address,
     PA: Persisted + Address,
     K: Key,
@@ -136,4 +163,12 @@ where
     fn search(&self, ptr: SA, key: &K) -> crate::Result<Option<V>>;
 
     fn load(store: &mut GlobalStore, ident: impl ToString) -> crate::Result<Self>;
+
+    fn build(
+        store: &mut GlobalStore,
+        ident: impl ToString,
+        iter: impl Iterator<Item = (K, V)>,
+    ) -> crate::Result<Self>;
+
+    fn entries(&self) -> impl Iterator<Item = crate::Result<(K, V)>> + '_;
 }
@@ -0,0 +1,50 @@
+//! Structured descriptions of the layers of a store, returned by the `describe` method of
+//! generated stores.
+
+/// Where the nodes of a layer are kept
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PersistType {
+    /// Nodes are kept in memory, and rebuilt or restored from a checkpoint on open
+    InMemory,
+
+    /// Nodes are persisted below an in-memory layer, which keeps track of their parents
+    BoundaryDisk,
+
+    /// Nodes are persisted below another persisted layer, along with their parents
+    DeepDisk,
+}
+
+/// Measured size of a single layer
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LayerSize {
+    /// Number of nodes of the layer. The top component counts as a single node.
+    pub nodes: usize,
+
+    /// Number of keys held by the nodes of the layer
+    pub keys: usize,
+
+    /// Bytes held in memory by the layer, not counting the shared page cache
+    pub memory_bytes: usize,
+
+    /// Bytes held on disk by the pages of the layer, including its catalog
+    pub disk_bytes: u64,
+}
+
+/// Description of a single layer of a store
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LayerDescription {
+    /// Kind of the component, such as `btree` or `custom`
+    pub kind: &'static str,
+
+    /// The component, described the same way as in `create_kv_store!`
+    pub component: &'static str,
+
+    /// Parameters of the component, such as its fanout, along with their values as written
+    pub parameters: &'static [(&'static str, &'static str)],
+
+    /// Where the nodes of the layer are kept
+    pub persist: PersistType,
+
+    /// Measured size of the layer
+    pub size: LayerSize,
+}
@@ -0,0 +1,724 @@
+use super::spec::ComponentSpec;
+use crate::common::list::memory::ArenaID;
+use crate::common::storage::{GlobalStore, StoreID};
+use crate::*;
+use serde::{Deserialize, Serialize};
+use std::marker::PhantomData;
+
+// Const generic parameters can only be chosen among values compiled in ahead of time, which must
+// match `SUPPORTED_FANOUTS` and `SUPPORTED_EPSILONS`
+macro_rules! dispatch {
+    ($value:expr, [$($supported:literal),*], |$N:ident| $body:expr) => {
+        match $value {
+            $($supported => {
+                const $N: usize = $supported;
+                $body
+            })*
+            value => unreachable!("{} is rejected by `LayoutSpec::new`", value),
+        }
+    };
+}
+
+macro_rules! with_fanout {
+    ($fanout:expr, |$N:ident| $body:expr) => {
+        dispatch!($fanout, [4, 8, 16, 32, 64, 128, 256, 512], |$N| $body)
+    };
+}
+
+macro_rules! with_epsilon {
+    ($epsilon:expr, |$N:ident| $body:expr) => {
+        dispatch!($epsilon, [4, 8, 16, 32, 64, 128], |$N| $body)
+    };
+}
+
+/// Address of a node in any layer of a dynamic store, so that layers can be stacked in an order
+/// only known at runtime
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DynAddress {
+    /// The top component, which is the parent of all nodes of the topmost layer
+    #[default]
+    Top,
+    Memory(ArenaID),
+    Disk(StoreID),
+}
+
+/// The concrete address types of components, converted to and from `DynAddress` wherever a
+/// component meets a neighbouring layer
+pub trait LayerAddress: Address + Copy {
+    fn into_dyn(self) -> DynAddress;
+
+    fn from_dyn(address: DynAddress) -> Self;
+}
+
+impl LayerAddress for DynAddress {
+    fn into_dyn(self) -> DynAddress {
+        self
+    }
+
+    fn from_dyn(address: DynAddress) -> Self {
+        address
+    }
+}
+
+impl LayerAddress for ArenaID {
+    fn into_dyn(self) -> DynAddress {
+        DynAddress::Memory(self)
+    }
+
+    fn from_dyn(address: DynAddress) -> Self {
+        match address {
+            DynAddress::Memory(address) => address,
+            _ => unreachable!("{:?} is not the address of an in-memory node", address),
+        }
+    }
+}
+
+impl LayerAddress for StoreID {
+    fn into_dyn(self) -> DynAddress {
+        DynAddress::Disk(self)
+    }
+
+    fn from_dyn(address: DynAddress) -> Self {
+        match address {
+            DynAddress::Disk(address) => address,
+            _ => unreachable!("{:?} is not the address of a persisted node", address),
+        }
+    }
+}
+
+impl LayerAddress for () {
+    fn into_dyn(self) -> DynAddress {
+        DynAddress::Top
+    }
+
+    fn from_dyn(address: DynAddress) -> Self {
+        match address {
+            DynAddress::Top => (),
+            _ => unreachable!("{:?} is not the address of the top component", address),
+        }
+    }
+}
+
+pub type DynPropagateInsert<K> = PropagateInsert<K, DynAddress, DynAddress>;
+
+fn into_dyn<K, SA: LayerAddress, PA: LayerAddress>(
+    prop: PropagateInsert<K, SA, PA>,
+) -> DynPropagateInsert<K> {
+    match prop {
+        PropagateInsert::Single(key, address, parent) => {
+            PropagateInsert::Single(key, address.into_dyn(), parent.into_dyn())
+        }
+        PropagateInsert::Replace(start, end) => {
+            PropagateInsert::Replace(start.into_dyn(), end.into_dyn())
+        }
+    }
+}
+
+fn from_dyn<K, SA: LayerAddress, PA: LayerAddress>(
+    prop: DynPropagateInsert<K>,
+) -> PropagateInsert<K, SA, PA> {
+    match prop {
+        PropagateInsert::Single(key, address, parent) => {
+            PropagateInsert::Single(key, SA::from_dyn(address), PA::from_dyn(parent))
+        }
+        PropagateInsert::Replace(start, end) => {
+            PropagateInsert::Replace(PA::from_dyn(start), PA::from_dyn(end))
+        }
+    }
+}
+
+// -------------------------------------------------------
+//                  Object safe layers
+// -------------------------------------------------------
+
+/// An object safe `NodeLayer` over `DynAddress`
+pub trait DynNodeLayer<K> {
+    fn parent(&self, ptr: DynAddress) -> Option<DynAddress>;
+
+    fn set_parent(&mut self, ptr: DynAddress, parent: DynAddress);
+
+    fn lower_bound(&self, ptr: DynAddress) -> K;
+
+    fn next(&self, ptr: DynAddress) -> Option<DynAddress>;
+
+    fn prev(&self, ptr: DynAddress) -> Option<DynAddress>;
+
+    fn first(&self) -> DynAddress;
+
+    fn last(&self) -> DynAddress;
+
+    fn take_error(&self) -> crate::Result<()>;
+}
+
+pub trait DynTop<K> {
+    fn search(&self, base: &dyn DynNodeLayer<K>, key: &K) -> DynAddress;
+
+    fn insert(&mut self, base: &mut dyn DynNodeLayer<K>, prop: DynPropagateInsert<K>);
+}
+
+/// An internal component of any kind, with the errors of in-memory components lifted
+pub trait DynInternal<K>: DynNodeLayer<K> {
+    fn search(
+        &self,
+        base: &dyn DynNodeLayer<K>,
+        ptr: DynAddress,
+        key: &K,
+    ) -> crate::Result<DynAddress>;
+
+    fn insert(
+        &mut self,
+        base: &mut dyn DynNodeLayer<K>,
+        prop: DynPropagateInsert<K>,
+    ) -> crate::Result<Option<DynPropagateInsert<K>>>;
+
+    /// The component as a disk component, if it persists its nodes
+    fn disk(&self) -> Option<&dyn DiskComponent>;
+}
+
+/// A base component of any kind, with the errors of in-memory components lifted
+pub trait DynBase<K, V>: DynNodeLayer<K> {
+    fn search(&self, ptr: DynAddress, key: &K) -> crate::Result<Option<V>>;
+
+    fn insert(
+        &mut self,
+        ptr: DynAddress,
+        key: K,
+        value: V,
+    ) -> crate::Result<Option<DynPropagateInsert<K>>>;
+
+    fn entries(&self) -> Box<dyn Iterator<Item = crate::Result<(K, V)>> + '_>;
+
+    /// The component as a disk component, if it persists its nodes
+    fn disk(&self) -> Option<&dyn DiskComponent>;
+}
+
+// -------------------------------------------------------
+//                  Views of neighbouring layers
+// -------------------------------------------------------
+
+enum LayerRef<'a, K> {
+    Shared(&'a dyn DynNodeLayer<K>),
+    Unique(&'a mut dyn DynNodeLayer<K>),
+}
+
+/// A neighbouring layer seen with the concrete address types a component expects of it
+pub struct View<'a, K, SA, PA> {
+    layer: LayerRef<'a, K>,
+    _ph: PhantomData<(SA, PA)>,
+}
+
+impl<'a, K, SA, PA> View<'a, K, SA, PA> {
+    fn new(layer: &'a dyn DynNodeLayer<K>) -> Self {
+        Self {
+            layer: LayerRef::Shared(layer),
+            _ph: PhantomData,
+        }
+    }
+
+    fn new_mut(layer: &'a mut dyn DynNodeLayer<K>) -> Self {
+        Self {
+            layer: LayerRef::Unique(layer),
+            _ph: PhantomData,
+        }
+    }
+
+    fn get(&self) -> &dyn DynNodeLayer<K> {
+        match &self.layer {
+            LayerRef::Shared(layer) => *layer,
+            LayerRef::Unique(layer) => *layer,
+        }
+    }
+}
+
+impl<K, SA, PA> NodeLayer<K, SA, PA> for View<'_, K, SA, PA>
+where
+    SA: LayerAddress,
+    PA: LayerAddress,
+{
+    fn parent(&self, ptr: SA) -> Option<PA> {
+        self.get().parent(ptr.into_dyn()).map(PA::from_dyn)
+    }
+
+    fn set_parent(&mut self, ptr: SA, parent: PA) {
+        match &mut self.layer {
+            LayerRef::Unique(layer) => layer.set_parent(ptr.into_dyn(), parent.into_dyn()),
+            LayerRef::Shared(_) => unreachable!("Searches never modify the base layer"),
+        }
+    }
+
+    fn lower_bound(&self, ptr: SA) -> K {
+        self.get().lower_bound(ptr.into_dyn())
+    }
+
+    fn next(&self, ptr: SA) -> Option<SA> {
+        self.get().next(ptr.into_dyn()).map(SA::from_dyn)
+    }
+
+    fn prev(&self, ptr: SA) -> Option<SA> {
+        self.get().prev(ptr.into_dyn()).map(SA::from_dyn)
+    }
+
+    fn first(&self) -> SA {
+        SA::from_dyn(self.get().first())
+    }
+
+    fn last(&self) -> SA {
+        SA::from_dyn(self.get().last())
+    }
+
+    fn take_error(&self) -> crate::Result<()> {
+        self.get().take_error()
+    }
+}
+
+// -------------------------------------------------------
+//                  Wrapped components
+// -------------------------------------------------------
+
+/// Components in memory, and components persisting their nodes below an in-memory layer or
+/// below another persisted layer, which implement different component traits
+pub struct InMemory;
+pub struct Boundary;
+pub struct Deep;
+
+/// An existing component behind the object safe traits, where `BA`, `SA` and `PA` are the
+/// concrete addresses of its base layer, its own nodes and its parents
+pub struct Wrapped<C, BA, SA, PA, Kind> {
+    inner: C,
+    _ph: PhantomData<(BA, SA, PA, Kind)>,
+}
+
+impl<C, BA, SA, PA, Kind> Wrapped<C, BA, SA, PA, Kind> {
+    fn new(inner: C) -> Self {
+        Self {
+            inner,
+            _ph: PhantomData,
+        }
+    }
+}
+
+impl<K, C, BA, SA, PA, Kind> DynNodeLayer<K> for Wrapped<C, BA, SA, PA, Kind>
+where
+    C: NodeLayer<K, SA, PA>,
+    SA: LayerAddress,
+    PA: LayerAddress,
+{
+    fn parent(&self, ptr: DynAddress) -> Option<DynAddress> {
+        self.inner.parent(SA::from_dyn(ptr)).map(PA::into_dyn)
+    }
+
+    fn set_parent(&mut self, ptr: DynAddress, parent: DynAddress) {
+        self.inner
+            .set_parent(SA::from_dyn(ptr), PA::from_dyn(parent))
+    }
+
+    fn lower_bound(&self, ptr: DynAddress) -> K {
+        self.inner.lower_bound(SA::from_dyn(ptr))
+    }
+
+    fn next(&self, ptr: DynAddress) -> Option<DynAddress> {
+        self.inner.next(SA::from_dyn(ptr)).map(SA::into_dyn)
+    }
+
+    fn prev(&self, ptr: DynAddress) -> Option<DynAddress> {
+        self.inner.prev(SA::from_dyn(ptr)).map(SA::into_dyn)
+    }
+
+    fn first(&self) -> DynAddress {
+        self.inner.first().into_dyn()
+    }
+
+    fn last(&self) -> DynAddress {
+        self.inner.last().into_dyn()
+    }
+
+    fn take_error(&self) -> crate::Result<()> {
+        self.inner.take_error()
+    }
+}
+
+impl<K, C, BA> DynTop<K> for Wrapped<C, BA, (), (), InMemory>
+where
+    C: for<'a> TopComponent<K, View<'a, K, BA, ()>, BA, ()>,
+    BA: LayerAddress,
+    K: Key,
+{
+    fn search(&self, base: &dyn DynNodeLayer<K>, key: &K) -> DynAddress {
+        self.inner.search(&View::new(base), key).into_dyn()
+    }
+
+    fn insert(&mut self, base: &mut dyn DynNodeLayer<K>, prop: DynPropagateInsert<K>) {
+        self.inner.insert(&mut View::new_mut(base), from_dyn(prop))
+    }
+}
+
+impl<K, C, BA, SA, PA> DynInternal<K> for Wrapped<C, BA, SA, PA, InMemory>
+where
+    C: for<'a> InternalComponent<K, View<'a, K, BA, SA>, BA, SA, PA>,
+    BA: LayerAddress,
+    SA: LayerAddress,
+    PA: LayerAddress,
+    K: Key,
+{
+    fn search(
+        &self,
+        base: &dyn DynNodeLayer<K>,
+        ptr: DynAddress,
+        key: &K,
+    ) -> crate::Result<DynAddress> {
+        Ok(self
+            .inner
+            .search(&View::new(base), SA::from_dyn(ptr), key)
+            .into_dyn())
+    }
+
+    fn insert(
+        &mut self,
+        base: &mut dyn DynNodeLayer<K>,
+        prop: DynPropagateInsert<K>,
+    ) -> crate::Result<Option<DynPropagateInsert<K>>> {
+        Ok(self
+            .inner
+            .insert(&mut View::new_mut(base), from_dyn(prop))
+            .map(into_dyn))
+    }
+
+    fn disk(&self) -> Option<&dyn DiskComponent> {
+        None
+    }
+}
+
+impl<K, C, BA, SA, PA> DynInternal<K> for Wrapped<C, BA, SA, PA, Boundary>
+where
+    C: for<'a> BoundaryDiskInternalComponent<K, View<'a, K, BA, SA>, BA, SA, PA>,
+    BA: Persisted + LayerAddress,
+    SA: Persisted + LayerAddress,
+    PA: LayerAddress,
+    K: Key,
+{
+    fn search(
+        &self,
+        base: &dyn DynNodeLayer<K>,
+        ptr: DynAddress,
+        key: &K,
+    ) -> crate::Result<DynAddress> {
+        Ok(self
+            .inner
+            .search(&View::new(base), SA::from_dyn(ptr), key)?
+            .into_dyn())
+    }
+
+    fn insert(
+        &mut self,
+        base: &mut dyn DynNodeLayer<K>,
+        prop: DynPropagateInsert<K>,
+    ) -> crate::Result<Option<DynPropagateInsert<K>>> {
+        Ok(self
+            .inner
+            .insert(&mut View::new_mut(base), from_dyn(prop))?
+            .map(into_dyn))
+    }
+
+    fn disk(&self) -> Option<&dyn DiskComponent> {
+        Some(&self.inner)
+    }
+}
+
+impl<K, C, BA, SA, PA> DynInternal<K> for Wrapped<C, BA, SA, PA, Deep>
+where
+    C: for<'a> DeepDiskInternalComponent<K, View<'a, K, BA, SA>, BA, SA, PA>,
+    BA: Persisted + LayerAddress,
+    SA: Persisted + LayerAddress,
+    PA: Persisted + LayerAddress,
+    K: Key,
+{
+    fn search(
+        &self,
+        base: &dyn DynNodeLayer<K>,
+        ptr: DynAddress,
+        key: &K,
+    ) -> crate::Result<DynAddress> {
+        Ok(self
+            .inner
+            .search(&View::new(base), SA::from_dyn(ptr), key)?
+            .into_dyn())
+    }
+
+    fn insert(
+        &mut self,
+        base: &mut dyn DynNodeLayer<K>,
+        prop: DynPropagateInsert<K>,
+    ) -> crate::Result<Option<DynPropagateInsert<K>>> {
+        Ok(self
+            .inner
+            .insert(&mut View::new_mut(base), from_dyn(prop))?
+            .map(into_dyn))
+    }
+
+    fn disk(&self) -> Option<&dyn DiskComponent> {
+        Some(&self.inner)
+    }
+}
+
+impl<K, V, C, SA, PA> DynBase<K, V> for Wrapped<C, (), SA, PA, InMemory>
+where
+    C: BaseComponent<K, V, SA, PA>,
+    SA: LayerAddress,
+    PA: LayerAddress,
+    K: Key,
+    V: Value,
+{
+    fn search(&self, ptr: DynAddress, key: &K) -> crate::Result<Option<V>> {
+        Ok(self.inner.search(SA::from_dyn(ptr), key))
+    }
+
+    fn insert(
+        &mut self,
+        ptr: DynAddress,
+        key: K,
+        value: V,
+    ) -> crate::Result<Option<DynPropagateInsert<K>>> {
+        Ok(self
+            .inner
+            .insert(SA::from_dyn(ptr), key, value)
+            .map(into_dyn))
+    }
+
+    fn entries(&self) -> Box<dyn Iterator<Item = crate::Result<(K, V)>> + '_> {
+        Box::new(self.inner.entries().map(Ok))
+    }
+
+    fn disk(&self) -> Option<&dyn DiskComponent> {
+        None
+    }
+}
+
+impl<K, V, C, SA, PA> DynBase<K, V> for Wrapped<C, (), SA, PA, Boundary>
+where
+    C: BoundaryDiskBaseComponent<K, V, SA, PA>,
+    SA: Persisted + LayerAddress,
+    PA: LayerAddress,
+    K: Key,
+{
+    fn search(&self, ptr: DynAddress, key: &K) -> crate::Result<Option<V>> {
+        self.inner.search(SA::from_dyn(ptr), key)
+    }
+
+    fn insert(
+        &mut self,
+        ptr: DynAddress,
+        key: K,
+        value: V,
+    ) -> crate::Result<Option<DynPropagateInsert<K>>> {
+        Ok(self
+            .inner
+            .insert(SA::from_dyn(ptr), key, value)?
+            .map(into_dyn))
+    }
+
+    fn entries(&self) -> Box<dyn Iterator<Item = crate::Result<(K, V)>> + '_> {
+        Box::new(self.inner.entries())
+    }
+
+    fn disk(&self) -> Option<&dyn DiskComponent> {
+        Some(&self.inner)
+    }
+}
+
+impl<K, V, C, SA, PA> DynBase<K, V> for Wrapped<C, (), SA, PA, Deep>
+where
+    C: DeepDiskBaseComponent<K, V, SA, PA>,
+    SA: Persisted + LayerAddress,
+    PA: Persisted + LayerAddress,
+    K: Key,
+{
+    fn search(&self, ptr: DynAddress, key: &K) -> crate::Result<Option<V>> {
+        self.inner.search(SA::from_dyn(ptr), key)
+    }
+
+    fn insert(
+        &mut self,
+        ptr: DynAddress,
+        key: K,
+        value: V,
+    ) -> crate::Result<Option<DynPropagateInsert<K>>> {
+        Ok(self
+            .inner
+            .insert(SA::from_dyn(ptr), key, value)?
+            .map(into_dyn))
+    }
+
+    fn entries(&self) -> Box<dyn Iterator<Item = crate::Result<(K, V)>> + '_> {
+        Box::new(self.inner.entries())
+    }
+
+    fn disk(&self) -> Option<&dyn DiskComponent> {
+        Some(&self.inner)
+    }
+}
+
+// -------------------------------------------------------
+//                  Construction
+// -------------------------------------------------------
+
+/// Where a component sits relative to the persisted layers, which decides the implementation
+/// used for it, as in `create_kv_store!`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Position {
+    InMemory,
+    Boundary,
+    Deep,
+}
+
+/// Positions of the components of a layout below the top component, from the top down
+pub fn positions(components: &[ComponentSpec]) -> Vec<Position> {
+    let mut in_persisted_region = false;
+
+    components
+        .iter()
+        .map(|component| {
+            let position = match (component.is_persisted(), in_persisted_region) {
+                (false, _) => Position::InMemory,
+                (true, false) => Position::Boundary,
+                (true, true) => Position::Deep,
+            };
+
+            in_persisted_region |= component.is_persisted();
+            position
+        })
+        .collect()
+}
+
+/// Load the base layer from `store`, or build it from `iter` if given. In-memory base layers
+/// without entries are created empty.
+pub fn base<K, V>(
+    component: ComponentSpec,
+    position: Position,
+    store: Option<&mut GlobalStore>,
+    ident: &str,
+    iter: Option<&mut dyn Iterator<Item = (K, V)>>,
+) -> crate::Result<Box<dyn DynBase<K, V>>>
+where
+    K: Persisted + Key,
+    V: Persisted + Value,
+{
+    Ok(match (component, position) {
+        (ComponentSpec::BTree { fanout, .. }, Position::InMemory) => with_fanout!(fanout, |F| {
+            let inner = match iter {
+                Some(iter) => BTreeBaseComponent::<K, V, F, DynAddress>::build(iter),
+                None => BTreeBaseComponent::<K, V, F, DynAddress>::empty(),
+            };
+
+            Box::new(Wrapped::<_, (), BTreeBaseAddress, DynAddress, InMemory>::new(inner))
+        }),
+        (ComponentSpec::PGM { epsilon }, _) => with_epsilon!(epsilon, |E| {
+            let inner = match iter {
+                Some(iter) => PGMBaseComponent::<K, V, E, DynAddress>::build(iter),
+                None => PGMBaseComponent::<K, V, E, DynAddress>::empty(),
+            };
+
+            Box::new(Wrapped::<_, (), PGMBaseAddress, DynAddress, InMemory>::new(
+                inner,
+            ))
+        }),
+        (ComponentSpec::BTree { fanout, .. }, Position::Boundary) => with_fanout!(fanout, |F| {
+            let store = store.expect("Persisted layers are only loaded with a store");
+            let inner = match iter {
+                Some(iter) => BoundaryDiskBTreeBaseComponent::<K, V, F, DynAddress>::build(
+                    store, ident, iter,
+                )?,
+                None => BoundaryDiskBTreeBaseComponent::<K, V, F, DynAddress>::load(store, ident)?,
+            };
+
+            Box::new(Wrapped::<_, (), StoreID, DynAddress, Boundary>::new(inner))
+        }),
+        (ComponentSpec::BTree { fanout, .. }, Position::Deep) => with_fanout!(fanout, |F| {
+            let store = store.expect("Persisted layers are only loaded with a store");
+            let inner = match iter {
+                Some(iter) => {
+                    DeepDiskBTreeBaseComponent::<K, V, F, StoreID>::build(store, ident, iter)?
+                }
+                None => DeepDiskBTreeBaseComponent::<K, V, F, StoreID>::load(store, ident)?,
+            };
+
+            Box::new(Wrapped::<_, (), StoreID, StoreID, Deep>::new(inner))
+        }),
+        (ComponentSpec::BTreeTop, _) => unreachable!("Rejected by `LayoutSpec::new`"),
+    })
+}
+
+/// Build an internal layer over `base`, loading it from `store` if it is persisted
+pub fn internal<K>(
+    component: ComponentSpec,
+    position: Position,
+    base: &mut dyn DynNodeLayer<K>,
+    store: Option<&mut GlobalStore>,
+    ident: &str,
+) -> crate::Result<Box<dyn DynInternal<K>>>
+where
+    K: Persisted + Key,
+{
+    Ok(match (component, position) {
+        (ComponentSpec::BTree { fanout, .. }, Position::InMemory) => with_fanout!(fanout, |F| {
+            let mut base = View::<K, DynAddress, BTreeInternalAddress>::new_mut(base);
+            let inner =
+                BTreeInternalComponent::<K, (), F, DynAddress, DynAddress>::build(&mut base);
+            base.take_error()?;
+
+            Box::new(Wrapped::<
+                _,
+                DynAddress,
+                BTreeInternalAddress,
+                DynAddress,
+                InMemory,
+            >::new(inner))
+        }),
+        (ComponentSpec::PGM { epsilon }, _) => with_epsilon!(epsilon, |E| {
+            let mut base = View::<K, DynAddress, PGMInternalAddress>::new_mut(base);
+            let inner = PGMInternalComponent::<K, (), E, DynAddress, DynAddress>::build(&mut base);
+            base.take_error()?;
+
+            Box::new(Wrapped::<
+                _,
+                DynAddress,
+                PGMInternalAddress,
+                DynAddress,
+                InMemory,
+            >::new(inner))
+        }),
+        (ComponentSpec::BTree { fanout, .. }, Position::Boundary) => with_fanout!(fanout, |F| {
+            let store = store.expect("Persisted layers are only loaded with a store");
+            let mut base = View::<K, StoreID, StoreID>::new_mut(base);
+            let inner = BoundaryDiskBTreeInternalComponent::<K, (), F, StoreID, DynAddress>::load(
+                &mut base, store, ident,
+            )?;
+
+            Box::new(Wrapped::<_, StoreID, StoreID, DynAddress, Boundary>::new(
+                inner,
+            ))
+        }),
+        (ComponentSpec::BTree { fanout, .. }, Position::Deep) => with_fanout!(fanout, |F| {
+            let store = store.expect("Persisted layers are only loaded with a store");
+            let mut base = View::<K, StoreID, StoreID>::new_mut(base);
+            let inner = DeepDiskBTreeInternalComponent::<K, (), F, StoreID, StoreID>::load(
+                &mut base, store, ident,
+            )?;
+
+            Box::new(Wrapped::<_, StoreID, StoreID, StoreID, Deep>::new(inner))
+        }),
+        (ComponentSpec::BTreeTop, _) => unreachable!("Rejected by `LayoutSpec::new`"),
+    })
+}
+
+/// Build the top component over `base`
+pub fn top<K>(base: &mut dyn DynNodeLayer<K>) -> crate::Result<Box<dyn DynTop<K>>>
+where
+    K: Key,
+{
+    let inner = BTreeTopComponent::<K, (), DynAddress>::build(&mut View::new_mut(base));
+    base.take_error()?;
+
+    Ok(Box::new(Wrapped::<_, DynAddress, (), (), InMemory>::new(
+        inner,
+    )))
+}
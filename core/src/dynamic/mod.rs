@@ -0,0 +1,19 @@
+//! Stores whose layout is chosen at runtime.
+//!
+//! `create_kv_store!` stacks components with concrete types known at compile time, so every
+//! layout has to be compiled in. `DynamicKVStore` instead assembles a store from a `LayoutSpec`,
+//! parsed from the same syntax as the `layout` of the macro. Every component is boxed behind a
+//! trait object and converts its addresses to and from `DynAddress` wherever it meets a
+//! neighbouring layer, which costs a virtual call per layer on every operation.
+//!
+//! Component parameters are const generics, so only the fanouts and epsilons in
+//! `SUPPORTED_FANOUTS` and `SUPPORTED_EPSILONS` are available, and memory-mapped and custom
+//! components as well as B-tree search strategies, fill factors and split policies are left to the
+//! macro.
+
+mod layer;
+mod spec;
+mod store;
+
+pub use spec::{ComponentSpec, LayoutSpec, SUPPORTED_EPSILONS, SUPPORTED_FANOUTS};
+pub use store::DynamicKVStore;
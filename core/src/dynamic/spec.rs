@@ -0,0 +1,612 @@
+use crate::common::storage::{Manifest, ManifestComponent};
+use crate::Error;
+use layout_file::{Component, Format, LayoutFile};
+use std::path::Path;
+use std::str::FromStr;
+
+/// Fanouts of B-tree components supported by dynamic layouts. Fanouts are const generic
+/// parameters of the components, so every supported one is compiled in ahead of time.
+pub const SUPPORTED_FANOUTS: &[usize] = &[4, 8, 16, 32, 64, 128, 256, 512];
+
+/// Epsilons of PGM components supported by dynamic layouts, see `SUPPORTED_FANOUTS`
+pub const SUPPORTED_EPSILONS: &[usize] = &[4, 8, 16, 32, 64, 128];
+
+/// A single component of a `LayoutSpec`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ComponentSpec {
+    BTreeTop,
+    BTree { fanout: usize, persist: bool },
+    PGM { epsilon: usize },
+}
+
+impl ComponentSpec {
+    pub fn is_persisted(&self) -> bool {
+        matches!(self, ComponentSpec::BTree { persist: true, .. })
+    }
+}
+
+// Matches the descriptions written to the manifest by `create_kv_store!`, so that dynamic stores
+// can open stores created with the same layout by the macro and vice versa
+impl std::fmt::Display for ComponentSpec {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::BTreeTop => write!(f, "btree_top()"),
+            Self::BTree {
+                fanout,
+                persist: false,
+            } => write!(f, "btree(fanout = {fanout})"),
+            Self::BTree { fanout, .. } => write!(f, "btree(fanout = {fanout}, persist)"),
+            Self::PGM { epsilon } => write!(f, "pgm(epsilon = {epsilon})"),
+        }
+    }
+}
+
+/// A layout chosen at runtime, with the same syntax as the `layout` of `create_kv_store!`:
+///
+/// ```
+/// use limousine_core::LayoutSpec;
+///
+/// let layout: LayoutSpec = "[btree_top(), btree(fanout = 8), btree(fanout = 64, persist)]"
+///     .parse()
+///     .unwrap();
+///
+/// assert!(layout.is_persisted());
+/// ```
+///
+/// Layouts can also be read from TOML or JSON files with `from_file`, in the format described
+/// in the `layout_file` crate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LayoutSpec {
+    // Components from the top layer down to the base layer
+    components: Vec<ComponentSpec>,
+
+    name: Option<String>,
+}
+
+impl LayoutSpec {
+    /// Validate a layout given from the top component down to the base component
+    pub fn new(components: Vec<ComponentSpec>) -> crate::Result<Self> {
+        Self::validate(&components).map_err(|(_, _, message)| Error::Layout(message))?;
+
+        Ok(Self {
+            components,
+            name: None,
+        })
+    }
+
+    /// Read a layout from a TOML or JSON file, chosen by its extension, or from any other file
+    /// in the syntax of `create_kv_store!`
+    pub fn from_file(path: impl AsRef<Path>) -> crate::Result<Self> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path)?;
+
+        match Format::from_path(path) {
+            Some(format) => Self::from_layout_file(&contents, format),
+            None => contents.parse(),
+        }
+    }
+
+    /// Parse a layout from the contents of a TOML layout file
+    pub fn from_toml(input: &str) -> crate::Result<Self> {
+        Self::from_layout_file(input, Format::Toml)
+    }
+
+    /// Parse a layout from the contents of a JSON layout file
+    pub fn from_json(input: &str) -> crate::Result<Self> {
+        Self::from_layout_file(input, Format::Json)
+    }
+
+    fn from_layout_file(input: &str, format: Format) -> crate::Result<Self> {
+        let file =
+            LayoutFile::parse(input, format).map_err(|error| Error::Layout(error.to_string()))?;
+
+        // Errors within a component point at the part of the file they came from
+        let components = file
+            .components
+            .iter()
+            .map(|component| {
+                let mut parser = Parser {
+                    input: component.source(),
+                    position: 0,
+                    origin: Some(component),
+                };
+
+                parser.component()
+            })
+            .collect::<crate::Result<Vec<_>>>()?;
+
+        Self::validate(&components).map_err(|(index, key, message)| {
+            let position = index.map(|index| {
+                let component = &file.components[index];
+                key.and_then(|key| component.key(key))
+                    .unwrap_or(component.position())
+            });
+
+            Error::Layout(layout_file::Error { message, position }.to_string())
+        })?;
+
+        Ok(Self {
+            components,
+            name: file.name.map(|(name, _)| name),
+        })
+    }
+
+    // Check a layout, returning the index of the offending component on failure if there is one,
+    // along with the attribute at fault if it is down to a single one
+    #[allow(clippy::type_complexity)]
+    fn validate(
+        components: &[ComponentSpec],
+    ) -> Result<(), (Option<usize>, Option<&'static str>, String)> {
+        let invalid = |index: Option<usize>, message: String| Err((index, None, message));
+
+        match components.first() {
+            None => return invalid(None, "Empty layout".to_string()),
+            Some(ComponentSpec::BTreeTop) => (),
+            Some(top) => return invalid(Some(0), format!("Invalid top component `{top}`")),
+        }
+
+        if components.len() == 1 {
+            return invalid(None, "No internal or base layers specified".to_string());
+        }
+
+        let mut in_persisted_region = false;
+        for (index, component) in components.iter().enumerate().skip(1) {
+            let invalid_key =
+                |key: &'static str, message: String| Err((Some(index), Some(key), message));
+            let invalid = |message: String| invalid(Some(index), message);
+
+            match *component {
+                ComponentSpec::BTreeTop => {
+                    return invalid("`btree_top()` can only be the top component".to_string())
+                }
+                ComponentSpec::BTree { fanout, .. } if !SUPPORTED_FANOUTS.contains(&fanout) => {
+                    return invalid_key(
+                        "fanout",
+                        format!(
+                            "Fanout {fanout} is not supported by dynamic layouts, expected one of \
+                         {SUPPORTED_FANOUTS:?}"
+                        ),
+                    )
+                }
+                ComponentSpec::PGM { epsilon } if !SUPPORTED_EPSILONS.contains(&epsilon) => {
+                    return invalid_key(
+                        "epsilon",
+                        format!(
+                        "Epsilon {epsilon} is not supported by dynamic layouts, expected one of \
+                         {SUPPORTED_EPSILONS:?}"
+                    ),
+                    )
+                }
+                _ => (),
+            }
+
+            if !component.is_persisted() && in_persisted_region {
+                return invalid(format!(
+                    "Cannot have the in-memory component `{component}` below a persisted \
+                     component"
+                ));
+            }
+
+            in_persisted_region |= component.is_persisted();
+        }
+
+        Ok(())
+    }
+
+    /// Components from the top layer down to the base layer
+    pub fn components(&self) -> &[ComponentSpec] {
+        &self.components
+    }
+
+    /// The name of the store, if the layout was read from a file which gives one
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    pub fn is_persisted(&self) -> bool {
+        self.components.iter().any(ComponentSpec::is_persisted)
+    }
+
+    /// The manifest of stores with this layout, identical to the one of a store generated by
+    /// `create_kv_store!` with the same layout
+    pub fn manifest<K, V>(&self) -> Manifest {
+        Manifest::new::<K, V>(
+            self.components
+                .iter()
+                .map(|component| ManifestComponent::new(component, component.is_persisted()))
+                .collect(),
+        )
+    }
+}
+
+impl std::fmt::Display for LayoutSpec {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[")?;
+
+        for (index, component) in self.components.iter().enumerate() {
+            if index > 0 {
+                write!(f, ", ")?;
+            }
+
+            write!(f, "{component}")?;
+        }
+
+        write!(f, "]")
+    }
+}
+
+impl FromStr for LayoutSpec {
+    type Err = Error;
+
+    fn from_str(input: &str) -> crate::Result<Self> {
+        let mut parser = Parser {
+            input,
+            position: 0,
+            origin: None,
+        };
+
+        let bracketed = parser.eat('[');
+        let mut components = Vec::new();
+
+        loop {
+            parser.skip_whitespace();
+            if parser.is_done() || (bracketed && parser.peek() == Some(']')) {
+                break;
+            }
+
+            components.push(parser.component()?);
+
+            if !parser.eat(',') {
+                break;
+            }
+        }
+
+        if bracketed {
+            parser.expect(']')?;
+        }
+
+        parser.skip_whitespace();
+        if !parser.is_done() {
+            return Err(parser.error("Unexpected trailing input"));
+        }
+
+        Self::new(components)
+    }
+}
+
+// A small recursive descent parser over the layout syntax, reporting errors by column, or by
+// where the layout file component the input was translated from was written
+struct Parser<'a> {
+    input: &'a str,
+    position: usize,
+    origin: Option<&'a Component>,
+}
+
+impl<'a> Parser<'a> {
+    fn error(&self, message: impl std::fmt::Display) -> Error {
+        if let Some(component) = self.origin {
+            let position = component.locate(self.position);
+            return Error::Layout(format!("{message} at {position}"));
+        }
+
+        let column = self.input[..self.position].chars().count() + 1;
+        Error::Layout(format!("{message} at column {column}"))
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.input[self.position..]
+    }
+
+    fn is_done(&self) -> bool {
+        self.rest().is_empty()
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn skip_whitespace(&mut self) {
+        self.position = self.input.len() - self.rest().trim_start().len();
+    }
+
+    fn eat(&mut self, token: char) -> bool {
+        self.skip_whitespace();
+
+        if self.peek() == Some(token) {
+            self.position += token.len_utf8();
+            return true;
+        }
+
+        false
+    }
+
+    fn expect(&mut self, token: char) -> crate::Result<()> {
+        match self.eat(token) {
+            true => Ok(()),
+            false => Err(self.error(format!("Expected `{token}`"))),
+        }
+    }
+
+    fn take_while(&mut self, predicate: impl Fn(char) -> bool) -> &'a str {
+        self.skip_whitespace();
+
+        let rest = self.rest();
+        let len = rest.find(|c| !predicate(c)).unwrap_or(rest.len());
+        self.position += len;
+
+        &rest[..len]
+    }
+
+    fn ident(&mut self) -> crate::Result<&'a str> {
+        match self.take_while(|c| c.is_ascii_alphanumeric() || c == '_') {
+            "" => Err(self.error("Expected an identifier")),
+            ident => Ok(ident),
+        }
+    }
+
+    fn integer(&mut self) -> crate::Result<usize> {
+        self.skip_whitespace();
+        let start = self.position;
+        let digits = self.take_while(|c| c.is_ascii_digit() || c == '_');
+
+        digits.replace('_', "").parse().map_err(|_| {
+            self.position = start;
+            self.error("Expected an integer")
+        })
+    }
+
+    fn component(&mut self) -> crate::Result<ComponentSpec> {
+        self.skip_whitespace();
+        let start = self.position;
+        let name = self.ident()?;
+        self.expect('(')?;
+
+        let mut fanout = None;
+        let mut epsilon = None;
+        let mut persist = false;
+
+        while !self.eat(')') {
+            self.skip_whitespace();
+            let attribute_start = self.position;
+            let attribute = self.ident()?;
+
+            let duplicate = match attribute {
+                "fanout" | "epsilon" => {
+                    self.expect('=')?;
+                    let value = self.integer()?;
+
+                    let slot = match attribute {
+                        "fanout" => &mut fanout,
+                        _ => &mut epsilon,
+                    };
+                    slot.replace(value).is_some()
+                }
+                "persist" => std::mem::replace(&mut persist, true),
+                "mmap" => {
+                    self.position = attribute_start;
+                    return Err(self.error(
+                        "Memory-mapped components require plain-old-data keys and are only \
+                         supported by `create_kv_store!`",
+                    ));
+                }
+                "search" | "fill" | "split" => {
+                    self.position = attribute_start;
+                    return Err(self.error(format!(
+                        "Dynamic layouts always use the default `{attribute}`, other values are \
+                         only supported by `create_kv_store!`"
+                    )));
+                }
+                _ => {
+                    self.position = attribute_start;
+                    return Err(self.error(format!("Unknown attribute `{attribute}`")));
+                }
+            };
+
+            if duplicate {
+                self.position = attribute_start;
+                return Err(self.error(format!("Attribute `{attribute}` is specified twice")));
+            }
+
+            if !self.eat(',') {
+                self.expect(')')?;
+                break;
+            }
+        }
+
+        let end = self.position;
+        self.position = start;
+
+        let component = match (name, fanout, epsilon) {
+            ("btree_top", None, None) if !persist => ComponentSpec::BTreeTop,
+            ("btree", Some(fanout), None) => ComponentSpec::BTree { fanout, persist },
+            ("pgm", None, Some(epsilon)) if !persist => ComponentSpec::PGM { epsilon },
+            ("btree_top" | "btree" | "pgm", ..) => {
+                return Err(self.error(format!("Invalid attributes for component `{name}`")))
+            }
+            ("custom", ..) => {
+                return Err(self.error("Custom components are only supported by `create_kv_store!`"))
+            }
+            _ => return Err(self.error(format!("Unknown component `{name}`"))),
+        };
+
+        self.position = end;
+        Ok(component)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(input: &str) -> crate::Result<LayoutSpec> {
+        input.parse()
+    }
+
+    fn error(input: &str) -> String {
+        match parse(input) {
+            Err(Error::Layout(message)) => message,
+            result => panic!("Expected a layout error, got {result:?}"),
+        }
+    }
+
+    #[test]
+    fn parse_layouts() {
+        let layout =
+            parse("[btree_top(), pgm(epsilon = 8), btree(fanout = 32, persist),]").unwrap();
+        assert_eq!(
+            layout.components(),
+            &[
+                ComponentSpec::BTreeTop,
+                ComponentSpec::PGM { epsilon: 8 },
+                ComponentSpec::BTree {
+                    fanout: 32,
+                    persist: true
+                },
+            ]
+        );
+
+        // Brackets are optional, and the display form parses back to the same layout
+        let unbracketed = parse("btree_top(), pgm(epsilon=8), btree(persist, fanout=32)").unwrap();
+        assert_eq!(unbracketed, layout);
+        assert_eq!(parse(&layout.to_string()).unwrap(), layout);
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(
+            error("btree_top(), btre(fanout = 8)"),
+            "Unknown component `btre` at column 14"
+        );
+        assert_eq!(
+            error("btree_top(), btree(fanout = 8, fast)"),
+            "Unknown attribute `fast` at column 32"
+        );
+        assert_eq!(
+            error("btree_top(), btree(fanout = x)"),
+            "Expected an integer at column 29"
+        );
+        assert_eq!(
+            error("[btree_top(), btree(fanout = 8)"),
+            "Expected `]` at column 32"
+        );
+        assert!(error("btree_top(), btree(fanout = 8, persist, mmap)").contains("column 41"));
+        assert!(error("btree_top(), btree(fanout = 8, search = simd)").contains("column 32"));
+        assert!(error("btree_top(), btree(fanout = 8, fill = 0.9)").contains("`fill`"));
+        assert!(error("btree_top(), btree(fanout = 8, split = rightmost)").contains("`split`"));
+        assert!(error("btree_top(), btree(fanout = 8, fanout = 8)").contains("twice"));
+        assert!(error("btree_top(), pgm(fanout = 8)").contains("Invalid attributes"));
+    }
+
+    #[test]
+    fn validate_layouts() {
+        assert!(error("").contains("Empty layout"));
+        assert!(error("btree(fanout = 8), btree(fanout = 8)").contains("Invalid top"));
+        assert!(error("btree_top()").contains("No internal or base"));
+        assert!(error("btree_top(), btree(fanout = 7)").contains("not supported"));
+        assert!(
+            error("btree_top(), btree(fanout = 8, persist), btree(fanout = 8)")
+                .contains("below a persisted")
+        );
+    }
+
+    #[test]
+    fn file_layouts() {
+        let toml = LayoutSpec::from_toml(
+            "name = \"Ingest\"\n\n\
+             [[components]]\ntype = \"btree_top\"\n\n\
+             [[components]]\ntype = \"btree\"\nfanout = 64\npersist = true\n",
+        )
+        .unwrap();
+
+        let json = LayoutSpec::from_json(
+            r#"{ "components": [{ "type": "btree_top" }, { "type": "btree", "fanout": 64, "persist": true }] }"#,
+        )
+        .unwrap();
+
+        assert_eq!(toml.components(), json.components());
+        assert_eq!(
+            toml.to_string(),
+            "[btree_top(), btree(fanout = 64, persist)]"
+        );
+        assert_eq!(toml.name(), Some("Ingest"));
+        assert_eq!(json.name(), None);
+
+        // Files without a structured extension use the syntax of the macro
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("ingest.json"), "{ \"components\": [] }").unwrap();
+        std::fs::write(dir.path().join(".layout"), "btree_top(), btree(fanout = 8)").unwrap();
+
+        assert!(matches!(
+            LayoutSpec::from_file(dir.path().join("ingest.json")),
+            Err(Error::Layout(message)) if message.contains("Empty layout")
+        ));
+        assert_eq!(
+            LayoutSpec::from_file(dir.path().join(".layout")).unwrap(),
+            parse("btree_top(), btree(fanout = 8)").unwrap()
+        );
+    }
+
+    #[test]
+    fn file_errors() {
+        let error = |input: &str| match LayoutSpec::from_toml(input) {
+            Err(Error::Layout(message)) => message,
+            result => panic!("Expected a layout error, got {result:?}"),
+        };
+
+        let layout =
+            |base: &str| format!("[[components]]\ntype = \"btree_top\"\n\n[[components]]\n{base}");
+
+        assert_eq!(
+            error(&layout("type = \"btree\"\nfanout = 8\nfast = true")),
+            "Unknown attribute `fast` at line 7, column 1"
+        );
+        assert_eq!(
+            error(&layout("type = \"btre\"\nfanout = 8")),
+            "Unknown component `btre` at line 5, column 8"
+        );
+        assert_eq!(
+            error(&layout("type = \"btree\"\nfanout = 7")),
+            "Fanout 7 is not supported by dynamic layouts, expected one of \
+             [4, 8, 16, 32, 64, 128, 256, 512] at line 6, column 1"
+        );
+        assert_eq!(
+            error(&layout("type = \"btree\"\nfanout = \"x\"")),
+            "Expected an integer at line 6, column 10"
+        );
+        assert_eq!(
+            error(&layout("type = \"btree\"\nfanout = 8\npersist = \"yes\"")),
+            "Expected `)` at line 7, column 1"
+        );
+        assert!(
+            error(&layout("type = \"btree\"\nfanout = 8\npersist = yes"))
+                .ends_with("at line 7, column 11")
+        );
+        assert!(LayoutSpec::from_json(
+            "{ \"components\": [{ \"type\": \"btree_top\" },\n  { \"type\": \"pgm\", \"epsilon\": 5 }] }"
+        )
+        .unwrap_err()
+        .to_string()
+        .ends_with("at line 2, column 20"));
+    }
+
+    #[test]
+    fn manifest_matches_macro() {
+        let layout = parse("btree_top(), btree(fanout = 8), btree(fanout = 64, persist)").unwrap();
+        let manifest = layout.manifest::<u64, u64>();
+
+        let descriptions: Vec<_> = manifest
+            .layout
+            .iter()
+            .map(|component| (component.description.as_str(), component.persisted))
+            .collect();
+
+        assert_eq!(
+            descriptions,
+            vec![
+                ("btree_top()", false),
+                ("btree(fanout = 8)", false),
+                ("btree(fanout = 64, persist)", true),
+            ]
+        );
+    }
+}
@@ -0,0 +1,461 @@
+use super::layer::{self, DynAddress, DynBase, DynInternal, DynNodeLayer, DynTop};
+use super::spec::LayoutSpec;
+use crate::common::storage::{GlobalStore, OpenOptions, StoreStats};
+use crate::{DiskComponent, EncryptionKey, Error, Key, Persisted, Value};
+use std::path::Path;
+
+/// A key-value store assembled at runtime from a `LayoutSpec`, without recompilation
+///
+/// Stores with an in-memory layout are created with `empty` or `build`, while stores with a
+/// persisted layout are opened from a path, just like the stores generated by `create_kv_store!`.
+/// Persisted layers use the same page format as the generated stores, so either can open a store
+/// created by the other with the same layout. In-memory layers are rebuilt whenever a dynamic
+/// store is opened, and never checkpointed.
+pub struct DynamicKVStore<K, V> {
+    layout: LayoutSpec,
+
+    // Layers are dropped before the store, since they hold references to it
+    top: Box<dyn DynTop<K>>,
+
+    // Internal layers from the base up
+    internal: Vec<Box<dyn DynInternal<K>>>,
+    base: Box<dyn DynBase<K, V>>,
+
+    store: Option<GlobalStore>,
+}
+
+impl<K, V> DynamicKVStore<K, V>
+where
+    K: Persisted + Key,
+    V: Persisted + Value,
+{
+    fn assemble(
+        layout: &LayoutSpec,
+        mut store: Option<GlobalStore>,
+        iter: Option<&mut dyn Iterator<Item = (K, V)>>,
+    ) -> crate::Result<Self> {
+        // Components below the top, with their positions, from the base up
+        let components = &layout.components()[1..];
+        let mut components = components
+            .iter()
+            .copied()
+            .zip(layer::positions(components))
+            .rev()
+            .enumerate();
+
+        // Layers use the same identifiers as the fields of generated stores
+        let (_, (component, position)) = components.next().unwrap();
+        let mut base = layer::base(component, position, store.as_mut(), "C0", iter)?;
+
+        let mut internal: Vec<Box<dyn DynInternal<K>>> = Vec::new();
+        for (index, (component, position)) in components {
+            let below: &mut dyn DynNodeLayer<K> = match internal.last_mut() {
+                Some(layer) => layer.as_mut(),
+                None => base.as_mut(),
+            };
+
+            let ident = format!("C{}", index);
+            let layer = layer::internal(component, position, below, store.as_mut(), &ident)?;
+            internal.push(layer);
+        }
+
+        let below: &mut dyn DynNodeLayer<K> = match internal.last_mut() {
+            Some(layer) => layer.as_mut(),
+            None => base.as_mut(),
+        };
+        let top = layer::top(below)?;
+
+        Ok(Self {
+            layout: layout.clone(),
+            top,
+            internal,
+            base,
+            store,
+        })
+    }
+
+    fn check_in_memory(layout: &LayoutSpec) -> crate::Result<()> {
+        match layout.is_persisted() {
+            true => Err(Error::Layout(format!(
+                "Layout {} is persisted and must be opened from a path",
+                layout
+            ))),
+            false => Ok(()),
+        }
+    }
+
+    fn check_persisted(layout: &LayoutSpec) -> crate::Result<()> {
+        match layout.is_persisted() {
+            true => Ok(()),
+            false => Err(Error::Layout(format!(
+                "Layout {} is in-memory and cannot be opened from a path",
+                layout
+            ))),
+        }
+    }
+
+    /// Create an empty store with an in-memory layout
+    pub fn empty(layout: &LayoutSpec) -> crate::Result<Self> {
+        Self::check_in_memory(layout)?;
+        Self::assemble(layout, None, None)
+    }
+
+    /// Bulk build a store with an in-memory layout from entries sorted by key, without
+    /// duplicates
+    pub fn build(
+        layout: &LayoutSpec,
+        mut iter: impl Iterator<Item = (K, V)>,
+    ) -> crate::Result<Self> {
+        Self::check_in_memory(layout)?;
+        Self::assemble(layout, None, Some(&mut iter))
+    }
+
+    /// Open the store at `path` with a persisted layout according to `options`, failing if it
+    /// was created with a different layout
+    pub fn open_with(
+        path: impl AsRef<Path>,
+        layout: &LayoutSpec,
+        options: OpenOptions,
+    ) -> crate::Result<Self> {
+        Self::check_persisted(layout)?;
+
+        let store = GlobalStore::open(path, layout.manifest::<K, V>(), options)?;
+        Self::assemble(layout, Some(store), None)
+    }
+
+    /// Open an existing store, failing if there is none at `path`
+    pub fn open(path: impl AsRef<Path>, layout: &LayoutSpec) -> crate::Result<Self> {
+        Self::open_with(path, layout, OpenOptions::new())
+    }
+
+    /// Open the store at `path`, creating an empty one if it does not exist
+    pub fn open_or_create(path: impl AsRef<Path>, layout: &LayoutSpec) -> crate::Result<Self> {
+        Self::open_with(path, layout, OpenOptions::new().create(true))
+    }
+
+    /// Bulk build a new store at `path` from entries sorted by key, without duplicates. Fails if
+    /// a store already exists at `path`.
+    pub fn build_with(
+        path: impl AsRef<Path>,
+        layout: &LayoutSpec,
+        options: OpenOptions,
+        mut iter: impl Iterator<Item = (K, V)>,
+    ) -> crate::Result<Self> {
+        Self::check_persisted(layout)?;
+
+        let store = GlobalStore::open(path, layout.manifest::<K, V>(), options.create_new(true))?;
+        Self::assemble(layout, Some(store), Some(&mut iter))
+    }
+
+    /// The layout the store was assembled from
+    pub fn layout(&self) -> &LayoutSpec {
+        &self.layout
+    }
+
+    pub fn search(&self, key: K) -> crate::Result<Option<V>> {
+        let mut ptr = self.top.search(self.layer(self.internal.len()), &key);
+
+        for (index, layer) in self.internal.iter().enumerate().rev() {
+            ptr = layer.search(self.layer(index), ptr, &key)?;
+        }
+
+        self.base.search(ptr, &key)
+    }
+
+    /// Insert an entry, failing with `Error::ReadOnly` if the store was opened read-only
+    pub fn insert(&mut self, key: K, value: V) -> crate::Result<Option<V>> {
+        // Checked up front, so that in-memory layers are never modified without their pages
+        if self.store.as_ref().is_some_and(GlobalStore::is_read_only) {
+            return Err(Error::ReadOnly);
+        }
+
+        // Nodes to descend through, from the base up
+        let mut path = vec![DynAddress::Top; self.internal.len() + 1];
+        path[self.internal.len()] = self.top.search(self.layer(self.internal.len()), &key);
+
+        for (index, layer) in self.internal.iter().enumerate().rev() {
+            path[index] = layer.search(self.layer(index), path[index + 1], &key)?;
+        }
+
+        let result = self.base.search(path[0], &key)?;
+
+        // Parents of the boundary layer, the topmost persisted one, only live in memory and are
+        // not rebuilt on open. Record the parent of the node we descended through instead, which
+        // is always the one a split has to propagate to.
+        let persisted = self.disk_layers().count();
+        if persisted > 0 {
+            let boundary = persisted - 1;
+            let parent = path.get(boundary + 1).copied().unwrap_or(DynAddress::Top);
+            self.layer_mut(boundary).set_parent(path[boundary], parent);
+        }
+
+        let Some(mut prop) = self.base.insert(path[0], key, value)? else {
+            return Ok(result);
+        };
+
+        for index in 0..self.internal.len() {
+            let (below, above) = self.internal.split_at_mut(index);
+            let base: &mut dyn DynNodeLayer<K> = match below.last_mut() {
+                Some(layer) => layer.as_mut(),
+                None => self.base.as_mut(),
+            };
+
+            match above[0].insert(base, prop)? {
+                Some(next) => prop = next,
+                None => return Ok(result),
+            }
+        }
+
+        let base: &mut dyn DynNodeLayer<K> = match self.internal.last_mut() {
+            Some(layer) => layer.as_mut(),
+            None => self.base.as_mut(),
+        };
+        self.top.insert(base, prop);
+
+        Ok(result)
+    }
+
+    /// Iterate over all entries of the store in ascending key order
+    pub fn entries(&self) -> impl Iterator<Item = crate::Result<(K, V)>> + '_ {
+        self.base.entries()
+    }
+
+    /// Write all buffered pages to disk. In-memory stores have nothing to sync.
+    pub fn sync(&mut self) -> crate::Result<()> {
+        self.flush()?;
+
+        match self.store.as_mut() {
+            Some(store) => store.sync(),
+            None => Ok(()),
+        }
+    }
+
+    /// Reclaim the space left behind by overwritten and deleted pages, see
+    /// `PersistedKVStore::compact`
+    pub fn compact(&mut self) -> crate::Result<()> {
+        self.flush()?;
+        self.store_mut()?.compact()
+    }
+
+    /// Storage statistics of the store and its persisted layers, see `PersistedKVStore::stats`
+    pub fn stats(&self) -> crate::Result<StoreStats> {
+        // Measuring the layers flushes them, so the store has to be measured last
+        let layers = self
+            .disk_layers()
+            .map(DiskComponent::stats)
+            .collect::<crate::Result<Vec<_>>>()?;
+
+        let mut stats = self.store()?.stats();
+        stats.layers = layers;
+
+        Ok(stats)
+    }
+
+    /// Re-encrypt all pages of the store with `key`, see `PersistedKVStore::rotate_key`
+    pub fn rotate_key(&mut self, key: EncryptionKey) -> crate::Result<()> {
+        self.flush()?;
+        self.store_mut()?.rotate_key(key)
+    }
+
+    /// Copy all pages of the store into a new store at `path`, see `PersistedKVStore::backup_to`
+    pub fn backup_to(&mut self, path: impl AsRef<Path>) -> crate::Result<()> {
+        self.sync()?;
+        self.store()?.backup_to(path)
+    }
+
+    /// Write all pages to disk and release the store
+    pub fn close(self) -> crate::Result<()> {
+        self.flush()?;
+
+        let Self {
+            top,
+            internal,
+            base,
+            store,
+            ..
+        } = self;
+
+        // Components hold references to the store, so they must be released first
+        drop(top);
+        drop(internal);
+        drop(base);
+
+        match store {
+            Some(mut store) => {
+                store.sync()?;
+                store.close()
+            }
+            None => Ok(()),
+        }
+    }
+
+    // Layers by index from the base up, below the top component
+    fn layer(&self, index: usize) -> &dyn DynNodeLayer<K> {
+        match index {
+            0 => self.base.as_ref(),
+            _ => self.internal[index - 1].as_ref(),
+        }
+    }
+
+    fn layer_mut(&mut self, index: usize) -> &mut dyn DynNodeLayer<K> {
+        match index {
+            0 => self.base.as_mut(),
+            _ => self.internal[index - 1].as_mut(),
+        }
+    }
+
+    // Persisted layers, always below the in-memory ones, from the base up
+    fn disk_layers(&self) -> impl Iterator<Item = &dyn DiskComponent> {
+        std::iter::once(self.base.disk())
+            .chain(self.internal.iter().map(|layer| layer.disk()))
+            .flatten()
+    }
+
+    fn flush(&self) -> crate::Result<()> {
+        self.disk_layers().try_for_each(DiskComponent::flush)
+    }
+
+    fn store(&self) -> crate::Result<&GlobalStore> {
+        self.store
+            .as_ref()
+            .ok_or_else(|| Error::Layout(format!("Layout {} is not persisted", self.layout)))
+    }
+
+    fn store_mut(&mut self) -> crate::Result<&mut GlobalStore> {
+        match self.store.as_mut() {
+            Some(store) => Ok(store),
+            None => Err(Error::Layout(format!(
+                "Layout {} is not persisted",
+                self.layout
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dynamic::{SUPPORTED_EPSILONS, SUPPORTED_FANOUTS};
+
+    fn layout(layout: &str) -> LayoutSpec {
+        layout.parse().unwrap()
+    }
+
+    #[test]
+    fn in_memory_layouts() {
+        let mut layouts = vec![layout("btree_top(), btree(fanout = 8)")];
+        for fanout in SUPPORTED_FANOUTS {
+            layouts.push(layout(&format!(
+                "btree_top(), btree(fanout = {fanout}), btree(fanout = {fanout})"
+            )));
+        }
+
+        for layout in layouts {
+            let mut store = DynamicKVStore::<u64, u64>::empty(&layout).unwrap();
+            for key in 0..2_000 {
+                assert_eq!(store.insert(key * 2, key).unwrap(), None);
+            }
+            assert_eq!(store.insert(10, 0).unwrap(), Some(5));
+
+            for key in 0..2_000 {
+                assert_eq!(store.search(key * 2 + 1).unwrap(), None);
+            }
+            assert_eq!(store.search(10).unwrap(), Some(0));
+            assert_eq!(store.entries().count(), 2_000);
+
+            assert!(matches!(store.stats(), Err(Error::Layout(_))));
+            store.close().unwrap();
+        }
+    }
+
+    #[test]
+    fn learned_layouts() {
+        for epsilon in SUPPORTED_EPSILONS {
+            let layout = layout(&format!(
+                "btree_top(), pgm(epsilon = {epsilon}), pgm(epsilon = {epsilon})"
+            ));
+
+            let store =
+                DynamicKVStore::build(&layout, (0..5_000u64).map(|key| (key * 3, key))).unwrap();
+            for key in 0..5_000 {
+                assert_eq!(store.search(key * 3).unwrap(), Some(key));
+                assert_eq!(store.search(key * 3 + 1).unwrap(), None);
+            }
+        }
+    }
+
+    #[test]
+    fn persisted_layouts() {
+        let layouts = [
+            "btree_top(), btree(fanout = 16, persist)",
+            "btree_top(), btree(fanout = 8), btree(fanout = 8, persist), btree(fanout = 16, persist)",
+            "btree_top(), pgm(epsilon = 8), btree(fanout = 32, persist)",
+        ];
+
+        for layout in layouts.map(self::layout) {
+            let dir = tempfile::tempdir().unwrap();
+
+            let mut store =
+                DynamicKVStore::<u64, u64>::open_or_create(dir.path(), &layout).unwrap();
+            for key in 0..5_000 {
+                store.insert(key, key + 1).unwrap();
+            }
+            assert_eq!(
+                store.stats().unwrap().layers.len(),
+                layout
+                    .components()
+                    .iter()
+                    .filter(|c| c.is_persisted())
+                    .count()
+            );
+            store.close().unwrap();
+
+            let store = DynamicKVStore::<u64, u64>::open(dir.path(), &layout).unwrap();
+            for key in 0..5_000 {
+                assert_eq!(store.search(key).unwrap(), Some(key + 1));
+            }
+            assert_eq!(store.search(5_000).unwrap(), None);
+            store.close().unwrap();
+        }
+    }
+
+    #[test]
+    fn persisted_build() {
+        let dir = tempfile::tempdir().unwrap();
+        let layout = layout("btree_top(), btree(fanout = 8), btree(fanout = 32, persist)");
+
+        let entries = (0..5_000u64).map(|key| (key * 2, key));
+        let store =
+            DynamicKVStore::build_with(dir.path(), &layout, OpenOptions::new(), entries).unwrap();
+        assert_eq!(store.search(200).unwrap(), Some(100));
+        store.close().unwrap();
+
+        let entries = std::iter::empty();
+        let rebuilt = DynamicKVStore::<u64, u64>::build_with(
+            dir.path(),
+            &layout,
+            OpenOptions::new(),
+            entries,
+        );
+        assert!(rebuilt.is_err());
+    }
+
+    #[test]
+    fn layout_mismatch() {
+        let dir = tempfile::tempdir().unwrap();
+        let persisted = layout("btree_top(), btree(fanout = 16, persist)");
+        let in_memory = layout("btree_top(), btree(fanout = 16)");
+
+        DynamicKVStore::<u64, u64>::open_or_create(dir.path(), &persisted)
+            .unwrap()
+            .close()
+            .unwrap();
+
+        let other = layout("btree_top(), btree(fanout = 32, persist)");
+        let store = DynamicKVStore::<u64, u64>::open(dir.path(), &other);
+        assert!(matches!(store, Err(Error::Layout(_))));
+
+        assert!(DynamicKVStore::<u64, u64>::open(dir.path(), &in_memory).is_err());
+        assert!(DynamicKVStore::<u64, u64>::empty(&persisted).is_err());
+    }
+}
@@ -0,0 +1,141 @@
+use crate::common::storage::StoreID;
+
+/// Errors returned by persisted key-value stores and their components.
+#[derive(Debug)]
+pub enum Error {
+    /// The underlying page store failed to read or write
+    Io(std::io::Error),
+
+    /// A page failed checksum verification when it was read back
+    Corruption { store: String, page: StoreID },
+
+    /// A page was written with a page format version this build cannot read
+    UnsupportedVersion {
+        store: String,
+        page: StoreID,
+        version: u8,
+    },
+
+    /// A store catalog is missing an entry or points to a missing page
+    Catalog(String),
+
+    /// A page could not be serialized or deserialized
+    Serialization(bincode::Error),
+
+    /// The requested layout is invalid, or does not match the store on disk
+    Layout(String),
+
+    /// Another handle holds a lock on the store which conflicts with the requested access
+    Locked(String),
+
+    /// The store was opened read-only and cannot be modified
+    ReadOnly,
+
+    /// An archive is malformed or holds different key or value types
+    Archive(String),
+
+    /// A page could not be encrypted or decrypted, or encryption is not supported
+    Encryption(String),
+
+    /// The backend of the store does not support the requested operation
+    Unsupported(String),
+}
+
+impl Error {
+    /// Whether retrying the failed operation may succeed. Only I/O errors caused by the
+    /// environment and locks held by other handles are transient; corruption, catalog,
+    /// serialization and layout errors will fail again on retry.
+    pub fn is_transient(&self) -> bool {
+        use std::io::ErrorKind;
+
+        match self {
+            Error::Io(error) => matches!(
+                error.kind(),
+                ErrorKind::Interrupted
+                    | ErrorKind::WouldBlock
+                    | ErrorKind::TimedOut
+                    | ErrorKind::OutOfMemory
+            ),
+            Error::Locked(_) => true,
+            _ => false,
+        }
+    }
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Io(error) => write!(f, "I/O error: {}", error),
+            Error::Corruption { store, page } => write!(
+                f,
+                "Page {} of store `{}` is corrupted (checksum mismatch)",
+                page, store
+            ),
+            Error::UnsupportedVersion {
+                store,
+                page,
+                version,
+            } => write!(
+                f,
+                "Page {} of store `{}` has unsupported format version {}",
+                page, store, version
+            ),
+            Error::Catalog(message) => write!(f, "Catalog error: {}", message),
+            Error::Serialization(error) => write!(f, "Serialization error: {}", error),
+            Error::Layout(message) => write!(f, "Layout error: {}", message),
+            Error::Locked(message) => write!(f, "Lock error: {}", message),
+            Error::ReadOnly => write!(f, "Store was opened read-only"),
+            Error::Archive(message) => write!(f, "Archive error: {}", message),
+            Error::Encryption(message) => write!(f, "Encryption error: {}", message),
+            Error::Unsupported(message) => write!(f, "Unsupported operation: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(error) => Some(error),
+            Error::Serialization(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(error: std::io::Error) -> Self {
+        Error::Io(error)
+    }
+}
+
+impl From<bincode::Error> for Error {
+    fn from(error: bincode::Error) -> Self {
+        Error::Serialization(error)
+    }
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transient_errors() {
+        let interrupted = Error::from(std::io::Error::from(std::io::ErrorKind::Interrupted));
+        assert!(interrupted.is_transient());
+
+        let not_found = Error::from(std::io::Error::from(std::io::ErrorKind::NotFound));
+        assert!(!not_found.is_transient());
+
+        let corruption = Error::Corruption {
+            store: "test".to_string(),
+            page: 1,
+        };
+        assert!(!corruption.is_transient());
+
+        assert!(Error::Locked("test".to_string()).is_transient());
+        assert!(!Error::ReadOnly.is_transient());
+        assert!(!Error::Unsupported("test".to_string()).is_transient());
+    }
+}
@@ -1,6 +1,28 @@
-use crate::{Key, Persisted, Value};
+use crate::archive::{self, ArchiveReader};
+use crate::{EncryptionKey, Key, OpenOptions, Persisted, StoreStats, Value};
+use std::io::{Read, Write};
 use std::path::Path;
 
+// Feed fallible entries to `consume`, stopping at the first error and returning it instead
+fn try_consume<T, R>(
+    entries: impl Iterator<Item = crate::Result<T>>,
+    consume: impl FnOnce(&mut dyn Iterator<Item = T>) -> R,
+) -> crate::Result<R> {
+    let mut error = None;
+    let result = consume(&mut entries.map_while(|entry| match entry {
+        Ok(entry) => Some(entry),
+        Err(err) => {
+            error = Some(err);
+            None
+        }
+    }));
+
+    match error {
+        Some(error) => Err(error),
+        None => Ok(result),
+    }
+}
+
 pub trait KVStore<K, V>
 where
     K: Key,
@@ -13,6 +35,30 @@ where
     fn empty() -> Self;
 
     fn build(iter: impl Iterator<Item = (K, V)>) -> Self;
+
+    /// Iterate over all entries of the store in ascending key order
+    fn entries(&self) -> impl Iterator<Item = (K, V)> + '_;
+
+    /// Write all entries to `writer` as a portable archive, see `limousine_core::archive`.
+    /// Returns the number of entries written.
+    fn export_to(&self, writer: impl Write) -> crate::Result<u64>
+    where
+        K: Persisted,
+        V: Persisted,
+    {
+        archive::export(self.entries().map(Ok), writer)
+    }
+
+    /// Build a store from an archive written by `export_to`, possibly by a store with a
+    /// different layout
+    fn import_from(reader: impl Read) -> crate::Result<Self>
+    where
+        Self: Sized,
+        K: Persisted,
+        V: Persisted,
+    {
+        try_consume(ArchiveReader::new(reader)?, |entries| Self::build(entries))
+    }
 }
 
 pub trait PersistedKVStore<K, V>
@@ -23,7 +69,130 @@ where
 {
     fn search(&self, key: K) -> crate::Result<Option<V>>;
 
+    /// Insert an entry, failing with `Error::ReadOnly` if the store was opened read-only
     fn insert(&mut self, key: K, value: V) -> crate::Result<Option<V>>;
 
-    fn open(path: impl AsRef<Path>) -> crate::Result<Self>;
+    /// Open the store at `path` according to `options`, failing if it was created with a
+    /// different layout
+    fn open_with(path: impl AsRef<Path>, options: OpenOptions) -> crate::Result<Self>;
+
+    /// Open an existing store, failing if there is none at `path` or if it was created with a
+    /// different layout
+    fn open(path: impl AsRef<Path>) -> crate::Result<Self> {
+        Self::open_with(path, OpenOptions::new())
+    }
+
+    /// Open the store at `path`, creating an empty one if it does not exist
+    fn open_or_create(path: impl AsRef<Path>) -> crate::Result<Self> {
+        Self::open_with(path, OpenOptions::new().create(true))
+    }
+
+    /// Open an existing store without ever writing to it. Any number of read-only handles may
+    /// share a store, while opening it for writing fails with `Error::Locked` until they are
+    /// all gone. The marble backend cannot share its directory, so a marble store admits a
+    /// single read-only handle at a time, and further ones fail with `Error::Locked`.
+    fn open_read_only(path: impl AsRef<Path>) -> crate::Result<Self> {
+        Self::open_with(path, OpenOptions::new().read_only(true))
+    }
+
+    /// Bulk build a new store at `path` from entries sorted by key, without duplicates. Fails if
+    /// a store already exists at `path`.
+    fn build_with(
+        path: impl AsRef<Path>,
+        options: OpenOptions,
+        iter: impl Iterator<Item = (K, V)>,
+    ) -> crate::Result<Self>;
+
+    /// Bulk build a new store with the default options, see `build_with`
+    fn build(path: impl AsRef<Path>, iter: impl Iterator<Item = (K, V)>) -> crate::Result<Self> {
+        Self::build_with(path, OpenOptions::new(), iter)
+    }
+
+    /// Iterate over all entries of the store in ascending key order
+    fn entries(&self) -> impl Iterator<Item = crate::Result<(K, V)>> + '_;
+
+    /// Build a new store at `dst_path` from all entries of the store at `src_path`, which may use
+    /// a different layout. If reading the source fails midway, the partially built destination
+    /// is left behind and the error is returned.
+    fn migrate_from<S>(
+        src_path: impl AsRef<Path>,
+        dst_path: impl AsRef<Path>,
+    ) -> crate::Result<Self>
+    where
+        S: PersistedKVStore<K, V>,
+    {
+        Self::migrate_from_with::<S>(src_path, OpenOptions::new(), dst_path, OpenOptions::new())
+    }
+
+    /// Migrate the store at `src_path`, opened with `src_options`, into a new store at
+    /// `dst_path` built with `dst_options`, see `migrate_from`. The source is always opened
+    /// read-only, so it is never modified and cannot be migrated while a writer has it open.
+    fn migrate_from_with<S>(
+        src_path: impl AsRef<Path>,
+        src_options: OpenOptions,
+        dst_path: impl AsRef<Path>,
+        dst_options: OpenOptions,
+    ) -> crate::Result<Self>
+    where
+        S: PersistedKVStore<K, V>,
+    {
+        let source = S::open_with(src_path, src_options.read_only(true))?;
+        let store = try_consume(source.entries(), |entries| {
+            Self::build_with(dst_path, dst_options, entries)
+        })??;
+
+        source.close()?;
+        Ok(store)
+    }
+
+    /// Write all entries to `writer` as a portable archive, see `limousine_core::archive`.
+    /// Returns the number of entries written.
+    fn export_to(&self, writer: impl Write) -> crate::Result<u64> {
+        archive::export(self.entries(), writer)
+    }
+
+    /// Bulk build a new store at `path` from an archive written by `export_to`, possibly by a
+    /// store with a different layout. If the archive turns out to be malformed midway, the
+    /// partially built store is left behind and the error is returned.
+    fn import_from(path: impl AsRef<Path>, reader: impl Read) -> crate::Result<Self> {
+        try_consume(ArchiveReader::new(reader)?, |entries| {
+            Self::build(path, entries)
+        })?
+    }
+
+    /// Flush all persisted layers and force them to durable storage
+    fn sync(&mut self) -> crate::Result<()>;
+
+    /// Sync the store and reclaim the space left behind by overwritten and deleted pages. Stores
+    /// are also compacted on `close` and `sync` according to `OpenOptions::compaction`.
+    fn compact(&mut self) -> crate::Result<()>;
+
+    /// Flush all persisted layers and report the pages and space occupied by the store and by
+    /// each of its persisted layers
+    fn stats(&self) -> crate::Result<StoreStats>;
+
+    /// Re-encrypt every page of a store opened with `OpenOptions::encryption` with `key`, which
+    /// is required to open the store from then on
+    fn rotate_key(&mut self, key: EncryptionKey) -> crate::Result<()>;
+
+    /// Sync the store and copy its pages to a new store at `path`, which must not exist yet. The
+    /// store remains usable afterwards, and the backup can be opened like any other store.
+    fn backup_to(&mut self, path: impl AsRef<Path>) -> crate::Result<()>;
+
+    /// Replace the store at `path` with the backup at `backup` according to `options`, then open
+    /// it. Fails without touching `path` if the backup has a different layout.
+    fn restore_from_with(
+        backup: impl AsRef<Path>,
+        path: impl AsRef<Path>,
+        options: OpenOptions,
+    ) -> crate::Result<Self>;
+
+    /// Restore a backup with the default options, see `restore_from_with`
+    fn restore_from(backup: impl AsRef<Path>, path: impl AsRef<Path>) -> crate::Result<Self> {
+        Self::restore_from_with(backup, path, OpenOptions::new())
+    }
+
+    /// Sync and shut down the store, reporting any error instead of ignoring it on drop. The
+    /// in-memory layers are checkpointed, so that the next open can skip rebuilding them.
+    fn close(self) -> crate::Result<()>;
 }
@@ -2,6 +2,7 @@ use learned_index_segmentation::LinearModel;
 
 use crate::{Key, KeyBounded, StaticBounded};
 use gapped_array::GappedKVArray;
+use serde::{Deserialize, Serialize};
 
 impl<K: StaticBounded, const EPSILON: usize> KeyBounded<K> for LinearModel<K, EPSILON> {
     fn lower_bound(&self) -> &K {
@@ -9,7 +10,7 @@ impl<K: StaticBounded, const EPSILON: usize> KeyBounded<K> for LinearModel<K, EP
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct PGMNode<K: Key, V, const EPSILON: usize> {
     gapped: GappedKVArray<K, V>,
     model: LinearModel<K, EPSILON>,
@@ -56,6 +57,20 @@ impl<K: Key, V, const EPSILON: usize> PGMNode<K, V, EPSILON> {
         }
     }
 
+    pub fn entries(&self) -> impl Iterator<Item = (&K, &V)> + '_ {
+        self.gapped.iter()
+    }
+
+    /// Number of entries in the node, excluding gaps
+    pub fn size(&self) -> usize {
+        self.gapped.size()
+    }
+
+    /// Bytes allocated by the gapped array of the node, outside of the node itself
+    pub fn heap_bytes(&self) -> usize {
+        self.gapped.size_in_bytes() as usize - std::mem::size_of::<GappedKVArray<K, V>>()
+    }
+
     pub fn grow_insert(&mut self, entry: (K, V)) {
         if self.gapped.density() >= 0.8 {
             let scale_factor = 2.0;
@@ -7,10 +7,13 @@ use std::ops::Bound;
 use learned_index_segmentation::linear_simple_segmentation;
 
 use crate::common::list::memory::*;
+use crate::describe::LayerSize;
 use crate::iter::Iter;
 use crate::learned::node::PGMNode;
 use crate::{impl_node_layer, Address, Key, NodeLayer};
+use serde::{Deserialize, Serialize};
 
+#[derive(Serialize, Deserialize)]
 pub struct MemoryPGMLayer<K: Key, V, const EPSILON: usize, PA> {
     inner: MemoryList<PGMNode<K, V, EPSILON>, PA>,
 }
@@ -60,6 +63,20 @@ where
         }
     }
 
+    pub fn nodes(&self) -> impl Iterator<Item = &PGMNode<K, V, EPSILON>> + '_ {
+        self.inner.nodes()
+    }
+
+    pub fn measure(&self) -> LayerSize {
+        LayerSize {
+            nodes: self.inner.len(),
+            keys: self.nodes().map(|node| node.size()).sum(),
+            memory_bytes: self.inner.memory_bytes()
+                + self.nodes().map(|node| node.heap_bytes()).sum::<usize>(),
+            disk_bytes: 0,
+        }
+    }
+
     pub fn fill_will_parent<B: NodeLayer<K, V, ArenaID>>(&mut self, base: &mut B)
     where
         V: Address,
@@ -1,11 +1,12 @@
 use num::PrimInt;
 
 use crate::{
-    common::list::memory::ArenaID, impl_node_layer, Address, BaseComponent, InternalComponent, Key,
-    NodeLayer, PropagateInsert, StaticBounded, Value,
+    common::list::memory::ArenaID, describe::LayerSize, impl_node_layer, Address, BaseComponent,
+    InternalComponent, Key, Measure, NodeLayer, PropagateInsert, Value,
 };
 
 use self::layer::MemoryPGMLayer;
+use serde::{Deserialize, Serialize};
 
 mod layer;
 
@@ -15,6 +16,7 @@ mod layer;
 
 pub type PGMInternalAddress = ArenaID;
 
+#[derive(Serialize, Deserialize)]
 pub struct PGMInternalComponent<K: Key, X: 'static, const EPSILON: usize, BA, PA> {
     inner: MemoryPGMLayer<K, BA, EPSILON, PA>,
     _ph: std::marker::PhantomData<X>,
@@ -23,13 +25,22 @@ pub struct PGMInternalComponent<K: Key, X: 'static, const EPSILON: usize, BA, PA
 impl<K, X, const EPSILON: usize, BA, PA> NodeLayer<K, PGMInternalAddress, PA>
     for PGMInternalComponent<K, X, EPSILON, BA, PA>
 where
-    K: Clone + Ord + StaticBounded + PrimInt,
+    K: Key,
     BA: Address,
     PA: Address,
 {
     impl_node_layer!(ArenaID, PA);
 }
 
+impl<K, X, const EPSILON: usize, BA, PA> Measure for PGMInternalComponent<K, X, EPSILON, BA, PA>
+where
+    K: Key,
+{
+    fn measure(&self) -> crate::Result<LayerSize> {
+        Ok(self.inner.measure())
+    }
+}
+
 impl<K, X, BA, PA, B: NodeLayer<K, BA, PGMInternalAddress>, const EPSILON: usize>
     InternalComponent<K, B, BA, PGMInternalAddress, PA>
     for PGMInternalComponent<K, X, EPSILON, BA, PA>
@@ -90,6 +101,15 @@ where
     impl_node_layer!(ArenaID, PA);
 }
 
+impl<K, V, const EPSILON: usize, PA> Measure for PGMBaseComponent<K, V, EPSILON, PA>
+where
+    K: Key,
+{
+    fn measure(&self) -> crate::Result<LayerSize> {
+        Ok(self.inner.measure())
+    }
+}
+
 impl<K, V, const EPSILON: usize, PA: 'static> BaseComponent<K, V, PGMBaseAddress, PA>
     for PGMBaseComponent<K, V, EPSILON, PA>
 where
@@ -126,4 +146,10 @@ where
 
         Self { inner: result }
     }
+
+    fn entries(&self) -> impl Iterator<Item = (K, V)> + '_ {
+        self.inner
+            .nodes()
+            .flat_map(|node| node.entries().map(|(key, value)| (*key, value.clone())))
+    }
 }
@@ -29,6 +29,13 @@ where
     /// Last node in the current node layer
     fn last(&self) -> SA;
 
+    /// Take the first error hit by the methods above since the last call. Layers reading their
+    /// nodes from disk record I/O and decoding errors there and end the traversal, as they have no
+    /// other way to report them.
+    fn take_error(&self) -> crate::Result<()> {
+        Ok(())
+    }
+
     /// An immutable iterator over the layer, returning (Key, Address) pairs
     fn range(&self, start: Bound<SA>, end: Bound<SA>) -> Iter<'_, K, Self, SA, PA> {
         Iter::range(self, start, end)
@@ -73,6 +80,10 @@ macro_rules! impl_node_layer {
         fn last(&self) -> $SA {
             self.inner.last()
         }
+
+        fn take_error(&self) -> crate::Result<()> {
+            self.inner.take_error()
+        }
     };
 }
 
@@ -0,0 +1,182 @@
+//! Expected shape and size of a layout, estimated by `create_kv_store!` when generating a store.
+//!
+//! Estimates assume a million keys, B-tree nodes filled to the usual 69% of their fanout and
+//! learned segments covering `2 * epsilon` keys. Sizes only count keys and entries, ignoring the
+//! bookkeeping of every node, so they are meant for comparing layouts rather than for sizing
+//! machines. The cost of custom components is unknown, so the estimates of layers above them are
+//! left out.
+
+use std::fmt;
+
+/// Estimates for a single layer of a layout
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LayerReport {
+    /// The component, described the same way as in `create_kv_store!`
+    pub component: &'static str,
+
+    /// Fanout of B-tree components
+    pub fanout: Option<usize>,
+
+    /// Epsilon of learned components
+    pub epsilon: Option<usize>,
+
+    /// Whether the layer is stored on disk
+    pub persisted: bool,
+
+    /// Estimated number of nodes per million keys
+    pub nodes: Option<usize>,
+
+    /// Estimated bytes per million keys, on disk for persisted layers and in memory otherwise
+    pub bytes: Option<usize>,
+}
+
+/// Estimates for a whole layout, generated as `LAYOUT_REPORT` with `report: true`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LayoutReport {
+    /// Layers from the top layer down to the base layer
+    pub layers: &'static [LayerReport],
+
+    /// Expected number of layers holding more than one node for a million keys, including the
+    /// top layer. Any layer above those only adds a step to every lookup.
+    pub depth: usize,
+}
+
+impl LayoutReport {
+    /// Estimated bytes of memory per million keys, or `None` if a layer is unknown
+    pub fn memory_per_million_keys(&self) -> Option<usize> {
+        self.total(false)
+    }
+
+    /// Estimated bytes on disk per million keys, or `None` if a layer is unknown
+    pub fn disk_per_million_keys(&self) -> Option<usize> {
+        self.total(true)
+    }
+
+    fn total(&self, persisted: bool) -> Option<usize> {
+        self.layers
+            .iter()
+            .filter(|layer| layer.persisted == persisted)
+            .map(|layer| layer.bytes)
+            .sum()
+    }
+}
+
+impl fmt::Display for LayoutReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let width = self
+            .layers
+            .iter()
+            .map(|layer| layer.component.len())
+            .max()
+            .unwrap_or_default();
+
+        writeln!(f, "Expected depth for a million keys: {}", self.depth)?;
+
+        for layer in self.layers {
+            let place = if layer.persisted { "disk" } else { "memory" };
+
+            writeln!(
+                f,
+                "  {:width$}  {:>9} nodes  {:>10} {place}",
+                layer.component,
+                estimate(layer.nodes, |nodes| nodes.to_string()),
+                estimate(layer.bytes, bytes),
+            )?;
+        }
+
+        write!(
+            f,
+            "Memory per million keys: {}, disk per million keys: {}",
+            estimate(self.memory_per_million_keys(), bytes),
+            estimate(self.disk_per_million_keys(), bytes)
+        )
+    }
+}
+
+fn estimate(value: Option<usize>, format: impl Fn(usize) -> String) -> String {
+    value.map_or("unknown".to_string(), format)
+}
+
+fn bytes(bytes: usize) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+
+    match unit {
+        0 => format!("{bytes} B"),
+        _ => format!("{value:.1} {}", UNITS[unit]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LAYERS: [LayerReport; 3] = [
+        LayerReport {
+            component: "btree_top()",
+            fanout: None,
+            epsilon: None,
+            persisted: false,
+            nodes: Some(1),
+            bytes: Some(1_600),
+        },
+        LayerReport {
+            component: "btree(fanout = 8)",
+            fanout: Some(8),
+            epsilon: None,
+            persisted: false,
+            nodes: Some(2_000),
+            bytes: Some(2_048_000),
+        },
+        LayerReport {
+            component: "btree(fanout = 64, persist)",
+            fanout: Some(64),
+            epsilon: None,
+            persisted: true,
+            nodes: Some(22_000),
+            bytes: Some(16_000_000),
+        },
+    ];
+
+    #[test]
+    fn totals() {
+        let report = LayoutReport {
+            layers: &LAYERS,
+            depth: 3,
+        };
+
+        assert_eq!(report.memory_per_million_keys(), Some(2_049_600));
+        assert_eq!(report.disk_per_million_keys(), Some(16_000_000));
+
+        let mut layers = LAYERS;
+        layers[1].bytes = None;
+        let layers: &'static [LayerReport] = Box::leak(Box::new(layers));
+
+        let report = LayoutReport { layers, depth: 3 };
+        assert_eq!(report.memory_per_million_keys(), None);
+        assert_eq!(report.disk_per_million_keys(), Some(16_000_000));
+    }
+
+    #[test]
+    fn display() {
+        let report = LayoutReport {
+            layers: &LAYERS,
+            depth: 3,
+        };
+
+        assert_eq!(
+            report.to_string(),
+            "Expected depth for a million keys: 3\n\
+             \x20 btree_top()                          1 nodes     1.6 KiB memory\n\
+             \x20 btree(fanout = 8)                 2000 nodes     2.0 MiB memory\n\
+             \x20 btree(fanout = 64, persist)      22000 nodes    15.3 MiB disk\n\
+             Memory per million keys: 2.0 MiB, disk per million keys: 15.3 MiB"
+        );
+    }
+}
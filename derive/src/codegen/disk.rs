@@ -1,10 +1,11 @@
+use super::{create_search_bounds, Store};
 use crate::HybridLayout;
 use proc_macro2::{Ident, Span, TokenStream};
 use quote::quote;
 
 pub fn create_index_struct(
-    name: &Ident,
-    _layout: &HybridLayout,
+    store: &Store,
+    layout: &HybridLayout,
     alias: &[Ident],
 ) -> (TokenStream, Vec<Ident>) {
     // Create fields
@@ -16,21 +17,28 @@ pub fn create_index_struct(
         ));
     }
 
-    // Create field definitions
+    // Create field definitions, naming the components from the invoking scope
+    let mod_name = store.module();
     let mut field_bodies = Vec::new();
     for (index, component) in alias.iter().enumerate() {
         let field = fields[index].clone();
 
-        // TODO: remove the `pub` field, this is for debugging purposes
         field_bodies.push(quote! {
-            pub #field: #component<K, V>,
+            #field: #mod_name::#component<K, V>,
         });
     }
 
+    let Store {
+        name, attrs, vis, ..
+    } = store;
+    let generics = store.generics(true);
+    let search_bounds = create_search_bounds(layout);
+
     let body = quote! {
-        pub struct #name<K: Persisted + Key, V: Persisted + Value> {
+        #(#attrs)*
+        #vis struct #name #generics where #search_bounds {
             #(#field_bodies)*
-            pub store: GlobalStore,
+            store: ::limousine_engine::private::GlobalStore,
         }
     };
 
@@ -38,7 +46,7 @@ pub fn create_index_struct(
 }
 
 pub fn create_index_impl(
-    name: &Ident,
+    store: &Store,
     layout: &HybridLayout,
     aliases: &[Ident],
     fields: &[Ident],
@@ -46,14 +54,49 @@ pub fn create_index_impl(
     let search_body = create_search_body(layout, aliases, fields);
     let insert_body = create_insert_body(layout, aliases, fields);
     let load_body = create_load_body(layout, aliases, fields);
+    let sync_body = create_sync_body(layout, fields);
+    let compact_body = create_compact_body(layout, fields);
+    let stats_body = create_stats_body(layout, fields);
+    let rotate_key_body = create_rotate_key_body(layout, fields);
+    let close_body = create_close_body(layout, fields);
+    let manifest_body = create_manifest_body(layout);
+    let map_bounds = create_map_bounds(layout);
+    let search_bounds = create_search_bounds(layout);
+
+    // Base layer is guaranteed to be a disk component
+    let base_alias = aliases[0].clone();
+    let base_name = base_alias.to_string();
+    let base_field = fields[0].clone();
 
-    let checksum = layout.persist_checksum();
+    let generics = store.generics(false);
+    let ty = store.ty();
 
     let body = quote! {
-        impl<K: Key, V: Value> PersistedKVStore<K, V> for #name<K, V>
+        impl #generics #ty
         where
             K: limousine_engine::private::Persisted,
             V: limousine_engine::private::Persisted,
+            #map_bounds
+            #search_bounds
+        {
+            fn manifest() -> Manifest {
+                #manifest_body
+            }
+
+            fn load(
+                mut store: GlobalStore,
+                mut #base_field: #base_alias<K, V>,
+            ) -> limousine_engine::Result<Self> {
+                #load_body
+            }
+        }
+
+        impl #generics PersistedKVStore<K, V> for #ty
+        where
+            K: limousine_engine::private::Persisted,
+            V: limousine_engine::private::Persisted,
+            #map_bounds
+            #search_bounds
         {
             fn search(&self, key: K) -> limousine_engine::Result<Option<V>> {
                 #search_body
@@ -63,9 +106,62 @@ pub fn create_index_impl(
                 #insert_body
             }
 
-            fn open(path: impl AsRef<Path>) -> limousine_engine::Result<Self> {
-                let path = limousine_engine::private::add_prefix_to_path(path, #checksum.to_string())?;
-                #load_body
+            fn open_with(
+                path: impl AsRef<Path>,
+                options: OpenOptions,
+            ) -> limousine_engine::Result<Self> {
+                let mut store = GlobalStore::open(path, Self::manifest(), options)?;
+                let base = #base_alias::load(&mut store, #base_name)?;
+                Self::load(store, base)
+            }
+
+            fn build_with(
+                path: impl AsRef<Path>,
+                options: OpenOptions,
+                iter: impl Iterator<Item = (K, V)>,
+            ) -> limousine_engine::Result<Self> {
+                let options = options.create_new(true);
+                let mut store = GlobalStore::open(path, Self::manifest(), options)?;
+                let base = #base_alias::build(&mut store, #base_name, iter)?;
+                Self::load(store, base)
+            }
+
+            fn entries(&self) -> impl Iterator<Item = limousine_engine::Result<(K, V)>> + '_ {
+                self.#base_field.entries()
+            }
+
+            fn sync(&mut self) -> limousine_engine::Result<()> {
+                #sync_body
+            }
+
+            fn compact(&mut self) -> limousine_engine::Result<()> {
+                #compact_body
+            }
+
+            fn stats(&self) -> limousine_engine::Result<StoreStats> {
+                #stats_body
+            }
+
+            fn rotate_key(&mut self, key: EncryptionKey) -> limousine_engine::Result<()> {
+                #rotate_key_body
+            }
+
+            fn backup_to(&mut self, path: impl AsRef<Path>) -> limousine_engine::Result<()> {
+                self.sync()?;
+                self.store.backup_to(path)
+            }
+
+            fn restore_from_with(
+                backup: impl AsRef<Path>,
+                path: impl AsRef<Path>,
+                options: OpenOptions,
+            ) -> limousine_engine::Result<Self> {
+                GlobalStore::restore(backup, path.as_ref(), Self::manifest(), options)?;
+                Self::open_with(path, options)
+            }
+
+            fn close(self) -> limousine_engine::Result<()> {
+                #close_body
             }
         }
     };
@@ -128,6 +224,13 @@ fn create_insert_body(layout: &HybridLayout, _aliases: &[Ident], fields: &[Ident
     let component_vars: Vec<Ident> = fields.iter().cloned().rev().collect();
     let mut insert_body = TokenStream::new();
 
+    // Checked up front, so that in-memory layers are never modified without their pages
+    insert_body.extend(quote! {
+        if self.store.is_read_only() {
+            return Err(limousine_engine::Error::ReadOnly);
+        }
+    });
+
     // Top component
     let search = search_vars[0].clone();
     let field = component_vars[0].clone();
@@ -161,6 +264,7 @@ fn create_insert_body(layout: &HybridLayout, _aliases: &[Ident], fields: &[Ident
 
     insert_body.extend(quote! { let #search = self.#field.search(#prev_search, &key)?;});
 
+    insert_body.extend(create_parent_body(layout, &search_vars, &component_vars));
     insert_body.extend(quote! { let result = s0; });
 
     // Insert stage
@@ -225,26 +329,37 @@ fn create_insert_body(layout: &HybridLayout, _aliases: &[Ident], fields: &[Ident
     insert_body
 }
 
+fn create_parent_body(
+    layout: &HybridLayout,
+    search_vars: &[Ident],
+    component_vars: &[Ident],
+) -> TokenStream {
+    // Parents of the boundary layer, the topmost persisted one, only live in memory and are not
+    // rebuilt on open. Record the parent of the node we descended through instead, which is
+    // always the one a split has to propagate to.
+    let boundary = layout
+        .internal
+        .iter()
+        .position(|component| component.is_persisted())
+        .unwrap_or(layout.internal.len())
+        + 1;
+
+    let field = component_vars[boundary].clone();
+    let search = search_vars[boundary - 1].clone();
+
+    if boundary == 1 {
+        quote! { self.#field.set_parent(#search, ()); }
+    } else {
+        let parent = search_vars[boundary - 2].clone();
+        quote! { self.#field.set_parent(#search, #parent); }
+    }
+}
+
 fn create_load_body(layout: &HybridLayout, aliases: &[Ident], fields: &[Ident]) -> TokenStream {
     let mut empty_body = TokenStream::new();
+    let mut build_body = TokenStream::new();
 
-    // Add body as the first component
-    let alias = aliases[0].clone();
-    let var = fields[0].clone();
-
-    empty_body.extend(quote! {
-        // Load the store
-        let mut store = GlobalStore::load(path)?;
-    });
-
-    // Base layer is guaranteed to be a disk component
-    let alias_name = alias.to_string();
-    empty_body.extend(quote! {
-        // Load the store
-        let mut #var = #alias::load(&mut store, #alias_name)?;
-    });
-
-    // Add internal components
+    // The base component is loaded or built by the caller, add internal components on top
     for index in 1..=layout.internal.len() {
         let alias = aliases[index].clone();
         let var = fields[index].clone();
@@ -256,8 +371,9 @@ fn create_load_body(layout: &HybridLayout, aliases: &[Ident], fields: &[Ident])
                 let mut #var = #alias::load(&mut #prev_var, &mut store, #alias_name)?;
             });
         } else {
-            empty_body.extend(quote! {
+            build_body.extend(quote! {
                 let mut #var = #alias::build(&mut #prev_var);
+                #prev_var.take_error()?;
             });
         }
     }
@@ -267,8 +383,25 @@ fn create_load_body(layout: &HybridLayout, aliases: &[Ident], fields: &[Ident])
     let var = fields[index].clone();
     let prev_var = fields[index - 1].clone();
 
-    empty_body.extend(quote! {
+    build_body.extend(quote! {
         let mut #var = #alias::build(&mut #prev_var);
+        #prev_var.take_error()?;
+    });
+
+    // In-memory components are restored from the checkpoint of the previous session if possible
+    let persisted = persisted_fields(layout, fields).len();
+    let memory_fields = &fields[persisted..];
+    let memory_aliases = &aliases[persisted..];
+
+    empty_body.extend(quote! {
+        let checkpoint = store.read_checkpoint::<(#(#memory_aliases<K, V>,)*)>(&Self::manifest())?;
+        let (#(#memory_fields,)*) = match checkpoint {
+            Some(layers) => layers,
+            None => {
+                #build_body
+                (#(#memory_fields,)*)
+            }
+        };
     });
 
     empty_body.extend(quote! {
@@ -280,3 +413,108 @@ fn create_load_body(layout: &HybridLayout, aliases: &[Ident], fields: &[Ident])
 
     empty_body
 }
+
+pub fn create_map_bounds(layout: &HybridLayout) -> TokenStream {
+    // Mapped nodes are read in place, so their keys and values must be plain old data
+    let mut bounds = TokenStream::new();
+
+    if layout.is_mapped() {
+        bounds.extend(quote! { K: limousine_engine::private::Pod, });
+    }
+
+    if layout.base.is_mapped() {
+        bounds.extend(quote! { V: limousine_engine::private::Pod, });
+    }
+
+    bounds
+}
+
+fn create_manifest_body(layout: &HybridLayout) -> TokenStream {
+    let components = layout.describe().into_iter().map(
+        |(description, persisted)| quote! { ManifestComponent::new(#description, #persisted) },
+    );
+
+    quote! {
+        Manifest::new::<K, V>(vec![#(#components,)*])
+    }
+}
+
+fn persisted_fields<'a>(layout: &HybridLayout, fields: &'a [Ident]) -> Vec<&'a Ident> {
+    // Fields are ordered from the base upwards, while the layout lists internal components from
+    // the top down. The base component is guaranteed to be a disk component.
+    let internal = layout
+        .internal
+        .iter()
+        .rev()
+        .map(|component| component.is_persisted());
+
+    std::iter::once(true)
+        .chain(internal)
+        .zip(fields)
+        .filter_map(|(persisted, field)| persisted.then_some(field))
+        .collect()
+}
+
+fn memory_fields<'a>(layout: &HybridLayout, fields: &'a [Ident]) -> Vec<&'a Ident> {
+    // In-memory components always sit above the persisted ones, up to and including the top
+    let persisted = persisted_fields(layout, fields).len();
+    fields[persisted..].iter().collect()
+}
+
+fn create_sync_body(layout: &HybridLayout, fields: &[Ident]) -> TokenStream {
+    let persisted = persisted_fields(layout, fields);
+
+    quote! {
+        #(self.#persisted.flush()?;)*
+        self.store.sync()
+    }
+}
+
+fn create_compact_body(layout: &HybridLayout, fields: &[Ident]) -> TokenStream {
+    let persisted = persisted_fields(layout, fields);
+
+    quote! {
+        #(self.#persisted.flush()?;)*
+        self.store.compact()
+    }
+}
+
+fn create_rotate_key_body(layout: &HybridLayout, fields: &[Ident]) -> TokenStream {
+    let persisted = persisted_fields(layout, fields);
+
+    quote! {
+        #(self.#persisted.flush()?;)*
+        self.store.rotate_key(key)
+    }
+}
+
+fn create_stats_body(layout: &HybridLayout, fields: &[Ident]) -> TokenStream {
+    let persisted = persisted_fields(layout, fields);
+
+    quote! {
+        // Measuring the layers flushes them, so the store has to be measured last
+        let layers = vec![#(self.#persisted.stats()?,)*];
+        let mut stats = self.store.stats();
+        stats.layers = layers;
+
+        Ok(stats)
+    }
+}
+
+fn create_close_body(layout: &HybridLayout, fields: &[Ident]) -> TokenStream {
+    let persisted = persisted_fields(layout, fields);
+    let memory = memory_fields(layout, fields);
+
+    quote! {
+        let Self { #(#fields,)* mut store } = self;
+        #(#persisted.flush()?;)*
+
+        // The checkpoint refers to persisted nodes, so it may only be written once they are durable
+        store.sync()?;
+        store.write_checkpoint(&Self::manifest(), &(#(&#memory,)*))?;
+
+        // Components hold references to the store, so they must be released first
+        #(drop(#fields);)*
+        store.close()
+    }
+}
@@ -1,9 +1,10 @@
+use super::{create_search_bounds, Store};
 use crate::HybridLayout;
 use proc_macro2::{Ident, Span, TokenStream};
 use quote::quote;
 
 pub fn create_index_struct(
-    name: &Ident,
+    store: &Store,
     _layout: &HybridLayout,
     alias: &[Ident],
 ) -> (TokenStream, Vec<Ident>) {
@@ -16,19 +17,25 @@ pub fn create_index_struct(
         ));
     }
 
-    // Create field definitions
+    // Create field definitions, naming the components from the invoking scope
+    let mod_name = store.module();
     let mut field_bodies = Vec::new();
     for (index, component) in alias.iter().enumerate() {
         let field = fields[index].clone();
 
-        // TODO: remove the `pub` field, this is for debugging purposes
         field_bodies.push(quote! {
-            pub #field: #component<K, V>,
+            #field: #mod_name::#component<K, V>,
         });
     }
 
+    let Store {
+        name, attrs, vis, ..
+    } = store;
+    let generics = store.generics(false);
+
     let body = quote! {
-        pub struct #name<K: Key, V: Value> {
+        #(#attrs)*
+        #vis struct #name #generics {
             #(#field_bodies)*
         }
     };
@@ -37,7 +44,7 @@ pub fn create_index_struct(
 }
 
 pub fn create_index_impl(
-    name: &Ident,
+    store: &Store,
     layout: &HybridLayout,
     aliases: &[Ident],
     fields: &[Ident],
@@ -46,9 +53,14 @@ pub fn create_index_impl(
     let insert_body = create_insert_body(layout, aliases, fields);
     let empty_body = create_empty_body(layout, aliases, fields);
     let build_body = create_build_body(layout, aliases, fields);
+    let base_field = fields[0].clone();
+    let search_bounds = create_search_bounds(layout);
+
+    let generics = store.generics(false);
+    let ty = store.ty();
 
     let body = quote! {
-        impl<K: Key, V: Value> KVStore<K, V> for #name<K, V> {
+        impl #generics KVStore<K, V> for #ty where #search_bounds {
             fn search(&self, key: K) -> Option<V> {
                 #search_body
             }
@@ -64,6 +76,10 @@ pub fn create_index_impl(
             fn build(iter: impl Iterator<Item = (K, V)>) -> Self {
                 #build_body
             }
+
+            fn entries(&self) -> impl Iterator<Item = (K, V)> + '_ {
+                self.#base_field.entries()
+            }
         }
     };
 
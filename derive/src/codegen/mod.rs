@@ -1,48 +1,166 @@
+use crate::estimate::{self, REPORT_KEYS, TOP_CAPACITY};
 use crate::HybridLayout;
-use proc_macro2::{Ident, Span, TokenStream};
-use quote::quote;
+use proc_macro2::{Delimiter, Group, Ident, Span, TokenStream, TokenTree};
+use quote::{quote, quote_spanned};
+use syn::{Attribute, Type, Visibility};
 
 mod disk;
 mod memory;
 
-pub fn create_implementation(name: Ident, layout: HybridLayout) -> proc_macro::TokenStream {
-    let mod_name = proc_macro2::Ident::new(
-        format!("__{}", name.to_string().to_lowercase()).as_str(),
-        proc_macro2::Span::call_site(),
-    );
+/// The generated store struct, as declared by the invocation
+pub struct Store {
+    pub name: Ident,
+
+    /// Attributes of the struct, such as doc comments and derives
+    pub attrs: Vec<Attribute>,
+
+    pub vis: Visibility,
+
+    /// Concrete key and value types, or `None` for a store generic over `K` and `V`
+    pub types: Option<(Type, Type)>,
+}
+
+impl Store {
+    /// Generic parameters of the struct and of its impls, empty for concrete types. Persisted
+    /// stores require their keys and values to be `Persisted` on the struct itself.
+    fn generics(&self, persisted: bool) -> TokenStream {
+        let bounds = |bound: TokenStream| match persisted {
+            true => quote!(::limousine_engine::private::Persisted + #bound),
+            false => bound,
+        };
+
+        let key = bounds(quote!(::limousine_engine::private::Key));
+        let value = bounds(quote!(::limousine_engine::private::Value));
+
+        match self.types {
+            Some(_) => TokenStream::new(),
+            None => quote!(<K: #key, V: #value>),
+        }
+    }
+
+    /// The struct along with its generic arguments
+    fn ty(&self) -> TokenStream {
+        let name = &self.name;
+
+        match self.types {
+            Some(_) => quote!(#name),
+            None => quote!(#name<K, V>),
+        }
+    }
+
+    /// The hidden module holding the components of the store
+    fn module(&self) -> Ident {
+        Ident::new(
+            &format!("__{}", self.name.to_string().to_lowercase()),
+            Span::call_site(),
+        )
+    }
+}
+
+pub fn create_implementation(
+    store: Store,
+    layout: HybridLayout,
+    report: bool,
+    lints: &[(Span, String)],
+) -> proc_macro::TokenStream {
+    let mod_name = store.module();
 
     let (alias_body, alias) = create_type_aliases(&layout);
 
     let (index_body, index_fields) = if layout.is_persisted() {
-        disk::create_index_struct(&name, &layout, &alias)
+        disk::create_index_struct(&store, &layout, &alias)
     } else {
-        memory::create_index_struct(&name, &layout, &alias)
+        memory::create_index_struct(&store, &layout, &alias)
     };
 
     let index_impl = if layout.is_persisted() {
-        disk::create_index_impl(&name, &layout, &alias, &index_fields)
+        disk::create_index_impl(&store, &layout, &alias, &index_fields)
     } else {
-        memory::create_index_impl(&name, &layout, &alias, &index_fields)
+        memory::create_index_impl(&store, &layout, &alias, &index_fields)
     };
 
+    let describe_impl = create_describe_impl(&store, &layout, &index_fields);
+    let report_impl = report.then(|| create_report_impl(&store, &layout));
+    let warnings = create_warnings(lints);
+
+    // The generated module is nested in the invoking scope, where custom components are named
+    let custom_imports = layout.is_custom().then(|| {
+        quote! {
+            #[allow(unused_imports)]
+            use super::*;
+        }
+    });
+
+    // Stores with concrete key and value types use them in place of `K` and `V`
+    let [index_body, index_impl, describe_impl, report_impl] =
+        [index_body, index_impl, describe_impl, quote!(#report_impl)].map(|tokens| {
+            match &store.types {
+                Some((key, value)) => substitute(tokens, key, value),
+                None => tokens,
+            }
+        });
+
+    // Conditionally compiled stores take their components and impls along
+    let cfgs: Vec<_> = store
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("cfg"))
+        .collect();
+
+    // The struct and its impls stay in the invoking scope, which may be a function body, so that
+    // concrete key and value types are named from there
     let mut implementation = proc_macro2::TokenStream::new();
     implementation.extend(quote! {
+        #(#cfgs)*
         pub mod #mod_name {
             use ::limousine_engine::private::*;
+            #custom_imports
 
             #alias_body
 
-            #index_body
+            #warnings
+        }
+
+        #index_body
+
+        #(#cfgs)*
+        const _: () = {
+            use ::limousine_engine::private::*;
+            use #mod_name::*;
 
             #index_impl
-        }
 
-        use #mod_name::#name;
+            #describe_impl
+
+            #report_impl
+        };
     });
 
     implementation.into()
 }
 
+/// Replace every `K` and `V` of generated code with the given key and value types
+fn substitute(tokens: TokenStream, key: &Type, value: &Type) -> TokenStream {
+    tokens
+        .into_iter()
+        .map(|tree| match tree {
+            TokenTree::Ident(ident) if ident == "K" => {
+                TokenTree::Group(Group::new(Delimiter::None, quote!(#key)))
+            }
+            TokenTree::Ident(ident) if ident == "V" => {
+                TokenTree::Group(Group::new(Delimiter::None, quote!(#value)))
+            }
+            TokenTree::Group(group) => {
+                let mut substituted =
+                    Group::new(group.delimiter(), substitute(group.stream(), key, value));
+                substituted.set_span(group.span());
+                TokenTree::Group(substituted)
+            }
+            tree => tree,
+        })
+        .collect()
+}
+
 fn create_type_aliases(layout: &HybridLayout) -> (TokenStream, Vec<Ident>) {
     let address_alias: Vec<Ident> = (0..=layout.internal.len() + 1)
         .map(|i| Ident::new(format!("A{}", i).as_str(), Span::call_site()))
@@ -54,7 +172,7 @@ fn create_type_aliases(layout: &HybridLayout) -> (TokenStream, Vec<Ident>) {
     let alias = address_alias[0].clone();
     let body = layout.base.address_type();
     type_alias_body.extend(quote::quote! {
-        type #alias = #body;
+        pub(super) type #alias = #body;
     });
 
     // Add internal components
@@ -64,12 +182,12 @@ fn create_type_aliases(layout: &HybridLayout) -> (TokenStream, Vec<Ident>) {
         let alias = address_alias[index].clone();
         let body = component.address_type();
         type_alias_body.extend(quote! {
-            type #alias = #body;
+            pub(super) type #alias = #body;
         });
     }
 
     let alias = address_alias.last().unwrap().clone();
-    type_alias_body.extend(quote! { type #alias = (); });
+    type_alias_body.extend(quote! { pub(super) type #alias = (); });
 
     let type_alias: Vec<Ident> = (0..=layout.internal.len() + 1)
         .map(|i| Ident::new(format!("C{}", i).as_str(), Span::call_site()))
@@ -80,7 +198,7 @@ fn create_type_aliases(layout: &HybridLayout) -> (TokenStream, Vec<Ident>) {
     let alias = type_alias[0].clone();
     let body = layout.base.component_type(parent_address_alias);
     type_alias_body.extend(quote::quote! {
-        type #alias<K, V> = #body;
+        pub(super) type #alias<K, V> = #body;
     });
 
     // Add internal components
@@ -94,7 +212,7 @@ fn create_type_aliases(layout: &HybridLayout) -> (TokenStream, Vec<Ident>) {
 
         let alias = type_alias[index].clone();
         type_alias_body.extend(quote! {
-            type #alias<K, V> = #body;
+            pub(super) type #alias<K, V> = #body;
         });
     }
 
@@ -106,8 +224,241 @@ fn create_type_aliases(layout: &HybridLayout) -> (TokenStream, Vec<Ident>) {
 
     let alias = type_alias[index].clone();
     type_alias_body.extend(quote! {
-        type #alias<K, V> = #body;
+        pub(super) type #alias<K, V> = #body;
     });
 
     (type_alias_body, type_alias)
 }
+
+/// Emit every lint as a deprecation warning, the only kind of warning available to procedural
+/// macros, pointing at the component it is about
+fn create_warnings(lints: &[(Span, String)]) -> TokenStream {
+    let warnings = lints.iter().map(|(span, message)| {
+        let warning = quote_spanned!(*span=> questionable_layout);
+
+        quote! {
+            const _: () = {
+                #[deprecated(note = #message)]
+                #[allow(non_upper_case_globals)]
+                const questionable_layout: () = ();
+                #warning
+            };
+        }
+    });
+
+    quote!(#(#warnings)*)
+}
+
+fn create_describe_impl(store: &Store, layout: &HybridLayout, fields: &[Ident]) -> TokenStream {
+    let mut components = vec![(layout.top.kind(), layout.top.to_string(), Vec::new(), false)];
+
+    for component in layout.internal.iter() {
+        components.push((
+            component.kind(),
+            component.to_string(),
+            component.parameters(),
+            component.is_persisted(),
+        ));
+    }
+
+    components.push((
+        layout.base.kind(),
+        layout.base.to_string(),
+        layout.base.parameters(),
+        layout.base.is_persisted(),
+    ));
+
+    // Components are listed from the top down while fields are ordered from the base upwards.
+    // Persisted layers below another persisted layer keep track of their own parents.
+    let mut above_persisted = false;
+    let layers = components.into_iter().zip(fields.iter().rev()).map(
+        |((kind, component, parameters, persisted), field)| {
+            let persist = match (persisted, above_persisted) {
+                (false, _) => quote!(PersistType::InMemory),
+                (true, false) => quote!(PersistType::BoundaryDisk),
+                (true, true) => quote!(PersistType::DeepDisk),
+            };
+            above_persisted = persisted;
+
+            let parameters = parameters
+                .iter()
+                .map(|(name, value)| quote!((#name, #value)));
+
+            quote! {
+                LayerDescription {
+                    kind: #kind,
+                    component: #component,
+                    parameters: &[#(#parameters,)*],
+                    persist: #persist,
+                    size: Measure::measure(&self.#field)?,
+                }
+            }
+        },
+    );
+
+    let persisted_bounds = layout.is_persisted().then(|| {
+        let map_bounds = disk::create_map_bounds(layout);
+
+        quote! {
+            K: limousine_engine::private::Persisted,
+            V: limousine_engine::private::Persisted,
+            #map_bounds
+        }
+    });
+    let search_bounds = create_search_bounds(layout);
+
+    let generics = store.generics(false);
+    let ty = store.ty();
+
+    quote! {
+        impl #generics #ty where #persisted_bounds #search_bounds {
+            /// Describe every layer of the store from the top down, along with its measured size
+            pub fn describe(&self) -> limousine_engine::Result<Vec<LayerDescription>> {
+                Ok(vec![#(#layers,)*])
+            }
+        }
+    }
+}
+
+/// Bounds on the key type required by the search strategies of the layout
+fn create_search_bounds(layout: &HybridLayout) -> TokenStream {
+    // Interpolation search estimates positions from the values of keys
+    match layout.is_interpolated() {
+        true => quote! { K: limousine_engine::private::Interpolate, },
+        false => TokenStream::new(),
+    }
+}
+
+fn create_report_impl(store: &Store, layout: &HybridLayout) -> TokenStream {
+    let layers = estimate::layers(layout);
+    let nodes = estimate::nodes(&layers, REPORT_KEYS);
+    let depth = estimate::depth(&layers, REPORT_KEYS);
+
+    let size = |ty: TokenStream| quote!(::std::mem::size_of::<#ty>());
+    let address = |index: usize| {
+        let alias = Ident::new(format!("A{}", index).as_str(), Span::call_site());
+        quote!(#alias)
+    };
+
+    let mut reports = Vec::new();
+
+    // Layers are estimated from the base upwards, where every layer has as many entries as the
+    // layer below it has nodes
+    for (index, layer) in layers.iter().enumerate() {
+        let entries = if index == 0 {
+            Some(REPORT_KEYS)
+        } else {
+            nodes[index - 1]
+        };
+
+        // Entries of the base layer are values, and addresses of the layer below otherwise
+        let entry = size(if index == 0 {
+            quote!(V)
+        } else {
+            address(index - 1)
+        });
+        let key = size(quote!(K));
+
+        let bytes = match (nodes[index], entries, layer.fanout, layer.epsilon) {
+            // Persisted nodes only hold their entries, in-memory nodes have room for all of them
+            (Some(_), Some(entries), Some(_), _) if layer.persisted => {
+                let entries = entries as usize;
+                quote!(Some(#entries * (#key + #entry)))
+            }
+            (Some(nodes), _, Some(fanout), _) => {
+                let slots = nodes as usize * fanout;
+                quote!(Some(#slots * (#key + #entry)))
+            }
+            // Every segment also holds its starting key, slope and intercept
+            (Some(nodes), Some(entries), _, Some(_)) => {
+                let (nodes, entries) = (nodes as usize, entries as usize);
+                quote!(Some(#entries * (#key + #entry) + #nodes * (#key + 16)))
+            }
+            _ => quote!(None),
+        };
+
+        reports.push(layer_report(
+            &layer.description,
+            layer.fanout,
+            layer.epsilon,
+            layer.persisted,
+            nodes[index],
+            bytes,
+        ));
+    }
+
+    // The top component holds an entry for every node of the layer below
+    let top_entries = nodes[layers.len()];
+    let top_nodes = top_entries.map(estimate::top_nodes);
+    let top_bytes = match top_nodes {
+        Some(nodes) => {
+            let slots = nodes as usize * TOP_CAPACITY;
+            let key = size(quote!(K));
+            let entry = size(address(layers.len() - 1));
+            quote!(Some(#slots * (#key + #entry)))
+        }
+        None => quote!(None),
+    };
+
+    reports.push(layer_report(
+        &layout.top.to_string(),
+        None,
+        None,
+        false,
+        top_nodes,
+        top_bytes,
+    ));
+
+    // Reports are listed from the top down
+    reports.reverse();
+
+    let bounds = layout.is_persisted().then(|| {
+        quote! {
+            where
+                K: limousine_engine::private::Persisted,
+                V: limousine_engine::private::Persisted,
+        }
+    });
+
+    let generics = store.generics(false);
+    let ty = store.ty();
+
+    quote! {
+        impl #generics #ty #bounds {
+            /// Expected shape and size of the layout, estimated when the store was generated
+            pub const LAYOUT_REPORT: LayoutReport = LayoutReport {
+                layers: &[#(#reports,)*],
+                depth: #depth,
+            };
+        }
+    }
+}
+
+fn layer_report(
+    description: &str,
+    fanout: Option<usize>,
+    epsilon: Option<usize>,
+    persisted: bool,
+    nodes: Option<f64>,
+    bytes: TokenStream,
+) -> TokenStream {
+    let option = |value: Option<usize>| match value {
+        Some(value) => quote!(Some(#value)),
+        None => quote!(None),
+    };
+
+    let fanout = option(fanout);
+    let epsilon = option(epsilon);
+    let nodes = option(nodes.map(|nodes| nodes as usize));
+
+    quote! {
+        LayerReport {
+            component: #description,
+            fanout: #fanout,
+            epsilon: #epsilon,
+            persisted: #persisted,
+            nodes: #nodes,
+            bytes: #bytes,
+        }
+    }
+}
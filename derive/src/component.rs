@@ -5,14 +5,173 @@ use std::collections::HashSet;
 use syn::{
     parenthesized,
     parse::{Parse, ParseStream},
-    Expr, Lit, LitInt, Token,
+    Expr, Lit, LitInt, Path, PathArguments, Token, Type,
 };
 
 #[derive(Clone)]
 pub enum Component {
     BTreeTop,
-    BTree { fanout: usize, persist: bool },
-    PGM { epsilon: usize, },
+    BTree {
+        fanout: usize,
+        persist: bool,
+        mmap: bool,
+        search: SearchStrategy,
+        fill: usize,
+        split: SplitPolicy,
+    },
+    PGM {
+        epsilon: usize,
+    },
+    Custom(Box<CustomComponent>),
+}
+
+/// A user-defined component, spliced into the layout as
+/// `path<K, V, params.., base address, parent address>` (without the base address for base
+/// components), mirroring the generic parameters of the built-in components
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CustomComponent {
+    path: Path,
+    address: Option<Type>,
+    params: Vec<(Ident, Expr)>,
+    persist: bool,
+}
+
+impl CustomComponent {
+    pub fn component_type(&self, addresses: TokenStream) -> TokenStream {
+        let path = &self.path;
+
+        // Only literals can be passed as const generic arguments without braces
+        let params = self.params.iter().map(|(_, value)| match value {
+            Expr::Lit(_) => quote!(#value),
+            _ => quote!({ #value }),
+        });
+
+        quote!(#path<K, V, #(#params,)* #addresses>)
+    }
+
+    /// The explicit `address` type, or the `...Address` type next to a `...Component` type
+    pub fn address_type(&self) -> TokenStream {
+        if let Some(address) = &self.address {
+            return quote!(#address);
+        }
+
+        let mut path = self.path.clone();
+        let last = path.segments.last_mut().unwrap();
+
+        let name = last.ident.to_string();
+        let name = name.strip_suffix("Component").unwrap_or(&name);
+        last.ident = Ident::new(&format!("{}Address", name), last.ident.span());
+        last.arguments = PathArguments::None;
+
+        quote!(#path)
+    }
+
+    pub fn is_persisted(&self) -> bool {
+        self.persist
+    }
+
+    /// The path of the component followed by its attributes, as written
+    pub fn parameters(&self) -> Vec<(String, String)> {
+        let path = &self.path;
+        let mut parameters = vec![(
+            "path".to_string(),
+            quote!(#path).to_string().replace(' ', ""),
+        )];
+
+        if let Some(address) = &self.address {
+            parameters.push((
+                "address".to_string(),
+                quote!(#address).to_string().replace(' ', ""),
+            ));
+        }
+
+        for (name, value) in self.params.iter() {
+            parameters.push((name.to_string(), quote!(#value).to_string()));
+        }
+
+        parameters
+    }
+}
+
+impl std::fmt::Display for CustomComponent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let path = &self.path;
+        write!(f, "custom({}", quote!(#path).to_string().replace(' ', ""))?;
+
+        if let Some(address) = &self.address {
+            write!(
+                f,
+                ", address = {}",
+                quote!(#address).to_string().replace(' ', "")
+            )?;
+        }
+
+        for (name, value) in self.params.iter() {
+            write!(f, ", {} = {}", name, quote!(#value))?;
+        }
+
+        if self.persist {
+            write!(f, ", persist")?;
+        }
+
+        write!(f, ")")
+    }
+}
+
+impl Parse for CustomComponent {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut custom = Self {
+            path: input.parse()?,
+            address: None,
+            params: Vec::new(),
+            persist: false,
+        };
+
+        while !input.is_empty() {
+            input.parse::<Token![,]>()?;
+            if input.is_empty() {
+                break;
+            }
+
+            let key: Ident = input.parse()?;
+            let name = key.to_string();
+
+            if name == "persist" {
+                if custom.persist {
+                    bail!(key, "Attribute `persist` is specified twice!");
+                }
+
+                custom.persist = true;
+                continue;
+            }
+
+            if !input.peek(Token![=]) {
+                bail!(
+                    key,
+                    "Unknown flag `{}`, custom components only support `persist`!",
+                    name
+                );
+            }
+            input.parse::<Token![=]>()?;
+
+            if name == "address" {
+                if custom.address.is_some() {
+                    bail!(key, "Attribute `address` is specified twice!");
+                }
+
+                custom.address = Some(input.parse()?);
+                continue;
+            }
+
+            if custom.params.iter().any(|(param, _)| *param == key) {
+                bail!(key, "Parameter `{}` is specified twice!", name);
+            }
+
+            custom.params.push((key, input.parse()?));
+        }
+
+        Ok(custom)
+    }
 }
 
 pub struct ParsedComponent {
@@ -30,6 +189,7 @@ impl ParsedComponent {
     pub fn is_persisted(&self) -> bool {
         match self.component {
             Component::BTree { persist, .. } => persist,
+            Component::Custom(ref custom) => custom.is_persisted(),
             _ => false,
         }
     }
@@ -46,6 +206,11 @@ impl Parse for ParsedComponent {
         let attributes;
         parenthesized!(attributes in input);
 
+        if ident == "custom" {
+            let component = Component::Custom(Box::new(attributes.parse()?));
+            return Ok(Self { ident, component });
+        }
+
         let mut attributes: Attributes = attributes.parse()?;
 
         let component = match ident.to_string().as_str() {
@@ -53,6 +218,16 @@ impl Parse for ParsedComponent {
             "btree" => {
                 let fanout = attributes.try_get_integer(&ident, "fanout")?;
                 let persist = attributes.try_get_bool("persist")?;
+                let mmap = attributes.try_get_bool("mmap")?;
+                let search = match attributes.try_get_ident("search")? {
+                    Some(search) => SearchStrategy::parse(&search)?,
+                    None => SearchStrategy::Optimal,
+                };
+                let fill = attributes.try_get_float("fill")?;
+                let split = match attributes.try_get_ident("split")? {
+                    Some(split) => SplitPolicy::parse(&split)?,
+                    None => SplitPolicy::Middle,
+                };
 
                 let fanout = if fanout >= 2 {
                     fanout as usize
@@ -60,11 +235,31 @@ impl Parse for ParsedComponent {
                     bail!(ident, "Specified fanout is less than 2!");
                 };
 
-                Component::BTree { fanout, persist }
+                if mmap && !persist {
+                    bail!(ident, "Only persisted components can be memory-mapped!");
+                }
+
+                // Bulk-built nodes are filled to a whole percentage of their fanout
+                let fill = match fill {
+                    Some(fill) if (0.01..=1.0).contains(&fill) => (fill * 100.0).round() as usize,
+                    Some(_) => {
+                        bail!(ident, "Specified fill is not between 0.01 and 1!");
+                    }
+                    None => DEFAULT_FILL,
+                };
+
+                Component::BTree {
+                    fanout,
+                    persist,
+                    mmap,
+                    search,
+                    fill,
+                    split,
+                }
             }
             "pgm" => {
                 let epsilon = attributes.try_get_integer(&ident, "epsilon")?;
-                
+
                 let epsilon = if epsilon > 0 {
                     epsilon as usize
                 } else {
@@ -104,6 +299,22 @@ impl TopComponent {
     }
 }
 
+impl TopComponent {
+    pub fn kind(&self) -> &'static str {
+        match *self {
+            TopComponent::BTreeTop => "btree_top",
+        }
+    }
+}
+
+impl std::fmt::Display for TopComponent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::BTreeTop => write!(f, "btree_top()"),
+        }
+    }
+}
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum PersistType {
     InMemory,
@@ -111,17 +322,36 @@ pub enum PersistType {
     DeepDisk,
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum InternalComponent {
-    BTree { fanout: usize, persist: PersistType },
-    PGM {epsilon: usize},
+    BTree {
+        fanout: usize,
+        persist: PersistType,
+        mmap: bool,
+        search: SearchStrategy,
+        fill: usize,
+        split: SplitPolicy,
+    },
+    PGM {
+        epsilon: usize,
+    },
+    Custom(Box<CustomComponent>),
 }
 
-impl ToString for InternalComponent {
-    fn to_string(&self) -> String {
+impl std::fmt::Display for InternalComponent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::BTree { fanout, persist } => format!("{persist:?}BTreeInternal{fanout:?}").to_string(),
-            Self::PGM {epsilon} => format!("PGMInternal{epsilon:?}").to_string(),
+            Self::BTree {
+                fanout,
+                persist: PersistType::InMemory,
+                ..
+            } => write!(f, "btree(fanout = {fanout})"),
+            // The node map is derived from the pages, and nodes are stored the same way whatever
+            // the search strategy, fill or split policy, so `mmap`, `search`, `fill` and `split` do
+            // not change the stored layout
+            Self::BTree { fanout, .. } => write!(f, "btree(fanout = {fanout}, persist)"),
+            Self::PGM { epsilon } => write!(f, "pgm(epsilon = {epsilon})"),
+            Self::Custom(custom) => custom.fmt(f),
         }
     }
 }
@@ -133,36 +363,60 @@ impl InternalComponent {
                 Component::BTree {
                     fanout,
                     persist: false,
+                    mmap,
+                    search,
+                    fill,
+                    split,
                 },
                 false,
             ) => Some(Self::BTree {
                 fanout,
                 persist: PersistType::InMemory,
+                mmap,
+                search,
+                fill,
+                split,
             }),
             (
                 Component::BTree {
                     fanout,
                     persist: true,
+                    mmap,
+                    search,
+                    fill,
+                    split,
                 },
                 false,
             ) => Some(Self::BTree {
                 fanout,
                 persist: PersistType::BoundaryDisk,
+                mmap,
+                search,
+                fill,
+                split,
             }),
             (
                 Component::BTree {
                     fanout,
                     persist: true,
+                    mmap,
+                    search,
+                    fill,
+                    split,
                 },
                 true,
             ) => Some(Self::BTree {
                 fanout,
                 persist: PersistType::DeepDisk,
+                mmap,
+                search,
+                fill,
+                split,
             }),
-            (
-                Component::PGM { epsilon },
-                _
-            ) => Some(Self::PGM { epsilon }),
+            (Component::PGM { epsilon }, _) => Some(Self::PGM { epsilon }),
+            // Custom components are the same type wherever they are placed, they implement the
+            // traits of the position they are meant for
+            (Component::Custom(custom), _) => Some(Self::Custom(custom)),
             _ => None,
         }
     }
@@ -176,65 +430,166 @@ impl InternalComponent {
             InternalComponent::BTree {
                 fanout,
                 persist: PersistType::InMemory,
-            } => quote!(BTreeInternalComponent<K, V, #fanout, #base_address, #parent_address>)
+                search,
+                fill,
+                split,
+                ..
+            } => quote!(BTreeInternalComponent<K, V, #fanout, #base_address, #parent_address, #search, #fill, #split>)
                 .to_token_stream(),
 
             InternalComponent::BTree {
                 fanout,
                 persist: PersistType::BoundaryDisk,
-            } => quote!(BoundaryDiskBTreeInternalComponent<K, V, #fanout, #base_address, #parent_address>)
-                .to_token_stream(),
-                
+                mmap,
+                search,
+                fill,
+                split,
+            } => {
+                let map = node_map(mmap, &base_address, fanout);
+                quote!(BoundaryDiskBTreeInternalComponent<K, V, #fanout, #base_address, #parent_address, #map, #search, #fill, #split>)
+                    .to_token_stream()
+            }
+
             InternalComponent::BTree {
                 fanout,
                 persist: PersistType::DeepDisk,
-            } => quote!(DeepDiskBTreeInternalComponent<K, V, #fanout, #base_address, #parent_address>)
-                .to_token_stream(),
-            
+                mmap,
+                search,
+                fill,
+                split,
+            } => {
+                let map = node_map(mmap, &base_address, fanout);
+                quote!(DeepDiskBTreeInternalComponent<K, V, #fanout, #base_address, #parent_address, #map, #search, #fill, #split>)
+                    .to_token_stream()
+            }
+
             InternalComponent::PGM { epsilon } =>
             quote!(PGMInternalComponent<K, V, #epsilon, #base_address, #parent_address>).to_token_stream(),
+
+            InternalComponent::Custom(ref custom) => {
+                custom.component_type(quote!(#base_address, #parent_address))
+            }
         }
     }
 
     pub fn address_type(&self) -> TokenStream {
         match *self {
-            InternalComponent::BTree { persist: PersistType::InMemory, .. } => {
-                quote!(BTreeInternalAddress).to_token_stream()
-            }
+            InternalComponent::BTree {
+                persist: PersistType::InMemory,
+                ..
+            } => quote!(BTreeInternalAddress).to_token_stream(),
 
-            InternalComponent::BTree { persist: PersistType::BoundaryDisk, .. } => {
-                quote!(BoundaryDiskBTreeInternalAddress).to_token_stream()
-            }
+            InternalComponent::BTree {
+                persist: PersistType::BoundaryDisk,
+                ..
+            } => quote!(BoundaryDiskBTreeInternalAddress).to_token_stream(),
 
-            InternalComponent::BTree { persist: PersistType::DeepDisk, .. } => {
-                quote!(DeepDiskBTreeInternalAddress).to_token_stream()
-            }
+            InternalComponent::BTree {
+                persist: PersistType::DeepDisk,
+                ..
+            } => quote!(DeepDiskBTreeInternalAddress).to_token_stream(),
 
-            InternalComponent::PGM {..} => {
-                quote!(PGMInternalAddress).to_token_stream()
-            }
+            InternalComponent::PGM { .. } => quote!(PGMInternalAddress).to_token_stream(),
+
+            InternalComponent::Custom(ref custom) => custom.address_type(),
         }
     }
 
     pub fn is_persisted(&self) -> bool {
         match *self {
             InternalComponent::BTree { persist, .. } => persist != PersistType::InMemory,
-            InternalComponent::PGM {..} => false,
+            InternalComponent::PGM { .. } => false,
+            InternalComponent::Custom(ref custom) => custom.is_persisted(),
+        }
+    }
+
+    pub fn is_mapped(&self) -> bool {
+        matches!(*self, InternalComponent::BTree { mmap: true, .. })
+    }
+
+    pub fn is_interpolated(&self) -> bool {
+        matches!(
+            *self,
+            InternalComponent::BTree {
+                search: SearchStrategy::Interpolation,
+                ..
+            }
+        )
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match *self {
+            InternalComponent::BTree { .. } => "btree",
+            InternalComponent::PGM { .. } => "pgm",
+            InternalComponent::Custom(_) => "custom",
+        }
+    }
+
+    /// Parameters of the component along with their values, as written
+    pub fn parameters(&self) -> Vec<(String, String)> {
+        match *self {
+            InternalComponent::BTree {
+                fanout,
+                mmap,
+                search,
+                fill,
+                split,
+                ..
+            } => {
+                let mut parameters = vec![("fanout".to_string(), fanout.to_string())];
+                if mmap {
+                    parameters.push(("mmap".to_string(), "true".to_string()));
+                }
+                if search != SearchStrategy::Optimal {
+                    parameters.push(("search".to_string(), search.to_string()));
+                }
+                if fill != DEFAULT_FILL {
+                    parameters.push(("fill".to_string(), (fill as f64 / 100.0).to_string()));
+                }
+                if split != SplitPolicy::Middle {
+                    parameters.push(("split".to_string(), split.to_string()));
+                }
+
+                parameters
+            }
+            InternalComponent::PGM { epsilon } => {
+                vec![("epsilon".to_string(), epsilon.to_string())]
+            }
+            InternalComponent::Custom(ref custom) => custom.parameters(),
         }
     }
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum BaseComponent {
-    BTree { fanout: usize, persist: PersistType },
-    PGM {epsilon: usize},
+    BTree {
+        fanout: usize,
+        persist: PersistType,
+        mmap: bool,
+        search: SearchStrategy,
+        fill: usize,
+        split: SplitPolicy,
+    },
+    PGM {
+        epsilon: usize,
+    },
+    Custom(Box<CustomComponent>),
 }
 
-impl ToString for BaseComponent {
-    fn to_string(&self) -> String {
+impl std::fmt::Display for BaseComponent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::BTree { fanout, persist } => format!("{persist:?}BTreeBase{fanout:?}").to_string(),
-            Self::PGM {epsilon} => format!("PGMBase{epsilon:?}").to_string(),
+            Self::BTree {
+                fanout,
+                persist: PersistType::InMemory,
+                ..
+            } => write!(f, "btree(fanout = {fanout})"),
+            // The node map is derived from the pages, and nodes are stored the same way whatever
+            // the search strategy, fill or split policy, so `mmap`, `search`, `fill` and `split` do
+            // not change the stored layout
+            Self::BTree { fanout, .. } => write!(f, "btree(fanout = {fanout}, persist)"),
+            Self::PGM { epsilon } => write!(f, "pgm(epsilon = {epsilon})"),
+            Self::Custom(custom) => custom.fmt(f),
         }
     }
 }
@@ -246,33 +601,58 @@ impl BaseComponent {
                 Component::BTree {
                     fanout,
                     persist: false,
+                    mmap,
+                    search,
+                    fill,
+                    split,
                 },
                 false,
             ) => Some(Self::BTree {
                 fanout,
                 persist: PersistType::InMemory,
+                mmap,
+                search,
+                fill,
+                split,
             }),
             (
                 Component::BTree {
                     fanout,
                     persist: true,
+                    mmap,
+                    search,
+                    fill,
+                    split,
                 },
                 false,
             ) => Some(Self::BTree {
                 fanout,
                 persist: PersistType::BoundaryDisk,
+                mmap,
+                search,
+                fill,
+                split,
             }),
             (
                 Component::BTree {
                     fanout,
                     persist: true,
+                    mmap,
+                    search,
+                    fill,
+                    split,
                 },
                 true,
             ) => Some(Self::BTree {
                 fanout,
                 persist: PersistType::DeepDisk,
+                mmap,
+                search,
+                fill,
+                split,
             }),
-            (Component::PGM {epsilon}, _) => Some(Self::PGM {epsilon}),
+            (Component::PGM { epsilon }, _) => Some(Self::PGM { epsilon }),
+            (Component::Custom(custom), _) => Some(Self::Custom(custom)),
             _ => None,
         }
     }
@@ -282,23 +662,44 @@ impl BaseComponent {
             BaseComponent::BTree {
                 fanout,
                 persist: PersistType::InMemory,
-            } => quote!(BTreeBaseComponent<K, V, #fanout, #base_address>).to_token_stream(),
+                search,
+                fill,
+                split,
+                ..
+            } => quote!(BTreeBaseComponent<K, V, #fanout, #base_address, #search, #fill, #split>)
+                .to_token_stream(),
 
             BaseComponent::BTree {
                 fanout,
                 persist: PersistType::BoundaryDisk,
-            } => quote!(BoundaryDiskBTreeBaseComponent<K, V, #fanout, #base_address>)
-                .to_token_stream(),
+                mmap,
+                search,
+                fill,
+                split,
+            } => {
+                let map = node_map(mmap, quote!(V), fanout);
+                quote!(BoundaryDiskBTreeBaseComponent<K, V, #fanout, #base_address, #map, #search, #fill, #split>)
+                    .to_token_stream()
+            }
 
             BaseComponent::BTree {
                 fanout,
                 persist: PersistType::DeepDisk,
-            } => quote!(DeepDiskBTreeBaseComponent<K, V, #fanout, #base_address>)
-                .to_token_stream(),
-            
-            BaseComponent::PGM {
-                epsilon
-            } => quote!(PGMBaseComponent<K, V, #epsilon, #base_address>).to_token_stream(),
+                mmap,
+                search,
+                fill,
+                split,
+            } => {
+                let map = node_map(mmap, quote!(V), fanout);
+                quote!(DeepDiskBTreeBaseComponent<K, V, #fanout, #base_address, #map, #search, #fill, #split>)
+                    .to_token_stream()
+            }
+
+            BaseComponent::PGM { epsilon } => {
+                quote!(PGMBaseComponent<K, V, #epsilon, #base_address>).to_token_stream()
+            }
+
+            BaseComponent::Custom(ref custom) => custom.component_type(quote!(#base_address)),
         }
     }
 
@@ -319,9 +720,9 @@ impl BaseComponent {
                 ..
             } => quote!(DeepDiskBTreeBaseAddress).to_token_stream(),
 
-            BaseComponent::PGM {
-                ..
-            } => quote!(PGMBaseAddress).to_token_stream(),
+            BaseComponent::PGM { .. } => quote!(PGMBaseAddress).to_token_stream(),
+
+            BaseComponent::Custom(ref custom) => custom.address_type(),
         }
     }
 
@@ -329,8 +730,174 @@ impl BaseComponent {
         match *self {
             BaseComponent::BTree { persist, .. } => persist != PersistType::InMemory,
             BaseComponent::PGM { .. } => false,
+            BaseComponent::Custom(ref custom) => custom.is_persisted(),
         }
     }
+
+    pub fn is_mapped(&self) -> bool {
+        matches!(*self, BaseComponent::BTree { mmap: true, .. })
+    }
+
+    pub fn is_interpolated(&self) -> bool {
+        matches!(
+            *self,
+            BaseComponent::BTree {
+                search: SearchStrategy::Interpolation,
+                ..
+            }
+        )
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match *self {
+            BaseComponent::BTree { .. } => "btree",
+            BaseComponent::PGM { .. } => "pgm",
+            BaseComponent::Custom(_) => "custom",
+        }
+    }
+
+    /// Parameters of the component along with their values, as written
+    pub fn parameters(&self) -> Vec<(String, String)> {
+        match *self {
+            BaseComponent::BTree {
+                fanout,
+                mmap,
+                search,
+                fill,
+                split,
+                ..
+            } => {
+                let mut parameters = vec![("fanout".to_string(), fanout.to_string())];
+                if mmap {
+                    parameters.push(("mmap".to_string(), "true".to_string()));
+                }
+                if search != SearchStrategy::Optimal {
+                    parameters.push(("search".to_string(), search.to_string()));
+                }
+                if fill != DEFAULT_FILL {
+                    parameters.push(("fill".to_string(), (fill as f64 / 100.0).to_string()));
+                }
+                if split != SplitPolicy::Middle {
+                    parameters.push(("split".to_string(), split.to_string()));
+                }
+
+                parameters
+            }
+            BaseComponent::PGM { epsilon } => vec![("epsilon".to_string(), epsilon.to_string())],
+            BaseComponent::Custom(ref custom) => custom.parameters(),
+        }
+    }
+}
+
+/// Percentage of the fanout bulk-built B-tree nodes are filled to, without `fill`
+const DEFAULT_FILL: usize = 50;
+
+/// Strategy used to search the entries of B-tree nodes, set with `search = ...`
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SearchStrategy {
+    /// Linear search on small nodes and binary search on large ones, used without `search`
+    Optimal,
+    Linear,
+    Binary,
+    Interpolation,
+    /// The branch-free chunked scan of `ChunkedSearch`, named `simd` in layouts
+    Simd,
+}
+
+impl SearchStrategy {
+    fn parse(ident: &Ident) -> syn::Result<Self> {
+        Ok(match ident.to_string().as_str() {
+            "linear" => Self::Linear,
+            "binary" => Self::Binary,
+            "interpolation" => Self::Interpolation,
+            "simd" => Self::Simd,
+            _ => {
+                bail!(
+                    ident,
+                    "Unknown search strategy `{}`, expected one of `linear`, `binary`, \
+                     `interpolation` or `simd`!",
+                    ident
+                );
+            }
+        })
+    }
+}
+
+impl std::fmt::Display for SearchStrategy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Optimal => write!(f, "optimal"),
+            Self::Linear => write!(f, "linear"),
+            Self::Binary => write!(f, "binary"),
+            Self::Interpolation => write!(f, "interpolation"),
+            Self::Simd => write!(f, "simd"),
+        }
+    }
+}
+
+impl ToTokens for SearchStrategy {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        tokens.extend(match self {
+            Self::Optimal => quote!(OptimalSearch),
+            Self::Linear => quote!(LinearSearch),
+            Self::Binary => quote!(BinarySearch),
+            Self::Interpolation => quote!(InterpolationSearch),
+            Self::Simd => quote!(ChunkedSearch),
+        })
+    }
+}
+
+/// How full B-tree nodes are split, set with `split = ...`
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SplitPolicy {
+    /// Always move the upper half of the entries into a new node, used without `split`
+    Middle,
+    /// Keep the last node of the layer full when keys are appended past its end, and start a new
+    /// node for them instead
+    Rightmost,
+}
+
+impl SplitPolicy {
+    fn parse(ident: &Ident) -> syn::Result<Self> {
+        Ok(match ident.to_string().as_str() {
+            "middle" => Self::Middle,
+            "rightmost" => Self::Rightmost,
+            _ => {
+                bail!(
+                    ident,
+                    "Unknown split policy `{}`, expected `middle` or `rightmost`!",
+                    ident
+                );
+            }
+        })
+    }
+}
+
+impl std::fmt::Display for SplitPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Middle => write!(f, "middle"),
+            Self::Rightmost => write!(f, "rightmost"),
+        }
+    }
+}
+
+impl ToTokens for SplitPolicy {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        tokens.extend(match self {
+            Self::Middle => quote!(false),
+            Self::Rightmost => quote!(true),
+        })
+    }
+}
+
+// Node map of a disk B-tree component, whose nodes hold `value` entries
+fn node_map(mmap: bool, value: impl ToTokens, fanout: usize) -> TokenStream {
+    if mmap {
+        quote!(MappedNodes<K, #value, #fanout>)
+    } else {
+        quote!(())
+    }
 }
 
 use std::borrow::Borrow;
@@ -353,6 +920,34 @@ impl Attributes {
         bail!(ident, "Could not find required attribute `{}`!", name);
     }
 
+    fn try_get_float(&mut self, name: &str) -> syn::Result<Option<f64>> {
+        if let Some(attr) = self.attrs.take(name) {
+            if let Some(value) = attr.try_get_float() {
+                return value.map(Some);
+            }
+
+            bail!(attr.key(), "Failed to parse number attribute `{}`!", name);
+        }
+
+        Ok(None)
+    }
+
+    fn try_get_ident(&mut self, name: &str) -> syn::Result<Option<Ident>> {
+        if let Some(attr) = self.attrs.take(name) {
+            if let Some(value) = attr.try_get_ident() {
+                return Ok(Some(value));
+            }
+
+            bail!(
+                attr.key(),
+                "Failed to parse attribute `{}`, expected an identifier!",
+                name
+            );
+        }
+
+        Ok(None)
+    }
+
     fn try_get_bool(&mut self, name: &str) -> syn::Result<bool> {
         if let Some(attr) = self.attrs.take(name) {
             if let Some(value) = attr.try_get_bool() {
@@ -418,6 +1013,28 @@ impl Attribute {
         None
     }
 
+    // Try parsing the attribute as a float, integers included
+    pub fn try_get_float(&self) -> Option<syn::Result<f64>> {
+        if let Some(Expr::Lit(expr)) = self.value.clone() {
+            match expr.lit {
+                Lit::Float(float) => return Some(float.base10_parse()),
+                Lit::Int(integer) => return Some(integer.base10_parse()),
+                _ => (),
+            }
+        }
+
+        None
+    }
+
+    // Try parsing the attribute as a plain identifier
+    pub fn try_get_ident(&self) -> Option<Ident> {
+        if let Some(Expr::Path(expr)) = self.value.clone() {
+            return expr.path.get_ident().cloned();
+        }
+
+        None
+    }
+
     // Try parsing the attribute as a boolean
     pub fn try_get_bool(&self) -> Option<bool> {
         if self.value.is_none() {
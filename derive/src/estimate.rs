@@ -0,0 +1,248 @@
+//! A rough cost model of layouts, used to warn about questionable layouts and to generate
+//! `LAYOUT_REPORT`.
+
+use crate::component::{BaseComponent, InternalComponent, PersistType};
+use crate::HybridLayout;
+use proc_macro2::Span;
+
+/// Expected fraction of a B-tree node in use, the classical occupancy of nodes which are split
+/// in half as they fill up (ln 2)
+const BTREE_FILL: f64 = 0.69;
+
+/// Fanouts below this make for deep layouts of tiny nodes, or many tiny reads on disk
+const MIN_FANOUT: usize = 8;
+
+/// Epsilons below this make for learned segments covering only a handful of keys
+const MIN_EPSILON: usize = 4;
+
+/// Number of keys up to which every layer is expected to be useful
+const MAX_KEYS: f64 = 1e9;
+
+/// Number of keys the report is estimated for
+pub const REPORT_KEYS: f64 = 1e6;
+
+/// Capacity of the nodes of the `BTreeMap` behind the top component
+pub const TOP_CAPACITY: usize = 11;
+
+/// A layer below the top component
+pub struct Layer {
+    pub description: String,
+    pub span: Span,
+    pub fanout: Option<usize>,
+    pub epsilon: Option<usize>,
+    pub persisted: bool,
+}
+
+impl Layer {
+    /// Estimated number of entries per node, or `None` for custom components
+    pub fn entries_per_node(&self) -> Option<f64> {
+        match (self.fanout, self.epsilon) {
+            (Some(fanout), _) => Some((fanout as f64 * BTREE_FILL).max(1.0)),
+            // Segments of a PGM cover at least `2 * epsilon` keys
+            (_, Some(epsilon)) => Some(2.0 * epsilon as f64),
+            _ => None,
+        }
+    }
+}
+
+/// The layers below the top component, from the base upwards
+pub fn layers(layout: &HybridLayout) -> Vec<Layer> {
+    let internal = layout.internal.iter().map(|component| {
+        let (fanout, epsilon, persisted) = match component {
+            InternalComponent::BTree {
+                fanout, persist, ..
+            } => (Some(*fanout), None, *persist != PersistType::InMemory),
+            InternalComponent::PGM { epsilon } => (None, Some(*epsilon), false),
+            InternalComponent::Custom(custom) => (None, None, custom.is_persisted()),
+        };
+
+        (component.to_string(), fanout, epsilon, persisted)
+    });
+
+    let (fanout, epsilon, persisted) = match &layout.base {
+        BaseComponent::BTree {
+            fanout, persist, ..
+        } => (Some(*fanout), None, *persist != PersistType::InMemory),
+        BaseComponent::PGM { epsilon } => (None, Some(*epsilon), false),
+        BaseComponent::Custom(custom) => (None, None, custom.is_persisted()),
+    };
+
+    let base = (layout.base.to_string(), fanout, epsilon, persisted);
+
+    // Spans are listed from the top down, starting with the top component
+    let mut layers: Vec<Layer> = internal
+        .chain(std::iter::once(base))
+        .zip(layout.spans.iter().skip(1))
+        .map(|((description, fanout, epsilon, persisted), span)| Layer {
+            description,
+            span: *span,
+            fanout,
+            epsilon,
+            persisted,
+        })
+        .collect();
+
+    layers.reverse();
+    layers
+}
+
+/// Estimated number of nodes of every layer from the base upwards for `keys` keys, followed by
+/// the number of entries of the top component. Estimates stop at the first custom component.
+pub fn nodes(layers: &[Layer], keys: f64) -> Vec<Option<f64>> {
+    let mut entries = Some(keys);
+    let mut nodes = Vec::new();
+
+    for layer in layers {
+        entries = entries
+            .zip(layer.entries_per_node())
+            .map(|(entries, per_node)| (entries / per_node).ceil().max(1.0));
+
+        nodes.push(entries);
+    }
+
+    nodes.push(entries);
+    nodes
+}
+
+/// Estimated number of nodes of the top component holding `entries` entries
+pub fn top_nodes(entries: f64) -> f64 {
+    (entries / (TOP_CAPACITY as f64 * BTREE_FILL))
+        .ceil()
+        .max(1.0)
+}
+
+/// Expected number of layers holding more than one node for `keys` keys, including the top
+pub fn depth(layers: &[Layer], keys: f64) -> usize {
+    let nodes = nodes(layers, keys);
+    let deep = nodes[..layers.len()]
+        .iter()
+        .filter(|nodes| nodes.is_none_or(|nodes| nodes > 1.0))
+        .count();
+
+    deep + 1
+}
+
+/// Warnings about questionable parts of a layout, along with the components they are about
+pub fn lints(layout: &HybridLayout) -> Vec<(Span, String)> {
+    let layers = layers(layout);
+    let mut lints = Vec::new();
+
+    for layer in layers.iter() {
+        match (layer.fanout, layer.epsilon) {
+            (Some(fanout), _) if fanout < MIN_FANOUT && layer.persisted => lints.push((
+                layer.span,
+                format!(
+                    "`{}` reads a separate page from disk for every {fanout} keys at most, \
+                     consider a fanout of at least {MIN_FANOUT}",
+                    layer.description
+                ),
+            )),
+            (Some(fanout), _) if fanout < MIN_FANOUT => lints.push((
+                layer.span,
+                format!(
+                    "`{}` makes for a deep layer of tiny nodes, consider a fanout of at least \
+                     {MIN_FANOUT}",
+                    layer.description
+                ),
+            )),
+            (_, Some(epsilon)) if epsilon < MIN_EPSILON => lints.push((
+                layer.span,
+                format!(
+                    "`{}` makes for segments covering only a handful of keys, consider an \
+                     epsilon of at least {MIN_EPSILON}",
+                    layer.description
+                ),
+            )),
+            _ => (),
+        }
+    }
+
+    // A layer whose entries all point into a single node of the layer below is redundant
+    let nodes = nodes(&layers, MAX_KEYS);
+    for (layer, entries) in layers.iter().skip(1).zip(nodes.iter()) {
+        if entries.is_some_and(|entries| entries < 2.0) {
+            lints.push((
+                layer.span,
+                format!(
+                    "`{}` indexes a single node even with a billion keys, and only adds a step \
+                     to every lookup",
+                    layer.description
+                ),
+            ));
+        }
+    }
+
+    lints
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(input: &str) -> HybridLayout {
+        syn::parse_str(input).unwrap()
+    }
+
+    fn messages(input: &str) -> Vec<String> {
+        lints(&parse(input))
+            .into_iter()
+            .map(|(_, message)| message)
+            .collect()
+    }
+
+    #[test]
+    fn estimates() {
+        let layout = parse("btree_top(), btree(fanout = 8), btree(fanout = 64, persist)");
+        let estimated = layers(&layout);
+
+        assert_eq!(estimated.len(), 2);
+        assert_eq!(estimated[0].description, "btree(fanout = 64, persist)");
+        assert!(estimated[0].persisted && !estimated[1].persisted);
+
+        // 1e6 / 44.16 and 22645 / 5.52 nodes, then one top entry per node
+        assert_eq!(
+            nodes(&estimated, REPORT_KEYS),
+            [Some(22645.0), Some(4103.0), Some(4103.0)]
+        );
+        assert_eq!(depth(&estimated, REPORT_KEYS), 3);
+
+        // Estimates stop at custom components
+        let layout = parse("btree_top(), btree(fanout = 8), custom(my::Component, persist)");
+        assert_eq!(nodes(&layers(&layout), REPORT_KEYS), [None, None, None]);
+        assert_eq!(depth(&layers(&layout), REPORT_KEYS), 3);
+    }
+
+    #[test]
+    fn questionable_layouts() {
+        assert!(messages("btree_top(), btree(fanout = 8), btree(fanout = 64, persist)").is_empty());
+
+        assert_eq!(
+            messages("btree_top(), btree(fanout = 4), btree(fanout = 2, persist)"),
+            [
+                "`btree(fanout = 2, persist)` reads a separate page from disk for every 2 keys \
+                 at most, consider a fanout of at least 8",
+                "`btree(fanout = 4)` makes for a deep layer of tiny nodes, consider a fanout of \
+                 at least 8"
+            ]
+        );
+
+        assert_eq!(
+            messages("btree_top(), pgm(epsilon = 2), btree(fanout = 32)"),
+            [
+                "`pgm(epsilon = 2)` makes for segments covering only a handful of keys, \
+                 consider an epsilon of at least 4"
+            ]
+        );
+
+        // Five layers of fanout 512 already reduce a billion keys to a single node
+        let messages = messages(
+            "btree_top(), btree(fanout = 512), btree(fanout = 512), btree(fanout = 512), \
+             btree(fanout = 512), btree(fanout = 512), btree(fanout = 512)",
+        );
+        assert_eq!(messages.len(), 2);
+        assert!(messages[0].ends_with(
+            "indexes a single node even with a billion keys, and \
+                                       only adds a step to every lookup"
+        ));
+    }
+}
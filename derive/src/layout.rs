@@ -1,5 +1,5 @@
-use crate::bail;
 use crate::component::{BaseComponent, InternalComponent, ParsedComponent, TopComponent};
+use proc_macro2::Span;
 use syn::parse::Parse;
 use syn::Token;
 
@@ -8,6 +8,9 @@ pub struct HybridLayout {
     pub top: TopComponent,
     pub internal: Vec<InternalComponent>,
     pub base: BaseComponent,
+
+    // Spans of the components from the top down, which lints point at
+    pub spans: Vec<Span>,
 }
 
 impl HybridLayout {
@@ -18,28 +21,42 @@ impl HybridLayout {
             || self.base.is_persisted()
     }
 
-    pub fn persist_checksum(&self) -> String {
-        let mut feed = self.base.to_string();
-        for component in self
-            .internal
+    /// Whether any component maps its nodes, which requires plain-old-data keys
+    pub fn is_mapped(&self) -> bool {
+        self.internal.iter().any(|component| component.is_mapped()) || self.base.is_mapped()
+    }
+
+    /// Whether any component uses interpolation search, which requires `Interpolate` keys
+    pub fn is_interpolated(&self) -> bool {
+        self.internal
             .iter()
-            .filter(|component| component.is_persisted())
-        {
-            feed += &component.to_string();
-        }
+            .any(|component| component.is_interpolated())
+            || self.base.is_interpolated()
+    }
 
-        use base64::prelude::*;
-        BASE64_URL_SAFE.encode(md5::compute(feed).to_vec())
+    /// Whether any component is user-defined, whose path is relative to the invoking scope
+    pub fn is_custom(&self) -> bool {
+        self.internal
+            .iter()
+            .any(|component| matches!(component, InternalComponent::Custom(_)))
+            || matches!(self.base, BaseComponent::Custom(_))
     }
-}
 
-impl Parse for HybridLayout {
-    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
-        // Get all of the components in order
-        let components: Vec<ParsedComponent> = input
-            .parse_terminated(ParsedComponent::parse, Token![,])
-            .map(|parsed| parsed.into_iter().collect())?;
+    /// Describe every component from the top down, along with whether it is persisted
+    pub fn describe(&self) -> Vec<(String, bool)> {
+        let mut components = vec![(self.top.to_string(), false)];
 
+        for component in self.internal.iter() {
+            components.push((component.to_string(), component.is_persisted()));
+        }
+
+        components.push((self.base.to_string(), self.base.is_persisted()));
+        components
+    }
+
+    /// Validate a layout, returning the index of the offending component on failure if there
+    /// is one
+    pub fn new(components: &[ParsedComponent]) -> Result<Self, (Option<usize>, &'static str)> {
         let mut in_persisted_region: bool = false;
 
         // Parse the top component
@@ -50,24 +67,29 @@ impl Parse for HybridLayout {
             if let Some(top_component) = TopComponent::try_new(first.into()) {
                 top = top_component;
             } else {
-                bail!(first.ident(), "Invalid top component type!");
+                return Err((Some(0), "Invalid top component type!"));
             }
         } else {
-            bail!("Empty layout!");
+            return Err((None, "Empty layout!"));
         }
 
         // Parse the internal components
         if components.len() == 1 {
-            bail!("No internal or base layers specified!");
+            return Err((None, "No internal or base layers specified!"));
         }
 
         let mut internal = Vec::new();
-        for parsed in &components[1..components.len() - 1] {
+        for (index, parsed) in components
+            .iter()
+            .enumerate()
+            .take(components.len() - 1)
+            .skip(1)
+        {
             if !parsed.is_persisted() && in_persisted_region {
-                bail!(
-                    parsed.ident(),
-                    "Cannot have an in-memory component below a persisted component!"
-                );
+                return Err((
+                    Some(index),
+                    "Cannot have an in-memory component below a persisted component!",
+                ));
             }
 
             let is_parent_persisted = parsed.is_persisted() && in_persisted_region;
@@ -78,7 +100,7 @@ impl Parse for HybridLayout {
             {
                 internal.push(internal_component);
             } else {
-                bail!(parsed.ident(), "Invalid internal component type!");
+                return Err((Some(index), "Invalid internal component type!"));
             }
         }
 
@@ -89,13 +111,31 @@ impl Parse for HybridLayout {
         {
             base = base_component;
         } else {
-            bail!("Invalid base component type!")
+            return Err((Some(components.len() - 1), "Invalid base component type!"));
         }
 
         Ok(Self {
             top,
             internal,
             base,
+            spans: components
+                .iter()
+                .map(|component| component.ident().span())
+                .collect(),
+        })
+    }
+}
+
+impl Parse for HybridLayout {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        // Get all of the components in order
+        let components: Vec<ParsedComponent> = input
+            .parse_terminated(ParsedComponent::parse, Token![,])
+            .map(|parsed| parsed.into_iter().collect())?;
+
+        Self::new(&components).map_err(|(index, message)| match index {
+            Some(index) => syn::Error::new_spanned(components[index].ident(), message),
+            None => syn::Error::new(Span::call_site(), message),
         })
     }
 }
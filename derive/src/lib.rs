@@ -1,14 +1,80 @@
+use codegen::Store;
+use component::ParsedComponent;
+use layout_file::{Format, LayoutFile, Position};
 use proc_macro2::{Ident, TokenStream};
+use std::path::PathBuf;
 use syn::bracketed;
-use syn::parse::Parse;
-use syn::parse_macro_input;
-use syn::{LitStr, Token};
+use syn::parse::{Parse, ParseStream};
+use syn::{parse_macro_input, parse_quote};
+use syn::{GenericParam, ItemStruct, LitBool, LitStr, Token, Type};
 
 #[proc_macro]
 pub fn create_kv_store(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     let input = parse_macro_input!(input as MacroInput);
 
-    codegen::create_implementation(input.name, input.layout)
+    let store = Store {
+        name: input.name,
+        attrs: Vec::new(),
+        vis: parse_quote!(pub),
+        types: input.fields.types.clone(),
+    };
+
+    expand(store, input.fields)
+}
+
+#[proc_macro_attribute]
+pub fn store(
+    attr: proc_macro::TokenStream,
+    item: proc_macro::TokenStream,
+) -> proc_macro::TokenStream {
+    let attribute = parse_macro_input!(attr as StoreAttribute);
+    let item = parse_macro_input!(item as ItemStruct);
+
+    let store = match store_types(&item, &attribute.fields) {
+        Ok(types) => Store {
+            name: item.ident.clone(),
+            attrs: item.attrs,
+            vis: item.vis,
+            types,
+        },
+        Err(error) => return error.to_compile_error().into(),
+    };
+
+    // A layout file may give the name of the store as well
+    if let Some(name) = attribute
+        .fields
+        .name
+        .as_ref()
+        .filter(|name| **name != item.ident)
+    {
+        let message = format!(
+            "The store is named `{}` by the layout file, not `{}`!",
+            name, item.ident
+        );
+
+        return syn::Error::new_spanned(&item.ident, message)
+            .to_compile_error()
+            .into();
+    }
+
+    expand(store, attribute.fields)
+}
+
+fn expand(store: Store, fields: Fields) -> proc_macro::TokenStream {
+    // Questionable layouts are warned about, or rejected in strict mode
+    let lints = estimate::lints(&fields.layout);
+    if fields.strict && !lints.is_empty() {
+        let mut errors = lints
+            .into_iter()
+            .map(|(span, message)| syn::Error::new(span, message));
+
+        let mut error = errors.next().unwrap();
+        errors.for_each(|next| error.combine(next));
+
+        return error.to_compile_error().into();
+    }
+
+    codegen::create_implementation(store, fields.layout, fields.report, &lints)
 }
 
 macro_rules! bail {
@@ -30,30 +96,82 @@ pub(crate) use bail;
 
 mod codegen;
 mod component;
+mod estimate;
 mod layout;
 
 use layout::HybridLayout;
 
 struct MacroInput {
     name: Ident,
-    layout: HybridLayout,
+    fields: Fields,
 }
 
 impl Parse for MacroInput {
-    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let fields = Fields::parse(input, false)?;
+
+        let Some(name) = fields.name.clone() else {
+            bail!("No `name` specified!");
+        };
+
+        Ok(Self { name, fields })
+    }
+}
+
+struct StoreAttribute {
+    fields: Fields,
+}
+
+impl Parse for StoreAttribute {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        Ok(Self {
+            fields: Fields::parse(input, true)?,
+        })
+    }
+}
+
+/// Fields shared by `create_kv_store!` and `#[store]`
+struct Fields {
+    // Name of the store, if given by the input or by the layout file
+    name: Option<Ident>,
+    layout: HybridLayout,
+
+    // Concrete key and value types of the store
+    types: Option<(Type, Type)>,
+
+    // Whether to generate `LAYOUT_REPORT`
+    report: bool,
+
+    // Whether questionable layouts are errors instead of warnings
+    strict: bool,
+}
+
+impl Fields {
+    /// Parse the fields of `create_kv_store!`, separated from their values by `:`, or the
+    /// arguments of `#[store]`, separated from their values by `=`. The attribute takes the
+    /// name of the store from the struct instead.
+    fn parse(input: ParseStream, attribute: bool) -> syn::Result<Self> {
         let mut name = None;
         let mut path = None;
         let mut layout = None;
+        let mut key = None;
+        let mut value = None;
+        let mut report = None;
+        let mut strict = None;
 
         // Parse the fields of the input struct
         while !input.is_empty() {
             let field_ident = input.parse::<Ident>()?;
-            input.parse::<Token![:]>()?;
+            if attribute {
+                input.parse::<Token![=]>()?;
+            } else {
+                input.parse::<Token![:]>()?;
+            }
             let field = field_ident.to_string();
 
             // Decide how to parse the expression
             match field.as_str() {
-                "name" => {
+                "name" if !attribute => {
                     if name.is_some() {
                         bail!(field_ident, "`name` is already defined!");
                     }
@@ -79,6 +197,34 @@ impl Parse for MacroInput {
                     let layout_stream: TokenStream = layout_buffer.parse()?;
                     layout = Some(layout_stream);
                 }
+                "key" => {
+                    if key.is_some() {
+                        bail!(field_ident, "`key` is already defined!");
+                    }
+
+                    key = Some(input.parse::<Type>()?);
+                }
+                "value" => {
+                    if value.is_some() {
+                        bail!(field_ident, "`value` is already defined!");
+                    }
+
+                    value = Some(input.parse::<Type>()?);
+                }
+                "report" => {
+                    if report.is_some() {
+                        bail!(field_ident, "`report` is already defined!");
+                    }
+
+                    report = Some(input.parse::<LitBool>()?.value);
+                }
+                "strict" => {
+                    if strict.is_some() {
+                        bail!(field_ident, "`strict` is already defined!");
+                    }
+
+                    strict = Some(input.parse::<LitBool>()?.value);
+                }
                 field => {
                     bail!(field_ident, "No rule to process field `{}`!", field);
                 }
@@ -89,49 +235,173 @@ impl Parse for MacroInput {
                 bail!(field_ident, "Cannot have both `layout` and `path` fields!");
             }
 
-            if let Some(ref path) = path {
-                let file_contents = std::fs::read_to_string(path.value());
-
-                // Try reading from the file
-                #[allow(clippy::needless_late_init)]
-                let layout_contents;
-                match file_contents {
-                    Ok(contents) => {
-                        layout_contents = contents;
-                    }
-                    Err(error) => {
-                        bail!(path, error.to_string());
-                    }
-                }
-
-                // Parse the contents
-                layout = Some(syn::parse_str(layout_contents.as_str())?);
-            }
-
             if !input.is_empty() {
                 input.parse::<Token![,]>()?;
             }
         }
 
-        let layout_stream: proc_macro::TokenStream;
-        if let Some(layout) = layout {
-            layout_stream = layout.into();
-        } else {
-            bail!("No `layout` or `path` specified!");
-        }
+        let (layout, file_name) = match (layout, path) {
+            (Some(layout), _) => (syn::parse2(layout)?, None),
+            (None, Some(path)) => read_layout_file(&path)?,
+            (None, None) => bail!("No `layout` or `path` specified!"),
+        };
 
-        let name_ident;
-        if let Some(name) = name {
-            name_ident = name;
-        } else {
-            bail!("No `name` specified!")
-        }
+        let name = match (name, file_name) {
+            (Some(name), Some(file_name)) if name != file_name => {
+                bail!(
+                    name,
+                    "`name` does not match the name `{}` given by the layout file!",
+                    file_name
+                );
+            }
+            (Some(name), _) | (None, Some(name)) => Some(name),
+            (None, None) => None,
+        };
 
-        let layout = syn::parse(layout_stream)?;
+        let types = match (key, value) {
+            (Some(key), Some(value)) => Some((key, value)),
+            (Some(key), None) => {
+                bail!(key, "`value` must be given along with `key`!");
+            }
+            (None, Some(value)) => {
+                bail!(value, "`key` must be given along with `value`!");
+            }
+            (None, None) => None,
+        };
 
         Ok(Self {
-            name: name_ident,
+            name,
             layout,
+            types,
+            report: report.unwrap_or(false),
+            strict: strict.unwrap_or(false),
         })
     }
 }
+
+/// The concrete key and value types of a store declared with `#[store]`, or `None` if it is
+/// generic over `K` and `V`
+fn store_types(item: &ItemStruct, fields: &Fields) -> syn::Result<Option<(Type, Type)>> {
+    if !matches!(item.fields, syn::Fields::Unit) {
+        bail!(
+            item.fields,
+            "Stores are declared as unit structs, such as `struct {};`!",
+            item.ident
+        );
+    }
+
+    if let Some(where_clause) = &item.generics.where_clause {
+        bail!(where_clause, "Stores cannot have a `where` clause!");
+    }
+
+    let params: Vec<_> = item.generics.params.iter().collect();
+    match (params.as_slice(), &fields.types) {
+        ([], Some(types)) => Ok(Some(types.clone())),
+        ([], None) => {
+            bail!(
+                item.ident,
+                "Give the `key` and `value` types of the store, or declare it as `{}<K, V>`!",
+                item.ident
+            );
+        }
+        ([GenericParam::Type(k), GenericParam::Type(v)], None)
+            if k.ident == "K" && v.ident == "V" && k.bounds.is_empty() && v.bounds.is_empty() =>
+        {
+            Ok(None)
+        }
+        ([_, _], Some((key, _))) => {
+            bail!(
+                key,
+                "A store generic over `K` and `V` cannot fix its key or value type!"
+            );
+        }
+        _ => {
+            bail!(
+                item.generics,
+                "Stores are either generic over `<K, V>`, without bounds, or not generic at all!"
+            );
+        }
+    }
+}
+
+/// Read the layout at `path`, relative to the invoking crate, along with the store name if the
+/// file gives one. TOML and JSON files are structured layout files, any other file holds a
+/// layout in the syntax of the `layout` field.
+fn read_layout_file(path: &LitStr) -> syn::Result<(HybridLayout, Option<Ident>)> {
+    let file = match std::env::var_os("CARGO_MANIFEST_DIR") {
+        Some(dir) => PathBuf::from(dir).join(path.value()),
+        None => PathBuf::from(path.value()),
+    };
+
+    let contents = match std::fs::read_to_string(&file) {
+        Ok(contents) => contents,
+        Err(error) => {
+            bail!(path, error.to_string());
+        }
+    };
+
+    let Some(format) = Format::from_path(&file) else {
+        let mut layout: HybridLayout = syn::parse_str(&contents)?;
+        layout.spans = vec![path.span(); layout.spans.len()];
+
+        return Ok((layout, None));
+    };
+
+    // Spans cannot point into the file, so errors point at the path and name the position
+    let error = |message: &dyn std::fmt::Display, position: Position| {
+        let message = format!(
+            "{}:{}:{}: {}",
+            path.value(),
+            position.line,
+            position.column,
+            message
+        );
+        syn::Error::new_spanned(path, message)
+    };
+
+    let layout_file =
+        LayoutFile::parse(&contents, format).map_err(|parse_error| match parse_error.position {
+            Some(position) => error(&parse_error.message, position),
+            None => syn::Error::new_spanned(path, format!("{}: {}", path.value(), parse_error)),
+        })?;
+
+    // Spans of tokens parsed from a string carry no offsets, so parse errors are blamed on the
+    // attribute of the component that brings them about
+    let components = layout_file
+        .components
+        .iter()
+        .map(|component| {
+            syn::parse_str::<ParsedComponent>(component.source()).map_err(|parse_error| {
+                let position = component.blame(|source| {
+                    syn::parse_str::<ParsedComponent>(source)
+                        .err()
+                        .map(|error| error.to_string())
+                });
+                error(&parse_error, position)
+            })
+        })
+        .collect::<syn::Result<Vec<_>>>()?;
+
+    let mut layout = HybridLayout::new(&components).map_err(|(index, message)| match index {
+        Some(index) => error(&message, layout_file.components[index].position()),
+        None => syn::Error::new_spanned(path, format!("{}: {}", path.value(), message)),
+    })?;
+
+    // Lints point at the path as well
+    layout.spans = vec![path.span(); layout.spans.len()];
+
+    let name = match layout_file.name {
+        Some((name, position)) => match syn::parse_str::<Ident>(&name) {
+            Ok(_) => Some(Ident::new(&name, path.span())),
+            Err(_) => {
+                return Err(error(
+                    &format!("`{name}` is not a valid store name!"),
+                    position,
+                ))
+            }
+        },
+        None => None,
+    };
+
+    Ok((layout, name))
+}
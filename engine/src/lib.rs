@@ -53,8 +53,8 @@
 //! We can then use these generated data structures to perform queries:
 //!
 //! ```ignore
-//! // Load the first two layer of the index from memory
-//! let index: ExampleStore<u128, u128> = ExampleStore::open("data/index")?;
+//! // Load the first two layer of the index from memory, creating it if needed
+//! let index: ExampleStore<u128, u128> = ExampleStore::open_or_create("data/index")?;
 //!
 //! index.insert(10, 50)?;
 //! index.insert(20, 60)?;
@@ -63,17 +63,289 @@
 //!
 //! assert_eq!(index.search(10)?, Some(50));
 //! ```
+//!
+//! Stores can also be declared as structs with the `store` attribute, which takes the same
+//! fields as `create_kv_store!` written as `field = value`, and keeps the doc comments,
+//! attributes and visibility of the struct. Declaring the struct as `MyStore<K, V>` makes it
+//! generic over its keys and values, while `key` and `value` fix them instead:
+//!
+//! ```
+//! use limousine_engine as limousine;
+//! use limousine_engine::prelude::*;
+//!
+//! /// Index from timestamps to readings
+//! #[limousine::store(layout = [btree_top(), btree(fanout = 16), btree(fanout = 64)])]
+//! pub(crate) struct GenericStore<K, V>;
+//!
+//! #[limousine::store(
+//!     layout = [btree_top(), btree(fanout = 16), btree(fanout = 64)],
+//!     key = u64,
+//!     value = [u8; 16]
+//! )]
+//! pub struct ReadingStore;
+//!
+//! let mut store = ReadingStore::empty();
+//! store.insert(10, [1; 16]);
+//! assert_eq!(store.search(10), Some([1; 16]));
+//!
+//! let store = GenericStore::<u32, u32>::build((0..100).map(|key| (key, key)));
+//! assert_eq!(store.search(50), Some(50));
+//! ```
+//!
+//! `create_kv_store!` takes `key` and `value` fields as well, as in `key: u64, value: [u8; 16]`.
+//! Stores with fixed types are plain structs without generic parameters, which compile faster and
+//! keep error messages short. Values of in-memory stores only need to be `Clone`, while
+//! persisted stores need `Persisted` keys and values either way:
+//!
+//! ```
+//! use limousine_engine::prelude::*;
+//!
+//! #[derive(Clone, Debug, PartialEq)]
+//! struct Reading(f64);
+//!
+//! create_kv_store! {
+//!     name: ReadingIndex,
+//!     layout: [btree_top(), btree(fanout = 16), btree(fanout = 64)],
+//!     key: u64,
+//!     value: Reading
+//! }
+//!
+//! let mut index = ReadingIndex::empty();
+//! index.insert(10, Reading(0.5));
+//! assert_eq!(index.search(10), Some(Reading(0.5)));
+//! ```
+//!
+//! Persisted BTree layers of read-mostly stores can add the `mmap` attribute, as in
+//! `btree(fanout = 64, persist, mmap)`. Nodes of such layers are mirrored into a fixed-size
+//! memory-mapped file next to the store, and searched in place instead of being deserialized.
+//! This requires plain-old-data keys (and values, for the base layer) implementing `Pod`.
+//!
+//! Nodes of BTree layers are searched with a linear scan while they are small and a binary
+//! search once they get large. The `search` attribute picks another strategy per layer, one of
+//! `linear`, `binary`, `interpolation` (for evenly spread keys, which must implement
+//! `Interpolate`) or `simd` (the branchless scan in fixed-size chunks of `ChunkedSearch`,
+//! suited to large nodes). It only changes how nodes are read, so it can be changed without
+//! rebuilding a persisted store:
+//!
+//! ```
+//! use limousine_engine::prelude::*;
+//!
+//! create_kv_store! {
+//!     name: SearchStore,
+//!     layout: [
+//!         btree_top(),
+//!         btree(fanout = 8, search = linear),
+//!         btree(fanout = 256, search = simd)
+//!     ]
+//! }
+//!
+//! let store = SearchStore::build((0..10_000u64).map(|key| (key, key * 2)));
+//! assert_eq!(store.search(1234), Some(2468));
+//! ```
+//!
+//! Bulk builds, as well as in-memory layers rebuilt when a store is opened, fill the nodes of
+//! BTree layers to half their fanout, leaving room for inserts. The `fill` attribute sets another
+//! fraction of the fanout per layer, as in `btree(fanout = 32, fill = 0.9)`. Dense nodes suit
+//! read-mostly stores, while sparse ones delay splits in stores taking many inserts. Like
+//! `search`, it does not change the stored layout:
+//!
+//! ```
+//! use limousine_engine::prelude::*;
+//!
+//! create_kv_store! {
+//!     name: DenseStore,
+//!     layout: [btree_top(), btree(fanout = 16, fill = 1.0), btree(fanout = 32, fill = 0.9)]
+//! }
+//!
+//! let store = DenseStore::build((0..10_000u64).map(|key| (key, key)));
+//! let layers = store.describe()?;
+//!
+//! // 28 keys per node, 90% of the fanout rounded down
+//! assert_eq!(layers[2].size.nodes, 358);
+//! # Ok::<(), limousine_engine::Error>(())
+//! ```
+//!
+//! Full nodes are split in half when a key is inserted into them, which leaves every node half
+//! empty when keys only ever grow, as timestamps do. With `split = rightmost`, a key appended past
+//! the end of the layer keeps the last node full and starts a new node instead, while other
+//! inserts still split in the middle:
+//!
+//! ```
+//! use limousine_engine::prelude::*;
+//!
+//! create_kv_store! {
+//!     name: TimeSeriesStore,
+//!     layout: [
+//!         btree_top(),
+//!         btree(fanout = 32, split = rightmost),
+//!         btree(fanout = 32, split = rightmost)
+//!     ]
+//! }
+//!
+//! let mut store = TimeSeriesStore::empty();
+//! for timestamp in 0..10_000u64 {
+//!     store.insert(timestamp, timestamp);
+//! }
+//!
+//! // Every node but the last holds 32 keys
+//! assert_eq!(store.describe()?[2].size.nodes, 313);
+//! # Ok::<(), limousine_engine::Error>(())
+//! ```
+//!
+//! In-memory layers above the persisted ones are rebuilt when a store is opened. Closing a store
+//! with `close` checkpoints them instead, and the next open restores them from the checkpoint as
+//! long as no other session modified the store in between. Only opens restored from a checkpoint
+//! avoid reading every node of the topmost persisted layer.
+//!
+//! A store is locked while it is open, so that a second writer fails with `Error::Locked` instead
+//! of corrupting it. `open_read_only` takes a shared lock instead, allowing any number of readers
+//! which never write to the store, as long as no writer has it open. Marble, the default
+//! backend, locks and recovers its directory on open, so a marble store admits a single reader
+//! at a time, and further readers fail with `Error::Locked` until it is closed. Stores with
+//! `Backend::File` share their readers.
+//!
+//! `backup_to` copies all pages of an open store into a new store without closing it, and
+//! `restore_from` replaces a store with such a backup.
+//!
+//! Both in-memory and persisted stores can `export_to` a portable archive of their entries, and
+//! `import_from` one written by a store of any layout with the same key and value types. The
+//! names of these types are only compared as a hint, see `limousine_core::archive`.
+//!
+//! Overwritten and deleted pages leave dead space behind until the store is compacted, which
+//! happens on explicit calls to `compact` and, according to the policy set with
+//! `OpenOptions::compaction`, on `close` (the default), whenever the store is synced or closed
+//! past a fragmentation threshold, or never. `stats` reports the live and freed pages of a store,
+//! its fragmentation, and the pages and bytes of every persisted layer.
+//!
+//! Stores opened with `OpenOptions::encryption` encrypt every page, including their catalogs,
+//! with XChaCha20-Poly1305 under the given key. `rotate_key` re-encrypts an open store with a
+//! new key.
+//!
+//! Layouts can also splice in user-defined components with `custom(path::to::MyComponent, ..)`.
+//! Any other `name = value` attribute is passed as a const generic parameter, in the order they
+//! are written, so `custom(MyComponent, fanout = 16)` becomes `MyComponent<K, V, 16, BA, PA>` in
+//! an internal layer and `MyComponent<K, V, 16, PA>` as the base. The component must implement
+//! the traits in [`component`] for its position, along with `Measure`, with addresses named
+//! after it as in `MyAddress`, or given with `address = Type`. Components which persist their
+//! nodes add the `persist` flag, and in-memory components above them must be serde serializable
+//! to be checkpointed.
+//!
+//! Layouts can also be chosen at runtime, without recompiling, by parsing a `LayoutSpec` from
+//! the same syntax and assembling a `DynamicKVStore` from it. Dynamic stores box every layer
+//! behind a trait object, and only support the power-of-two fanouts and epsilons compiled into
+//! them ahead of time. Their persisted layers share the page format of the
+//! generated stores, so a store can be created with `create_kv_store!` and opened dynamically
+//! with the same layout, or the other way around.
+//!
+//! Instead of an inline `layout`, the macro can read one from a file with `path: "..."`,
+//! relative to the invoking crate. Files ending in `.toml` or `.json` are structured layout
+//! files, which list the components from the top down, each with its `type` and attributes,
+//! and may give the `name` of the store in place of the `name` field:
+//!
+//! ```toml
+//! name = "ExampleStore"
+//!
+//! [[components]]
+//! type = "btree_top"
+//!
+//! [[components]]
+//! type = "btree"
+//! fanout = 64
+//! persist = true
+//! ```
+//!
+//! Errors in such a file report the line and column they were found at, pointing at the
+//! offending attribute of an invalid component where there is one. `LayoutSpec::from_file`
+//! reads the same files at runtime.
+//!
+//! The macro warns about questionable layouts, such as B-tree layers with tiny fanouts or layers
+//! which would index a single node even with a billion keys. Adding `strict: true` turns these
+//! warnings into errors. With `report: true`, the generated store also gets a
+//! `LAYOUT_REPORT` constant estimating the depth of the layout, along with the nodes and bytes
+//! of every layer per million keys:
+//!
+//! ```
+//! use limousine_engine::prelude::*;
+//!
+//! create_kv_store! {
+//!     name: ReportedStore,
+//!     layout: [btree_top(), btree(fanout = 32), btree(fanout = 64)],
+//!     report: true
+//! }
+//!
+//! let report = ReportedStore::<u64, u64>::LAYOUT_REPORT;
+//! assert_eq!(report.layers[1].fanout, Some(32));
+//! println!("{report}");
+//! ```
+//!
+//! The layers of a store are private, and `describe` reports them from the top down instead,
+//! with the kind and parameters of their component, where their nodes are kept, and their
+//! measured number of nodes and keys along with the bytes they take up in memory and on disk:
+//!
+//! ```
+//! use limousine_engine::prelude::*;
+//! use limousine_engine::PersistType;
+//!
+//! create_kv_store! {
+//!     name: DescribedStore,
+//!     layout: [btree_top(), btree(fanout = 8), btree(fanout = 32)]
+//! }
+//!
+//! let store = DescribedStore::build((0..1000u64).map(|key| (key, key)));
+//! let layers = store.describe().unwrap();
+//!
+//! assert_eq!(layers[1].parameters, [("fanout", "8")]);
+//! assert_eq!(layers[2].persist, PersistType::InMemory);
+//! assert_eq!(layers[2].size.keys, 1000);
+//! ```
 #![deny(missing_docs)]
 
 /// Include this at the top of the file when materializing a hybrid index or using a hybrid index.
 pub mod prelude {
-    pub use limousine_derive::create_kv_store;
+    pub use limousine_derive::{create_kv_store, store};
 
     pub use limousine_core::KVStore;
     pub use limousine_core::PersistedKVStore;
+    pub use limousine_core::{Backend, CompactionPolicy, EncryptionKey, OpenOptions};
+    pub use limousine_core::{DynamicKVStore, LayoutSpec};
+}
+
+/// Traits and building blocks for user-defined components used with `custom(...)`.
+pub mod component {
+    pub use limousine_core::{
+        Address, BaseComponent, BoundaryDiskBaseComponent, BoundaryDiskInternalComponent,
+        DeepDiskBaseComponent, DeepDiskInternalComponent, DiskComponent, GlobalStore,
+        InternalComponent, Key, Measure, NodeLayer, Persisted, PropagateInsert, Value,
+    };
+
+    pub use limousine_core::{
+        BTreeBaseAddress, BTreeBaseComponent, BTreeInternalAddress, BTreeInternalComponent,
+        BoundaryDiskBTreeBaseAddress, BoundaryDiskBTreeBaseComponent,
+        BoundaryDiskBTreeInternalAddress, BoundaryDiskBTreeInternalComponent,
+        DeepDiskBTreeBaseAddress, DeepDiskBTreeBaseComponent, DeepDiskBTreeInternalAddress,
+        DeepDiskBTreeInternalComponent,
+    };
+
+    pub use limousine_core::{
+        BinarySearch, ChunkedSearch, Interpolate, InterpolationSearch, LinearSearch, OptimalSearch,
+        Search,
+    };
 }
 
-pub use limousine_core::Result;
+/// Attribute declaring a key-value store, the struct form of `create_kv_store!`.
+pub use limousine_derive::store;
+
+/// Error and result types returned by persisted key-value stores.
+pub use limousine_core::{Error, Result};
+
+/// Storage statistics reported by persisted key-value stores.
+pub use limousine_core::{BackendStats, LayerStats, StoreStats};
+
+/// Layout estimates generated by `create_kv_store!` with `report: true`.
+pub use limousine_core::{LayerReport, LayoutReport};
+
+/// Descriptions of the layers of a store, returned by the generated `describe` method.
+pub use limousine_core::{LayerDescription, LayerSize, PersistType};
 
 #[doc(hidden)]
 pub use limousine_core as private;
@@ -1,6 +1,7 @@
 #[cfg(test)]
 mod tests {
     use limousine_engine::prelude::*;
+    use limousine_engine::Error;
     use rand::{thread_rng, Rng};
     use rand_distr::Uniform;
     use tempfile::tempdir;
@@ -27,12 +28,13 @@ mod tests {
         let values: Vec<V> = (&mut rng).sample_iter(value_dist).take(num).collect();
 
         {
-            let mut kv_store = KV::open(temp_path)?;
+            let mut kv_store = KV::open_or_create(temp_path)?;
 
             // Test inserts
             for i in 0..num {
                 kv_store.insert(keys[i], values[i])?;
             }
+            kv_store.sync()?;
 
             // Test searches
             for i in 0..num {
@@ -42,6 +44,8 @@ mod tests {
             for key in 0..10_000 {
                 assert_eq!(kv_store.search(key as K)?, None);
             }
+
+            kv_store.close()?;
         }
 
         let mut index = KV::open(temp_path)?;
@@ -247,110 +251,1534 @@ mod tests {
     }
 
     #[test]
-    fn test_kv_store_1() {
+    fn test_persisted_kv_store_layout_mismatch() -> limousine_engine::Result<()> {
         create_kv_store! {
             name: KVStore1,
             layout: [
                 btree_top(),
-                btree(fanout = 64),
+                btree(fanout = 8),
+                btree(fanout = 32, persist),
             ]
         }
 
-        test_kv_store::<KVStore1<K, V>>();
+        create_kv_store! {
+            name: KVStore2,
+            layout: [
+                btree_top(),
+                btree(fanout = 16),
+                btree(fanout = 32, persist),
+            ]
+        }
+
+        create_kv_store! {
+            name: KVStore3,
+            layout: [
+                btree_top(),
+                btree(fanout = 8),
+                btree(fanout = 64, persist),
+            ]
+        }
+
+        let temp_dir = tempdir()?;
+        let temp_path = temp_dir.path();
+
+        // Opening a missing store should not create it
+        assert!(KVStore1::<K, V>::open(temp_path).is_err());
+
+        let mut kv_store = KVStore1::<K, V>::open_or_create(temp_path)?;
+        kv_store.insert(1, 2)?;
+        kv_store.close()?;
+
+        // In-memory layers can change freely
+        let kv_store = KVStore2::<K, V>::open(temp_path)?;
+        assert_eq!(kv_store.search(1)?, Some(2));
+        kv_store.close()?;
+
+        // Persisted layers and types cannot
+        assert!(matches!(
+            KVStore3::<K, V>::open(temp_path),
+            Err(limousine_engine::Error::Layout(_))
+        ));
+        assert!(matches!(
+            KVStore1::<K, u64>::open(temp_path),
+            Err(limousine_engine::Error::Layout(_))
+        ));
+
+        Ok(())
     }
 
     #[test]
-    fn test_kv_store_2() {
+    fn test_persisted_kv_store_migrate() -> limousine_engine::Result<()> {
         create_kv_store! {
             name: KVStore1,
             layout: [
                 btree_top(),
-                btree(fanout = 32),
+                btree(fanout = 8),
+                btree(fanout = 32, persist),
             ]
         }
 
-        test_kv_store::<KVStore1<K, V>>();
+        create_kv_store! {
+            name: KVStore2,
+            layout: [
+                btree_top(),
+                btree(fanout = 16, persist),
+                btree(fanout = 64, persist),
+            ]
+        }
+
+        let temp_dir = tempdir()?;
+        let src_path = temp_dir.path().join("src");
+        let dst_path = temp_dir.path().join("dst");
+
+        let mut rng = thread_rng();
+        let keys: Vec<K> = (&mut rng)
+            .sample_iter(Uniform::new(K::MIN, K::MAX))
+            .take(10_000)
+            .collect();
+
+        let mut src = KVStore1::<K, V>::open_or_create(&src_path)?;
+        for &key in keys.iter() {
+            src.insert(key, key / 2)?;
+        }
+        src.close()?;
+
+        let mut dst = KVStore2::<K, V>::migrate_from::<KVStore1<K, V>>(&src_path, &dst_path)?;
+
+        // Entries should come out in sorted order
+        let entries = dst
+            .entries()
+            .collect::<limousine_engine::Result<Vec<_>>>()?;
+        let mut expected: Vec<(K, V)> = keys.iter().map(|&key| (key, key / 2)).collect();
+        expected.sort();
+        expected.dedup_by_key(|(key, _)| *key);
+        assert_eq!(entries, expected);
+
+        for &key in keys.iter() {
+            assert_eq!(dst.search(key)?, Some(key / 2));
+        }
+
+        // The migrated store should accept new inserts
+        for key in 0..1_000 {
+            dst.insert(key, key)?;
+        }
+
+        for key in 0..1_000 {
+            assert_eq!(dst.search(key)?, Some(key));
+        }
+        dst.close()?;
+
+        // Migrating onto an existing store is not allowed
+        assert!(KVStore2::<K, V>::migrate_from::<KVStore1<K, V>>(&src_path, &dst_path).is_err());
+
+        // Sources are read with their own options, and never while a writer has them open
+        let file_src_path = temp_dir.path().join("file_src");
+        let file_dst_path = temp_dir.path().join("file_dst");
+        let options = OpenOptions::new().backend(Backend::File);
+
+        let mut src = KVStore1::<K, V>::open_with(&file_src_path, options.create(true))?;
+        for key in 0..1_000 {
+            src.insert(key, key)?;
+        }
+
+        let migrated = KVStore2::<K, V>::migrate_from_with::<KVStore1<K, V>>(
+            &file_src_path,
+            options,
+            &file_dst_path,
+            options,
+        );
+        assert!(matches!(migrated, Err(Error::Locked(_))));
+        src.close()?;
+
+        // The source is only shared with other readers during the migration
+        let reader = KVStore1::<K, V>::open_with(&file_src_path, options.read_only(true))?;
+        let dst = KVStore2::<K, V>::migrate_from_with::<KVStore1<K, V>>(
+            &file_src_path,
+            options,
+            &file_dst_path,
+            options,
+        )?;
+        for key in 0..1_000 {
+            assert_eq!(dst.search(key)?, Some(key));
+        }
+        dst.close()?;
+        reader.close()?;
+
+        Ok(())
     }
 
     #[test]
-    fn test_kv_store_3() {
+    fn test_persisted_kv_store_backends() -> limousine_engine::Result<()> {
         create_kv_store! {
             name: KVStore1,
             layout: [
                 btree_top(),
-                btree(fanout = 32),
-                btree(fanout = 32),
+                btree(fanout = 8, persist),
+                btree(fanout = 32, persist),
             ]
         }
 
-        test_kv_store::<KVStore1<K, V>>();
+        let temp_dir = tempdir()?;
+        let num = 10_000;
+
+        for backend in [Backend::Memory, Backend::File] {
+            let temp_path = temp_dir.path().join(format!("{backend:?}"));
+            let options = OpenOptions::new().backend(backend).create(true);
+
+            let mut kv_store = KVStore1::<K, V>::open_with(&temp_path, options)?;
+            for key in 0..num {
+                kv_store.insert(key, key * key)?;
+            }
+
+            for key in 0..num {
+                assert_eq!(kv_store.search(key)?, Some(key * key));
+            }
+            kv_store.close()?;
+        }
+
+        // Only the file backend survives reopening
+        let options = OpenOptions::new().backend(Backend::File);
+        let kv_store = KVStore1::<K, V>::open_with(temp_dir.path().join("File"), options)?;
+        for key in 0..num {
+            assert_eq!(kv_store.search(key)?, Some(key * key));
+        }
+
+        let options = OpenOptions::new().backend(Backend::Memory);
+        assert!(KVStore1::<K, V>::open_with(temp_dir.path().join("Memory"), options).is_err());
+
+        Ok(())
     }
 
     #[test]
-    fn test_kv_store_4() {
+    fn test_persisted_kv_store_mmap() -> limousine_engine::Result<()> {
         create_kv_store! {
             name: KVStore1,
             layout: [
                 btree_top(),
                 btree(fanout = 8),
-                btree(fanout = 8),
-                btree(fanout = 32),
+                btree(fanout = 8, persist, mmap),
+                btree(fanout = 32, persist, mmap),
             ]
         }
 
-        test_kv_store::<KVStore1<K, V>>();
+        test_persisted_kv_store::<KVStore1<K, V>>()
     }
 
     #[test]
-    fn test_kv_store_5() {
+    fn test_persisted_kv_store_mmap_rebuild() -> limousine_engine::Result<()> {
         create_kv_store! {
-            name: KVStore1,
+            name: MappedStore,
+            layout: [
+                btree_top(),
+                btree(fanout = 8, persist, mmap),
+                btree(fanout = 32, persist, mmap),
+            ]
+        }
+
+        create_kv_store! {
+            name: UnmappedStore,
+            layout: [
+                btree_top(),
+                btree(fanout = 8, persist),
+                btree(fanout = 32, persist),
+            ]
+        }
+
+        let temp_dir = tempdir()?;
+        let temp_path = temp_dir.path();
+        let num = 5_000;
+
+        // Dropped without closing, so the node maps are never sealed
+        let mut kv_store = MappedStore::<K, V>::open_or_create(temp_path)?;
+        for key in 0..num {
+            kv_store.insert(key, key)?;
+        }
+        drop(kv_store);
+
+        // Modified without the node maps, which must not be trusted afterwards
+        let mut kv_store = UnmappedStore::<K, V>::open(temp_path)?;
+        for key in 0..num {
+            assert_eq!(kv_store.search(key)?, Some(key));
+            kv_store.insert(key, key + 1)?;
+        }
+        kv_store.close()?;
+
+        let kv_store = MappedStore::<K, V>::open(temp_path)?;
+        for key in 0..num {
+            assert_eq!(kv_store.search(key)?, Some(key + 1));
+        }
+        kv_store.close()?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_persisted_kv_store_search() -> limousine_engine::Result<()> {
+        create_kv_store! {
+            name: SearchStore,
+            layout: [
+                btree_top(),
+                btree(fanout = 8, search = linear),
+                btree(fanout = 16, persist, search = binary),
+                btree(fanout = 64, persist, mmap, search = interpolation),
+                btree(fanout = 256, persist, search = simd),
+            ]
+        }
+
+        create_kv_store! {
+            name: DefaultStore,
             layout: [
                 btree_top(),
                 btree(fanout = 8),
+                btree(fanout = 16, persist),
+                btree(fanout = 64, persist, mmap),
+                btree(fanout = 256, persist),
+            ]
+        }
+
+        test_persisted_kv_store::<SearchStore<K, V>>()?;
+
+        let temp_dir = tempdir()?;
+        let temp_path = temp_dir.path();
+        let num = 5_000;
+
+        let kv_store = SearchStore::<K, V>::build(temp_path, (0..num).map(|key| (key * 3, key)))?;
+        let layers = kv_store.describe()?;
+        assert_eq!(
+            layers[1].parameters,
+            [("fanout", "8"), ("search", "linear")]
+        );
+        assert_eq!(
+            layers[3].parameters,
+            [
+                ("fanout", "64"),
+                ("mmap", "true"),
+                ("search", "interpolation")
+            ]
+        );
+        kv_store.close()?;
+
+        // The search strategy does not change the stored layout
+        let mut kv_store = DefaultStore::<K, V>::open(temp_path)?;
+        for key in 0..num {
+            assert_eq!(kv_store.search(key * 3)?, Some(key));
+            assert_eq!(kv_store.search(key * 3 + 1)?, None);
+        }
+        kv_store.insert(-1, -1)?;
+        kv_store.close()?;
+
+        let kv_store = SearchStore::<K, V>::open(temp_path)?;
+        assert_eq!(kv_store.search(-1)?, Some(-1));
+        assert_eq!(kv_store.search(3 * (num - 1))?, Some(num - 1));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_persisted_kv_store_fill() -> limousine_engine::Result<()> {
+        create_kv_store! {
+            name: DenseStore,
+            layout: [
+                btree_top(),
+                btree(fanout = 8, fill = 1.0),
+                btree(fanout = 16, persist, fill = 0.75),
+                btree(fanout = 64, persist, fill = 0.9),
+            ]
+        }
+
+        create_kv_store! {
+            name: SparseStore,
+            layout: [
+                btree_top(),
                 btree(fanout = 8),
+                btree(fanout = 16, persist),
+                btree(fanout = 64, persist),
+            ]
+        }
+
+        test_persisted_kv_store::<DenseStore<K, V>>()?;
+
+        let temp_dir = tempdir()?;
+        let temp_path = temp_dir.path();
+
+        // 57 keys per base node, and 12 base nodes per node above
+        let kv_store = DenseStore::<K, V>::build(temp_path, (0..5_700).map(|key| (key, key)))?;
+        let layers = kv_store.describe()?;
+        assert_eq!(layers[3].parameters, [("fanout", "64"), ("fill", "0.9")]);
+        assert_eq!(layers[3].size.nodes, 100);
+        assert_eq!(layers[2].size.nodes, 9);
+        assert_eq!(layers[1].size.nodes, 2);
+        kv_store.close()?;
+
+        // The fill does not change the stored layout, only how the in-memory layers are rebuilt
+        let mut kv_store = SparseStore::<K, V>::open(temp_path)?;
+        assert_eq!(kv_store.describe()?[3].size.nodes, 100);
+        for key in 0..5_700 {
+            assert_eq!(kv_store.search(key)?, Some(key));
+        }
+        kv_store.insert(-1, -1)?;
+        kv_store.close()?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_persisted_kv_store_split() -> limousine_engine::Result<()> {
+        create_kv_store! {
+            name: AppendStore,
+            layout: [
+                btree_top(),
+                btree(fanout = 8, split = rightmost),
+                btree(fanout = 16, persist, split = rightmost),
+                btree(fanout = 64, persist, split = rightmost),
+            ]
+        }
+
+        create_kv_store! {
+            name: MiddleStore,
+            layout: [
+                btree_top(),
                 btree(fanout = 8),
-                btree(fanout = 32),
+                btree(fanout = 16, persist),
+                btree(fanout = 64, persist),
             ]
         }
 
-        test_kv_store::<KVStore1<K, V>>();
+        test_persisted_kv_store::<AppendStore<K, V>>()?;
+
+        let temp_dir = tempdir()?;
+        let temp_path = temp_dir.path();
+
+        let mut kv_store = AppendStore::<K, V>::open_or_create(temp_path)?;
+        for key in 0..6_400 {
+            kv_store.insert(key, key)?;
+        }
+
+        let layers = kv_store.describe()?;
+        assert_eq!(
+            layers[3].parameters,
+            [("fanout", "64"), ("split", "rightmost")]
+        );
+        assert_eq!(layers[3].size.nodes, 100);
+        assert_eq!(layers[2].size.nodes, 7);
+        kv_store.close()?;
+
+        // The split policy does not change the stored layout
+        let mut kv_store = MiddleStore::<K, V>::open(temp_path)?;
+        assert_eq!(kv_store.describe()?[3].size.nodes, 100);
+        for key in 0..6_400 {
+            assert_eq!(kv_store.search(key)?, Some(key));
+        }
+
+        // Appends past the end split in the middle without the rightmost policy
+        for key in 6_400..12_800 {
+            kv_store.insert(key, key)?;
+        }
+        assert_eq!(kv_store.describe()?[3].size.nodes, 300);
+        kv_store.close()?;
+
+        Ok(())
     }
 
     #[test]
-    fn test_kv_store_6() {
+    fn test_persisted_kv_store_reopen_split() -> limousine_engine::Result<()> {
         create_kv_store! {
             name: KVStore1,
             layout: [
                 btree_top(),
                 btree(fanout = 8),
-                btree(fanout = 8),
-                btree(fanout = 8),
-                btree(fanout = 8),
-                btree(fanout = 8),
-                btree(fanout = 32),
+                btree(fanout = 8, persist),
+                btree(fanout = 8, persist),
             ]
         }
 
-        test_kv_store::<KVStore1<K, V>>();
+        let temp_dir = tempdir()?;
+        let temp_path = temp_dir.path();
+
+        let mut kv_store = KVStore1::<K, V>::open_or_create(temp_path)?;
+        for key in (0..24_000).step_by(3) {
+            kv_store.insert(key, key)?;
+        }
+        kv_store.close()?;
+
+        // Restored from the checkpoint, so boundary parents are only recorded by the inserts
+        let mut kv_store = KVStore1::<K, V>::open(temp_path)?;
+        let nodes = kv_store.describe()?[2].size.nodes;
+        for key in (0..24_000).filter(|key| key % 3 != 0) {
+            kv_store.insert(key, key)?;
+        }
+        assert!(kv_store.describe()?[2].size.nodes > nodes);
+        for key in 0..24_000 {
+            assert_eq!(kv_store.search(key)?, Some(key));
+        }
+        kv_store.sync()?;
+        drop(kv_store);
+
+        // Without a checkpoint the in-memory layers are rebuilt from a scan of the boundary layer
+        let mut kv_store = KVStore1::<K, V>::open(temp_path)?;
+        let nodes = kv_store.describe()?[2].size.nodes;
+        for key in 24_000..48_000 {
+            kv_store.insert(key, key)?;
+        }
+        assert!(kv_store.describe()?[2].size.nodes > nodes);
+        for key in 0..48_000 {
+            assert_eq!(kv_store.search(key)?, Some(key));
+        }
+        kv_store.close()?;
+
+        Ok(())
     }
 
     #[test]
-    fn test_kv_store_7() {
+    fn test_persisted_kv_store_checkpoint() -> limousine_engine::Result<()> {
         create_kv_store! {
             name: KVStore1,
             layout: [
                 btree_top(),
+                pgm(epsilon = 8),
                 btree(fanout = 8),
+                btree(fanout = 8, persist),
+                btree(fanout = 32, persist),
+            ]
+        }
+
+        test_persisted_kv_store::<KVStore1<K, V>>()
+    }
+
+    #[test]
+    fn test_persisted_kv_store_checkpoint_reuse() -> limousine_engine::Result<()> {
+        create_kv_store! {
+            name: CheckpointStore,
+            layout: [
+                btree_top(),
                 btree(fanout = 8),
-                btree(fanout = 8),
-                btree(fanout = 8),
-                btree(fanout = 8),
-                btree(fanout = 8),
+                btree(fanout = 8, persist),
+                btree(fanout = 32, persist),
             ]
         }
 
-        test_kv_store::<KVStore1<K, V>>();
+        let temp_dir = tempdir()?;
+        let temp_path = temp_dir.path();
+        let num = 5_000;
+
+        let mut kv_store = CheckpointStore::<K, V>::open_or_create(temp_path)?;
+        for key in 0..num {
+            kv_store.insert(key, key)?;
+        }
+        kv_store.close()?;
+        assert!(temp_path.join("checkpoint").exists());
+
+        // Restored from the checkpoint, splits must still reach the in-memory layers
+        let mut kv_store = CheckpointStore::<K, V>::open(temp_path)?;
+        for key in 0..num {
+            assert_eq!(kv_store.search(key)?, Some(key));
+        }
+        for key in num..2 * num {
+            kv_store.insert(key, key)?;
+        }
+        drop(kv_store);
+
+        // Dropped without closing, so the checkpoint is stale and must be rebuilt
+        let kv_store = CheckpointStore::<K, V>::open(temp_path)?;
+        for key in 0..2 * num {
+            assert_eq!(kv_store.search(key)?, Some(key));
+        }
+        kv_store.close()?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_persisted_kv_store_compact() -> limousine_engine::Result<()> {
+        create_kv_store! {
+            name: CompactStore,
+            layout: [
+                btree_top(),
+                btree(fanout = 8, persist),
+                btree(fanout = 32, persist),
+            ]
+        }
+
+        let temp_dir = tempdir()?;
+        let temp_path = temp_dir.path();
+        let num = 5_000;
+
+        let options = OpenOptions::new().backend(Backend::File);
+        let mut kv_store = CompactStore::<K, V>::open_with(temp_path, options.create(true))?;
+        let mut rng = thread_rng();
+        for _ in 0..num {
+            let key = rng.sample(Uniform::new(0, 100 * num));
+            kv_store.insert(key, key)?;
+        }
+
+        // Every allocated page besides the global catalog belongs to one of the layers
+        let stats = kv_store.stats()?;
+        assert_eq!(stats.layers.len(), 2);
+        assert!(stats.layers.iter().all(|layer| layer.bytes > 0));
+        let layer_pages: u64 = stats.layers.iter().map(|layer| layer.pages).sum();
+        assert_eq!(layer_pages + 1, stats.live_pages);
+        assert!(stats.layers[0].pages > stats.layers[1].pages);
+
+        kv_store.compact()?;
+        assert!(kv_store.stats()?.backend.total_bytes <= stats.backend.total_bytes);
+        kv_store.close()?;
+
+        let mut kv_store = CompactStore::<K, V>::open_with(temp_path, options.read_only(true))?;
+        assert_eq!(kv_store.stats()?.live_pages, stats.live_pages);
+        assert!(matches!(kv_store.compact(), Err(Error::ReadOnly)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_persisted_kv_store_encryption() -> limousine_engine::Result<()> {
+        create_kv_store! {
+            name: EncryptedStore,
+            layout: [
+                btree_top(),
+                btree(fanout = 8),
+                btree(fanout = 8, persist),
+                btree(fanout = 32, persist),
+            ]
+        }
+
+        create_kv_store! {
+            name: EncryptedMappedStore,
+            layout: [
+                btree_top(),
+                btree(fanout = 32, persist, mmap),
+            ]
+        }
+
+        let temp_dir = tempdir()?;
+        let temp_path = temp_dir.path();
+        let num = 5_000;
+
+        let (key, new_key) = (EncryptionKey::new([7; 32]), EncryptionKey::new([8; 32]));
+        let options = OpenOptions::new().backend(Backend::File);
+
+        let mut kv_store =
+            EncryptedStore::<K, V>::open_with(temp_path, options.encryption(key).create(true))?;
+        for key in 0..num {
+            kv_store.insert(key, key)?;
+        }
+        kv_store.close()?;
+        assert!(!temp_path.join("checkpoint").exists());
+
+        let kv_store = EncryptedStore::<K, V>::open_with(temp_path, options.encryption(new_key));
+        assert!(matches!(kv_store, Err(Error::Encryption(_))));
+
+        let mut kv_store = EncryptedStore::<K, V>::open_with(temp_path, options.encryption(key))?;
+        kv_store.rotate_key(new_key)?;
+        for key in 0..num {
+            assert_eq!(kv_store.search(key)?, Some(key));
+        }
+        kv_store.close()?;
+
+        let kv_store = EncryptedStore::<K, V>::open_with(temp_path, options.encryption(key));
+        assert!(matches!(kv_store, Err(Error::Encryption(_))));
+
+        let kv_store = EncryptedStore::<K, V>::open_with(temp_path, options.encryption(new_key))?;
+        for key in 0..num {
+            assert_eq!(kv_store.search(key)?, Some(key));
+        }
+
+        // Mapped nodes would be written to disk in plaintext
+        let mapped_dir = tempdir()?;
+        let mapped = EncryptedMappedStore::<K, V>::open_with(
+            mapped_dir.path(),
+            options.encryption(key).create(true),
+        );
+        assert!(matches!(mapped, Err(Error::Encryption(_))));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_dynamic_kv_store() -> limousine_engine::Result<()> {
+        create_kv_store! {
+            name: GeneratedStore,
+            layout: [
+                btree_top(),
+                btree(fanout = 8),
+                btree(fanout = 8, persist),
+                btree(fanout = 32, persist),
+            ]
+        }
+
+        let layout: LayoutSpec =
+            "[btree_top(), btree(fanout = 8), btree(fanout = 8, persist), btree(fanout = 32, persist)]"
+                .parse()?;
+
+        let temp_dir = tempdir()?;
+        let temp_path = temp_dir.path();
+        let num = 5_000;
+
+        // A store generated by the macro can be opened dynamically with the same layout
+        let mut kv_store = GeneratedStore::<K, V>::open_or_create(temp_path)?;
+        for key in 0..num {
+            kv_store.insert(key, key)?;
+        }
+        kv_store.close()?;
+
+        let mut dynamic = DynamicKVStore::<K, V>::open(temp_path, &layout)?;
+        for key in 0..num {
+            assert_eq!(dynamic.search(key)?, Some(key));
+            dynamic.insert(key + num, key)?;
+        }
+        dynamic.close()?;
+
+        // And the other way around
+        let kv_store = GeneratedStore::<K, V>::open(temp_path)?;
+        for key in 0..num {
+            assert_eq!(kv_store.search(key)?, Some(key));
+            assert_eq!(kv_store.search(key + num)?, Some(key));
+        }
+        kv_store.close()?;
+
+        let other: LayoutSpec = "[btree_top(), btree(fanout = 64, persist)]".parse()?;
+        let dynamic = DynamicKVStore::<K, V>::open(temp_path, &other);
+        assert!(matches!(dynamic, Err(Error::Layout(_))));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_persisted_kv_store_layout_file() -> limousine_engine::Result<()> {
+        // The name of the store is given by the file
+        create_kv_store! {
+            path: "layouts/ingest.toml"
+        }
+
+        create_kv_store! {
+            name: JsonStore,
+            path: "layouts/ingest.json"
+        }
+
+        test_persisted_kv_store::<IngestStore<K, V>>()?;
+        test_persisted_kv_store::<JsonStore<K, V>>()?;
+
+        let temp_dir = tempdir()?;
+        let temp_path = temp_dir.path();
+
+        let mut kv_store = IngestStore::<K, V>::open_or_create(temp_path)?;
+        for key in 0..1_000 {
+            kv_store.insert(key, key)?;
+        }
+        kv_store.close()?;
+
+        // Both files describe the same layout, which can also be read at runtime
+        let kv_store = JsonStore::<K, V>::open(temp_path)?;
+        assert_eq!(kv_store.search(500)?, Some(500));
+        kv_store.close()?;
+
+        let layout =
+            LayoutSpec::from_file(concat!(env!("CARGO_MANIFEST_DIR"), "/layouts/ingest.toml"))?;
+        assert_eq!(layout.name(), Some("IngestStore"));
+
+        let dynamic = DynamicKVStore::<K, V>::open(temp_path, &layout)?;
+        assert_eq!(dynamic.search(500)?, Some(500));
+        dynamic.close()?;
+
+        Ok(())
+    }
+
+    /// A user-defined base component, which counts the entries inserted through it on top of a
+    /// persisted BTree layer
+    mod wrapped {
+        use limousine_engine::component::*;
+        use limousine_engine::{LayerSize, LayerStats};
+        use std::cell::Cell;
+
+        pub type CountingBaseAddress = BoundaryDiskBTreeBaseAddress;
+
+        pub struct CountingBaseComponent<K, V, const FANOUT: usize, PA>
+        where
+            K: Persisted + Key,
+            V: Persisted,
+        {
+            inner: BoundaryDiskBTreeBaseComponent<K, V, FANOUT, PA>,
+            pub inserts: Cell<usize>,
+        }
+
+        impl<K, V, const FANOUT: usize, PA> NodeLayer<K, CountingBaseAddress, PA>
+            for CountingBaseComponent<K, V, FANOUT, PA>
+        where
+            K: Persisted + Key,
+            V: Persisted,
+            PA: Address,
+        {
+            fn parent(&self, ptr: CountingBaseAddress) -> Option<PA> {
+                self.inner.parent(ptr)
+            }
+
+            fn set_parent(&mut self, ptr: CountingBaseAddress, parent: PA) {
+                self.inner.set_parent(ptr, parent)
+            }
+
+            fn lower_bound(&self, ptr: CountingBaseAddress) -> K {
+                self.inner.lower_bound(ptr)
+            }
+
+            fn next(&self, ptr: CountingBaseAddress) -> Option<CountingBaseAddress> {
+                self.inner.next(ptr)
+            }
+
+            fn prev(&self, ptr: CountingBaseAddress) -> Option<CountingBaseAddress> {
+                self.inner.prev(ptr)
+            }
+
+            fn first(&self) -> CountingBaseAddress {
+                self.inner.first()
+            }
+
+            fn last(&self) -> CountingBaseAddress {
+                self.inner.last()
+            }
+        }
+
+        impl<K, V, const FANOUT: usize, PA> DiskComponent for CountingBaseComponent<K, V, FANOUT, PA>
+        where
+            K: Persisted + Key,
+            V: Persisted,
+            PA: Address,
+        {
+            fn flush(&self) -> limousine_engine::Result<()> {
+                self.inner.flush()
+            }
+
+            fn stats(&self) -> limousine_engine::Result<LayerStats> {
+                self.inner.stats()
+            }
+        }
+
+        impl<K, V, const FANOUT: usize, PA> Measure for CountingBaseComponent<K, V, FANOUT, PA>
+        where
+            K: Persisted + Key,
+            V: Persisted,
+            PA: Address,
+        {
+            fn measure(&self) -> limousine_engine::Result<LayerSize> {
+                self.inner.measure()
+            }
+        }
+
+        impl<K, V, const FANOUT: usize, PA> BoundaryDiskBaseComponent<K, V, CountingBaseAddress, PA>
+            for CountingBaseComponent<K, V, FANOUT, PA>
+        where
+            K: Persisted + Key,
+            V: Persisted,
+            PA: Address,
+        {
+            fn insert(
+                &mut self,
+                ptr: CountingBaseAddress,
+                key: K,
+                value: V,
+            ) -> limousine_engine::Result<Option<PropagateInsert<K, CountingBaseAddress, PA>>>
+            {
+                self.inserts.set(self.inserts.get() + 1);
+                self.inner.insert(ptr, key, value)
+            }
+
+            fn search(
+                &self,
+                ptr: CountingBaseAddress,
+                key: &K,
+            ) -> limousine_engine::Result<Option<V>> {
+                self.inner.search(ptr, key)
+            }
+
+            fn load(
+                store: &mut GlobalStore,
+                ident: impl ToString,
+            ) -> limousine_engine::Result<Self> {
+                Ok(Self {
+                    inner: BoundaryDiskBaseComponent::load(store, ident)?,
+                    inserts: Cell::new(0),
+                })
+            }
+
+            fn build(
+                store: &mut GlobalStore,
+                ident: impl ToString,
+                iter: impl Iterator<Item = (K, V)>,
+            ) -> limousine_engine::Result<Self> {
+                Ok(Self {
+                    inner: BoundaryDiskBaseComponent::build(store, ident, iter)?,
+                    inserts: Cell::new(0),
+                })
+            }
+
+            fn entries(&self) -> impl Iterator<Item = limousine_engine::Result<(K, V)>> + '_ {
+                self.inner.entries()
+            }
+        }
+    }
+
+    #[test]
+    fn test_persisted_kv_store_custom() -> limousine_engine::Result<()> {
+        create_kv_store! {
+            name: CustomStore,
+            layout: [
+                btree_top(),
+                btree(fanout = 8),
+                custom(wrapped::CountingBaseComponent, fanout = 16, persist),
+            ]
+        }
+
+        test_persisted_kv_store::<CustomStore<K, V>>()?;
+
+        let temp_dir = tempdir()?;
+        let mut kv_store = CustomStore::<K, V>::open_or_create(temp_dir.path())?;
+        for key in 0..1_000 {
+            kv_store.insert(key, key)?;
+        }
+
+        let layers = kv_store.describe()?;
+        assert_eq!(layers[2].kind, "custom");
+        assert_eq!(
+            layers[2].parameters,
+            [("path", "wrapped::CountingBaseComponent"), ("fanout", "16")]
+        );
+        assert_eq!(layers[2].size.keys, 1_000);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_persisted_kv_store_read_only() -> limousine_engine::Result<()> {
+        create_kv_store! {
+            name: ReadOnlyStore,
+            layout: [
+                btree_top(),
+                btree(fanout = 8),
+                btree(fanout = 8, persist, mmap),
+                btree(fanout = 32, persist),
+            ]
+        }
+
+        let temp_dir = tempdir()?;
+        let temp_path = temp_dir.path();
+        let num = 5_000;
+
+        let options = OpenOptions::new().backend(Backend::File);
+        assert!(ReadOnlyStore::<K, V>::open_with(temp_path, options.read_only(true)).is_err());
+
+        let mut kv_store = ReadOnlyStore::<K, V>::open_with(temp_path, options.create(true))?;
+        for key in 0..num {
+            kv_store.insert(key, key)?;
+        }
+
+        // Readers are locked out while the store is open for writing
+        let reader = ReadOnlyStore::<K, V>::open_with(temp_path, options.read_only(true));
+        assert!(matches!(reader, Err(Error::Locked(_))));
+        kv_store.close()?;
+
+        let mut first = ReadOnlyStore::<K, V>::open_with(temp_path, options.read_only(true))?;
+        let second = ReadOnlyStore::<K, V>::open_with(temp_path, options.read_only(true))?;
+        for key in 0..num {
+            assert_eq!(first.search(key)?, Some(key));
+            assert_eq!(second.search(key)?, Some(key));
+        }
+
+        assert!(matches!(first.insert(num, num), Err(Error::ReadOnly)));
+        assert_eq!(first.search(num)?, None);
+
+        let writer = ReadOnlyStore::<K, V>::open_with(temp_path, options);
+        assert!(matches!(writer, Err(Error::Locked(_))));
+
+        first.close()?;
+        drop(second);
+
+        let mut kv_store = ReadOnlyStore::<K, V>::open_with(temp_path, options)?;
+        kv_store.insert(num, num)?;
+        kv_store.close()?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_persisted_kv_store_read_only_default_backend() -> limousine_engine::Result<()> {
+        create_kv_store! {
+            name: ReadOnlyStore,
+            layout: [
+                btree_top(),
+                btree(fanout = 8),
+                btree(fanout = 32, persist),
+            ]
+        }
+
+        let temp_dir = tempdir()?;
+        let temp_path = temp_dir.path();
+        let num = 5_000;
+
+        let mut kv_store = ReadOnlyStore::<K, V>::open_or_create(temp_path)?;
+        for key in 0..num {
+            kv_store.insert(key, key)?;
+        }
+
+        let reader = ReadOnlyStore::<K, V>::open_read_only(temp_path);
+        assert!(matches!(reader, Err(Error::Locked(_))));
+        kv_store.close()?;
+
+        // Marble serves a single reader at a time, which never writes to the store
+        let mut reader = ReadOnlyStore::<K, V>::open_read_only(temp_path)?;
+        for key in 0..num {
+            assert_eq!(reader.search(key)?, Some(key));
+        }
+        assert!(matches!(reader.insert(num, num), Err(Error::ReadOnly)));
+
+        let second = ReadOnlyStore::<K, V>::open_read_only(temp_path);
+        assert!(matches!(second, Err(Error::Locked(_))));
+        let writer = ReadOnlyStore::<K, V>::open(temp_path);
+        assert!(matches!(writer, Err(Error::Locked(_))));
+        reader.close()?;
+
+        let mut kv_store = ReadOnlyStore::<K, V>::open(temp_path)?;
+        assert_eq!(kv_store.search(num)?, None);
+        kv_store.insert(num, num)?;
+        kv_store.close()?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_persisted_kv_store_backup() -> limousine_engine::Result<()> {
+        create_kv_store! {
+            name: BackupStore,
+            layout: [
+                btree_top(),
+                btree(fanout = 8),
+                btree(fanout = 8, persist, mmap),
+                btree(fanout = 32, persist),
+            ]
+        }
+
+        let temp_dir = tempdir()?;
+        let path = temp_dir.path().join("store");
+        let backup = temp_dir.path().join("backup");
+        let num = 5_000;
+
+        let mut kv_store = BackupStore::<K, V>::open_or_create(&path)?;
+        for key in 0..num {
+            kv_store.insert(key, key)?;
+        }
+        kv_store.backup_to(&backup)?;
+
+        // Changes after the backup are not part of it
+        for key in 0..2 * num {
+            kv_store.insert(key, key + 1)?;
+        }
+        kv_store.close()?;
+
+        let kv_store = BackupStore::<K, V>::open(&backup)?;
+        for key in 0..num {
+            assert_eq!(kv_store.search(key)?, Some(key));
+        }
+        assert_eq!(kv_store.search(num)?, None);
+        kv_store.close()?;
+
+        // Node maps and checkpoints of the replaced pages must not be reused
+        let kv_store = BackupStore::<K, V>::restore_from(&backup, &path)?;
+        for key in 0..num {
+            assert_eq!(kv_store.search(key)?, Some(key));
+        }
+        assert_eq!(kv_store.search(num)?, None);
+        kv_store.close()?;
+
+        let kv_store = BackupStore::<K, V>::open(&path)?;
+        for key in 0..num {
+            assert_eq!(kv_store.search(key)?, Some(key));
+        }
+        kv_store.close()?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_kv_store_archive() -> limousine_engine::Result<()> {
+        create_kv_store! {
+            name: BTreeStore,
+            layout: [
+                btree_top(),
+                btree(fanout = 8),
+                btree(fanout = 64),
+            ]
+        }
+
+        create_kv_store! {
+            name: PGMStore,
+            layout: [
+                btree_top(),
+                pgm(epsilon = 8),
+                pgm(epsilon = 8),
+            ]
+        }
+
+        create_kv_store! {
+            name: PersistedStore,
+            layout: [
+                btree_top(),
+                btree(fanout = 8, persist),
+                btree(fanout = 32, persist),
+            ]
+        }
+
+        let num = 10_000;
+        let entries: Vec<(K, V)> = (0..num).map(|key| (key * 3, key * key)).collect();
+
+        // In-memory to in-memory with a different layout
+        let kv_store = BTreeStore::<K, V>::build(entries.iter().copied());
+        let mut archive = Vec::new();
+        assert_eq!(kv_store.export_to(&mut archive)?, num as u64);
+
+        let kv_store = PGMStore::<K, V>::import_from(&archive[..])?;
+        assert_eq!(kv_store.entries().collect::<Vec<_>>(), entries);
+
+        // In-memory to persisted and back
+        let temp_dir = tempdir()?;
+        let kv_store = PersistedStore::<K, V>::import_from(temp_dir.path(), &archive[..])?;
+        for &(key, value) in entries.iter() {
+            assert_eq!(kv_store.search(key)?, Some(value));
+        }
+
+        let mut exported = Vec::new();
+        assert_eq!(kv_store.export_to(&mut exported)?, num as u64);
+        assert_eq!(exported, archive);
+        kv_store.close()?;
+
+        let kv_store = BTreeStore::<K, V>::import_from(&exported[..])?;
+        assert_eq!(kv_store.entries().collect::<Vec<_>>(), entries);
+
+        // Archives of key or value types of another size are rejected
+        let mut other = Vec::new();
+        BTreeStore::<u64, u64>::build((0..num as u64).map(|key| (key, key)))
+            .export_to(&mut other)?;
+        assert!(matches!(
+            BTreeStore::<K, V>::import_from(&other[..]),
+            Err(Error::Archive(_))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_kv_store_1() {
+        create_kv_store! {
+            name: KVStore1,
+            layout: [
+                btree_top(),
+                btree(fanout = 64),
+            ]
+        }
+
+        test_kv_store::<KVStore1<K, V>>();
+    }
+
+    #[test]
+    fn test_kv_store_2() {
+        create_kv_store! {
+            name: KVStore1,
+            layout: [
+                btree_top(),
+                btree(fanout = 32),
+            ]
+        }
+
+        test_kv_store::<KVStore1<K, V>>();
+    }
+
+    #[test]
+    fn test_kv_store_3() {
+        create_kv_store! {
+            name: KVStore1,
+            layout: [
+                btree_top(),
+                btree(fanout = 32),
+                btree(fanout = 32),
+            ]
+        }
+
+        test_kv_store::<KVStore1<K, V>>();
+    }
+
+    #[test]
+    fn test_kv_store_4() {
+        create_kv_store! {
+            name: KVStore1,
+            layout: [
+                btree_top(),
+                btree(fanout = 8),
+                btree(fanout = 8),
+                btree(fanout = 32),
+            ]
+        }
+
+        test_kv_store::<KVStore1<K, V>>();
+    }
+
+    #[test]
+    fn test_kv_store_5() {
+        create_kv_store! {
+            name: KVStore1,
+            layout: [
+                btree_top(),
+                btree(fanout = 8),
+                btree(fanout = 8),
+                btree(fanout = 8),
+                btree(fanout = 32),
+            ]
+        }
+
+        test_kv_store::<KVStore1<K, V>>();
+    }
+
+    #[test]
+    fn test_kv_store_6() {
+        create_kv_store! {
+            name: KVStore1,
+            layout: [
+                btree_top(),
+                btree(fanout = 8),
+                btree(fanout = 8),
+                btree(fanout = 8),
+                btree(fanout = 8),
+                btree(fanout = 8),
+                btree(fanout = 32),
+            ]
+        }
+
+        test_kv_store::<KVStore1<K, V>>();
+    }
+
+    #[test]
+    fn test_kv_store_7() {
+        create_kv_store! {
+            name: KVStore1,
+            layout: [
+                btree_top(),
+                btree(fanout = 8),
+                btree(fanout = 8),
+                btree(fanout = 8),
+                btree(fanout = 8),
+                btree(fanout = 8),
+                btree(fanout = 8),
+            ]
+        }
+
+        test_kv_store::<KVStore1<K, V>>();
+    }
+
+    #[test]
+    fn test_kv_store_report() {
+        create_kv_store! {
+            name: ReportedStore,
+            layout: [
+                btree_top(),
+                btree(fanout = 8),
+                btree(fanout = 64, persist),
+            ],
+            report: true,
+            strict: true
+        }
+
+        let report = ReportedStore::<u64, u64>::LAYOUT_REPORT;
+        let components: Vec<_> = report.layers.iter().map(|layer| layer.component).collect();
+        assert_eq!(
+            components,
+            [
+                "btree_top()",
+                "btree(fanout = 8)",
+                "btree(fanout = 64, persist)"
+            ]
+        );
+        assert_eq!(report.depth, 3);
+
+        // Persisted nodes only hold their entries, a million keys and values of 16 bytes
+        let base = report.layers[2];
+        assert_eq!((base.fanout, base.persisted), (Some(64), true));
+        assert_eq!(base.bytes, Some(16_000_000));
+        assert_eq!(report.disk_per_million_keys(), Some(16_000_000));
+
+        // Estimates grow with the key and value types
+        let wide = ReportedStore::<K, V>::LAYOUT_REPORT;
+        assert!(wide.memory_per_million_keys() > report.memory_per_million_keys());
+        assert_eq!(wide.disk_per_million_keys(), Some(32_000_000));
+
+        create_kv_store! {
+            name: CustomReportedStore,
+            layout: [
+                btree_top(),
+                btree(fanout = 8),
+                custom(wrapped::CountingBaseComponent, fanout = 16, persist),
+            ],
+            report: true
+        }
+
+        // Custom components cannot be estimated
+        let report = CustomReportedStore::<K, V>::LAYOUT_REPORT;
+        assert_eq!(report.layers[2].nodes, None);
+        assert_eq!(report.disk_per_million_keys(), None);
+        assert!(report.to_string().contains("unknown"));
+    }
+
+    #[test]
+    fn test_kv_store_search() {
+        create_kv_store! {
+            name: SearchStore,
+            layout: [
+                btree_top(),
+                btree(fanout = 8, search = binary),
+                btree(fanout = 16, search = interpolation),
+                btree(fanout = 32, search = linear),
+                btree(fanout = 256, search = simd),
+            ]
+        }
+
+        test_kv_store::<SearchStore<K, V>>();
+        test_kv_store_build::<SearchStore<K, V>>();
+    }
+
+    #[test]
+    fn test_kv_store_split() -> limousine_engine::Result<()> {
+        create_kv_store! {
+            name: AppendStore,
+            layout: [
+                btree_top(),
+                btree(fanout = 8, split = rightmost),
+                btree(fanout = 32, split = rightmost)
+            ]
+        }
+
+        create_kv_store! {
+            name: MiddleStore,
+            layout: [btree_top(), btree(fanout = 8), btree(fanout = 32, split = middle)]
+        }
+
+        test_kv_store::<AppendStore<K, V>>();
+        test_kv_store_build::<AppendStore<K, V>>();
+
+        let mut append = AppendStore::<K, V>::empty();
+        let mut middle = MiddleStore::<K, V>::empty();
+        for key in 0..32_000 {
+            append.insert(key, key);
+            middle.insert(key, key);
+        }
+
+        for key in 0..32_000 {
+            assert_eq!(append.search(key), Some(key));
+        }
+
+        let append_layers = append.describe()?;
+        let middle_layers = middle.describe()?;
+
+        assert_eq!(
+            append_layers[2].parameters,
+            [("fanout", "32"), ("split", "rightmost")]
+        );
+        assert_eq!(middle_layers[2].parameters, [("fanout", "32")]);
+
+        assert_eq!(append_layers[2].size.nodes, 1_000);
+        assert_eq!(append_layers[1].size.nodes, 125);
+        assert_eq!(middle_layers[2].size.nodes, 1_999);
+
+        // Inserts below the last node still split in the middle
+        let mut append = AppendStore::<K, V>::empty();
+        for key in (0..32_000).rev() {
+            append.insert(key, key);
+        }
+        assert_eq!(append.describe()?[2].size.nodes, 1_999);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_kv_store_fill() -> limousine_engine::Result<()> {
+        create_kv_store! {
+            name: FullStore,
+            layout: [btree_top(), btree(fanout = 8, fill = 0.25), btree(fanout = 32, fill = 1.0)]
+        }
+
+        create_kv_store! {
+            name: HalfStore,
+            layout: [btree_top(), btree(fanout = 8), btree(fanout = 32)]
+        }
+
+        test_kv_store::<FullStore<K, V>>();
+        test_kv_store_build::<FullStore<K, V>>();
+
+        let full = FullStore::<K, V>::build((0..32_000).map(|key| (key, key))).describe()?;
+        let half = HalfStore::<K, V>::build((0..32_000).map(|key| (key, key))).describe()?;
+
+        assert_eq!(full[1].parameters, [("fanout", "8"), ("fill", "0.25")]);
+        assert_eq!(full[2].parameters, [("fanout", "32"), ("fill", "1")]);
+
+        assert_eq!(full[2].size.nodes, 1_000);
+        assert_eq!(half[2].size.nodes, 2_000);
+
+        // Two keys per node
+        assert_eq!(full[1].size.nodes, 500);
+        assert_eq!(half[1].size.nodes, 500);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_kv_store_describe() -> limousine_engine::Result<()> {
+        use limousine_engine::PersistType;
+
+        create_kv_store! {
+            name: DescribedStore,
+            layout: [
+                btree_top(),
+                pgm(epsilon = 8),
+                btree(fanout = 8, persist),
+                btree(fanout = 32, persist, mmap),
+            ]
+        }
+
+        let temp_dir = tempdir()?;
+        let kv_store =
+            DescribedStore::<u64, u64>::build(temp_dir.path(), (0..10_000).map(|key| (key, key)))?;
+        let layers = kv_store.describe()?;
+
+        let kinds: Vec<_> = layers.iter().map(|layer| layer.kind).collect();
+        assert_eq!(kinds, ["btree_top", "pgm", "btree", "btree"]);
+
+        let persist: Vec<_> = layers.iter().map(|layer| layer.persist).collect();
+        assert_eq!(
+            persist,
+            [
+                PersistType::InMemory,
+                PersistType::InMemory,
+                PersistType::BoundaryDisk,
+                PersistType::DeepDisk
+            ]
+        );
+
+        assert_eq!(layers[1].parameters, [("epsilon", "8")]);
+        assert_eq!(layers[3].parameters, [("fanout", "32"), ("mmap", "true")]);
+
+        // Every layer holds a key for every node of the layer below
+        assert_eq!(layers[3].size.keys, 10_000);
+        for (layer, below) in layers.iter().zip(layers.iter().skip(1)) {
+            assert_eq!(layer.size.keys, below.size.nodes);
+        }
+
+        assert_eq!(layers[1].size.disk_bytes, 0);
+        assert!(layers[1].size.memory_bytes > 0);
+        assert!(layers[2].size.disk_bytes > 0 && layers[3].size.disk_bytes > 0);
+        assert_eq!(layers[3].size.memory_bytes, 0);
+
+        Ok(())
+    }
+
+    /// Stores declared with the `store` attribute, in a module of their own so that their
+    /// visibility is checked
+    mod declared {
+        use super::{wrapped, K, V};
+        use limousine_engine::prelude::*;
+
+        /// A persisted store generic over its keys and values
+        #[store(layout = [btree_top(), btree(fanout = 8), btree(fanout = 32, persist)])]
+        pub(super) struct GenericStore<K, V>;
+
+        #[store(
+            layout = [btree_top(), btree(fanout = 8), btree(fanout = 32)],
+            key = K,
+            value = V,
+            strict = true
+        )]
+        pub struct FixedStore;
+
+        #[store(path = "layouts/ingest.toml", key = u64, value = u64)]
+        pub(in crate::tests) struct IngestStore;
+
+        #[store(
+            layout = [
+                btree_top(),
+                btree(fanout = 8),
+                custom(wrapped::CountingBaseComponent, fanout = 16, persist),
+            ],
+            key = K,
+            value = V
+        )]
+        pub(crate) struct CustomStore;
+
+        // Stores which are not compiled take their hidden module along
+        #[store(layout = [btree_top(), btree(fanout = 8)], key = Missing, value = Missing)]
+        #[cfg(any())]
+        pub struct DisabledStore;
+    }
+
+    #[test]
+    fn test_kv_store_fixed_types() -> limousine_engine::Result<()> {
+        // Values of in-memory stores need not be persisted
+        #[derive(Clone, Debug, PartialEq)]
+        struct Reading(f64);
+
+        create_kv_store! {
+            name: ReadingStore,
+            layout: [btree_top(), btree(fanout = 8), btree(fanout = 32)],
+            key: u64,
+            value: Reading
+        }
+
+        let mut kv_store = ReadingStore::build((0..1_000).map(|key| (key, Reading(key as f64))));
+        assert_eq!(kv_store.insert(10, Reading(0.5)), Some(Reading(10.0)));
+        assert_eq!(kv_store.search(10), Some(Reading(0.5)));
+        assert_eq!(kv_store.describe()?[2].size.keys, 1_000);
+
+        create_kv_store! {
+            name: FixedPersistedStore,
+            layout: [
+                btree_top(),
+                btree(fanout = 8),
+                btree(fanout = 32, persist),
+                btree(fanout = 32, persist, mmap),
+            ],
+            key: u64,
+            value: [u8; 16],
+            report: true
+        }
+
+        create_kv_store! {
+            name: FixedStore,
+            layout: [btree_top(), btree(fanout = 8), btree(fanout = 32)],
+            key: K,
+            value: V
+        }
+
+        test_kv_store::<FixedStore>();
+
+        let temp_dir = tempdir()?;
+        let mut kv_store = FixedPersistedStore::open_or_create(temp_dir.path())?;
+        for key in 0..1_000 {
+            kv_store.insert(key, [key as u8; 16])?;
+        }
+        assert_eq!(kv_store.search(500)?, Some([500u16 as u8; 16]));
+        assert_eq!(FixedPersistedStore::LAYOUT_REPORT.layers.len(), 4);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_kv_store_attribute() -> limousine_engine::Result<()> {
+        use declared::{CustomStore, FixedStore, GenericStore, IngestStore};
+
+        test_persisted_kv_store::<GenericStore<K, V>>()?;
+        test_persisted_kv_store::<CustomStore>()?;
+        test_kv_store::<FixedStore>();
+        test_kv_store_build::<FixedStore>();
+
+        let temp_dir = tempdir()?;
+        let mut kv_store = IngestStore::open_or_create(temp_dir.path())?;
+        kv_store.insert(10, 50)?;
+        assert_eq!(kv_store.search(10)?, Some(50));
+        assert_eq!(kv_store.describe()?.len(), 3);
+
+        Ok(())
     }
 
     #[test]
@@ -2,6 +2,9 @@ use core::fmt;
 use core::mem::MaybeUninit;
 use std::mem::size_of;
 
+#[cfg(feature = "serde")]
+mod serde;
+
 /// A sorted array which is constructed with intentional gaps to allow for practical in-place inserts
 /// NOTE: The current implementation assumes keys are unique. It may break if this is not true.
 /// NOTE: The current implementation is not heavily optimized.
@@ -421,6 +424,18 @@ where
         (self.len() + (size_of::<K>() + size_of::<V>()) * num_unoccupied) as u128
     }
 
+    /// Iterate over the occupied entries in order, skipping the gaps
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> + '_ {
+        (0..self.len())
+            .filter(|&ix| self.bitmap[ix])
+            .map(|ix| unsafe {
+                (
+                    self.keys[ix].assume_init_ref(),
+                    self.vals[ix].assume_init_ref(),
+                )
+            })
+    }
+
     /// The minimum key in this array, or None if it's empty
     pub fn min(&self) -> Option<&K> {
         match self.next_occupied_ix(0) {
@@ -757,6 +772,18 @@ mod gapped_array_tests {
         }
     }
 
+    #[test]
+    fn iter_skips_gaps() {
+        let mut ga = GappedKVArray::<i32, i32>::new(8);
+        for (value, hint) in [(1, 0), (2, 3), (3, 6)] {
+            ga.initial_model_based_insert((value, value * 10), hint)
+                .unwrap();
+        }
+
+        let entries: Vec<(i32, i32)> = ga.iter().map(|(&key, &value)| (key, value)).collect();
+        assert_eq!(entries, vec![(1, 10), (2, 20), (3, 30)]);
+    }
+
     #[test]
     fn debug_initial_gapped() {
         let perm = vec![0, 1, 2, 3, 4, 5];
@@ -0,0 +1,76 @@
+use serde::ser::{SerializeSeq, SerializeTuple};
+use serde::{Deserialize, Serialize};
+
+use crate::GappedKVArray;
+
+/// The occupied slots of a gapped array, serialized along with their index so that the gaps are
+/// preserved
+struct Occupied<'a, K: Ord, V>(&'a GappedKVArray<K, V>);
+
+impl<'a, K, V> Serialize for Occupied<'a, K, V>
+where
+    K: Serialize + Ord,
+    V: Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let array = self.0;
+        let mut seq = serializer.serialize_seq(Some(array.size()))?;
+        for ix in (0..array.len()).filter(|&ix| array.bitmap[ix]) {
+            // SAFETY: the bitmap marks the slot as occupied
+            let (key, value) = unsafe {
+                (
+                    array.keys[ix].assume_init_ref(),
+                    array.vals[ix].assume_init_ref(),
+                )
+            };
+            seq.serialize_element(&(ix, key, value))?;
+        }
+        seq.end()
+    }
+}
+
+impl<K, V> Serialize for GappedKVArray<K, V>
+where
+    K: Serialize + Ord,
+    V: Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut tuple = serializer.serialize_tuple(2)?;
+        tuple.serialize_element(&self.len())?;
+        tuple.serialize_element(&Occupied(self))?;
+        tuple.end()
+    }
+}
+
+impl<'de, K, V> Deserialize<'de> for GappedKVArray<K, V>
+where
+    K: Deserialize<'de> + Ord,
+    V: Deserialize<'de>,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let (len, entries) = <(usize, Vec<(usize, K, V)>)>::deserialize(deserializer)?;
+
+        let mut array = GappedKVArray::new(len);
+        let mut last = None;
+        for (ix, key, value) in entries {
+            if ix >= len || last.is_some_and(|last| last >= ix) {
+                return Err(serde::de::Error::custom(
+                    "GappedKVArray slots out of order during deserialization",
+                ));
+            }
+            array.upsert_at((key, value), ix);
+            last = Some(ix);
+        }
+
+        Ok(array)
+    }
+}
@@ -50,9 +50,35 @@ impl_primitive_index!(u128);
 /// A slab-based id allocator which can deal with automatic reclamation
 #[derive(Clone)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(from = "Slab<I>"))]
 pub struct IDAllocator<I> {
     data: Vec<Option<I>>,
     next: I,
+
+    // Number of allocated ids, counted again instead of serialized
+    #[cfg_attr(feature = "serde", serde(skip))]
+    allocated: usize,
+}
+
+// Serialized form of an `IDAllocator`
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct Slab<I> {
+    data: Vec<Option<I>>,
+    next: I,
+}
+
+#[cfg(feature = "serde")]
+impl<I> From<Slab<I>> for IDAllocator<I> {
+    fn from(slab: Slab<I>) -> Self {
+        let allocated = slab.data.iter().filter(|entry| entry.is_none()).count();
+
+        Self {
+            data: slab.data,
+            next: slab.next,
+            allocated,
+        }
+    }
 }
 
 #[allow(unused)]
@@ -65,12 +91,14 @@ where
         Self {
             data: Vec::new(),
             next: I::initial(),
+            allocated: 0,
         }
     }
 
     pub fn clear(&mut self) {
         self.data.clear();
         self.next = I::initial();
+        self.allocated = 0;
     }
 
     /// Allocate the next id.
@@ -84,6 +112,7 @@ where
             self.next.increment()
         };
 
+        self.allocated += 1;
         index
     }
 
@@ -93,6 +122,7 @@ where
             if entry.is_none() {
                 *entry = Some(self.next);
                 self.next = index;
+                self.allocated -= 1;
                 return true;
             }
         }
@@ -107,6 +137,16 @@ where
 
         false
     }
+
+    /// Number of ids currently allocated.
+    pub fn allocated(&self) -> usize {
+        self.allocated
+    }
+
+    /// Number of freed ids waiting to be reused.
+    pub fn freed(&self) -> usize {
+        self.data.len() - self.allocated
+    }
 }
 
 pub struct IDAllocatorIterator<'a, I> {
@@ -219,6 +259,20 @@ mod tests {
         assert!(!allocator.is_allocated(100)); // should be false, id was never allocated
     }
 
+    #[test]
+    fn allocated_and_freed_counts() {
+        let mut allocator = IDAllocator::<u8>::new();
+        let ids = (0..5).map(|_| allocator.allocate()).collect::<Vec<_>>();
+        assert!(allocator.free(ids[1]));
+        assert!(allocator.free(ids[3]));
+        assert_eq!(allocator.allocated(), 3);
+        assert_eq!(allocator.freed(), 2);
+
+        allocator.allocate();
+        assert_eq!(allocator.allocated(), 4);
+        assert_eq!(allocator.freed(), 1);
+    }
+
     #[test]
     fn multiple_allocations() {
         let mut allocator = IDAllocator::<u32>::new();
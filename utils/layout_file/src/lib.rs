@@ -0,0 +1,663 @@
+//! Structured layout files for limousine stores.
+//!
+//! Layouts are usually written in the Rust token syntax of `create_kv_store!`, such as
+//! `[btree_top(), btree(fanout = 8), btree(fanout = 64, persist)]`. This crate reads the same
+//! layouts from TOML or JSON files, which list the components from the top layer down, each
+//! with its `type` and attributes, and optionally the `name` of the store:
+//!
+//! ```toml
+//! name = "MyStore"
+//!
+//! [[components]]
+//! type = "btree_top"
+//!
+//! [[components]]
+//! type = "btree"
+//! fanout = 64
+//! persist = true
+//! ```
+//!
+//! Files are parsed with `toml` and `serde_json`, and every component is translated back into
+//! the token syntax, so that the macro and `LayoutSpec` validate it as usual, while keeping track
+//! of where each part of it was written so that errors point into the file.
+#![deny(missing_docs)]
+
+use serde::de::{self, Deserializer, Visitor};
+use serde::Deserialize;
+use serde_json::value::RawValue;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::ops::Range;
+use std::path::Path;
+use toml::Spanned;
+
+/// A position in a layout file, with lines and columns counted from 1
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Position {
+    /// The line of the position
+    pub line: usize,
+    /// The column of the position, in characters
+    pub column: usize,
+}
+
+impl Position {
+    // The position of a byte offset into `input`
+    fn of_offset(input: &str, offset: usize) -> Self {
+        let before = &input[..offset.min(input.len())];
+        let line_start = before.rfind('\n').map_or(0, |newline| newline + 1);
+
+        Self {
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+        }
+    }
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}, column {}", self.line, self.column)
+    }
+}
+
+/// An invalid layout file, along with the position of the error if it is known
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Error {
+    /// What is wrong with the file
+    pub message: String,
+    /// Where in the file it went wrong
+    pub position: Option<Position>,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.position {
+            Some(position) => write!(f, "{} at {}", self.message, position),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl Error {
+    fn from_toml(input: &str, error: toml::de::Error) -> Self {
+        Self {
+            message: error.message().trim_end().to_string(),
+            position: error
+                .span()
+                .map(|span| Position::of_offset(input, span.start)),
+        }
+    }
+
+    fn from_json(error: serde_json::Error) -> Self {
+        // The message of a `serde_json` error ends with its position, which is kept separately
+        let message = error.to_string();
+        let suffix = format!(" at line {} column {}", error.line(), error.column());
+
+        Self {
+            message: message
+                .strip_suffix(&suffix)
+                .unwrap_or(&message)
+                .to_string(),
+            position: (error.line() > 0).then(|| Position {
+                line: error.line(),
+                column: error.column(),
+            }),
+        }
+    }
+}
+
+/// The format of a layout file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// A TOML document, with the components as an array of tables
+    Toml,
+    /// A JSON object, with the components as an array of objects
+    Json,
+}
+
+impl Format {
+    /// The format of a layout file by its extension, or `None` if it is not a structured one
+    pub fn from_path(path: impl AsRef<Path>) -> Option<Self> {
+        match path.as_ref().extension()?.to_str()? {
+            "toml" => Some(Self::Toml),
+            "json" => Some(Self::Json),
+            _ => None,
+        }
+    }
+}
+
+/// A single component of a layout file, translated into the token syntax
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Component {
+    source: String,
+    position: Position,
+
+    // Offsets into `source` at which each translated part starts, along with where it was
+    // written in the file
+    parts: Vec<(usize, Position)>,
+
+    // Keys of the attributes along with the range of `source` holding each one and where its
+    // key was written
+    attributes: Vec<(String, Range<usize>, Position)>,
+}
+
+impl Component {
+    /// The component in the token syntax, such as `btree(fanout = 64, persist)`
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Where the component starts in the file
+    pub fn position(&self) -> Position {
+        self.position
+    }
+
+    /// Where the part of the file which was translated to `offset` in `source` was written
+    pub fn locate(&self, offset: usize) -> Position {
+        self.parts
+            .iter()
+            .take_while(|(start, _)| *start <= offset)
+            .last()
+            .map_or(self.position, |(_, position)| *position)
+    }
+
+    /// Where the attribute responsible for an error was written, for checks whose errors cannot
+    /// point into `source`. `check` returns the error of a component in the token syntax, if
+    /// any, which is blamed on the first attribute that brings it about, or on the type of the
+    /// component if it fails without any attributes.
+    pub fn blame<E: PartialEq>(&self, check: impl Fn(&str) -> Option<E>) -> Position {
+        let Some(error) = check(&self.source) else {
+            return self.position;
+        };
+
+        let head = self
+            .source
+            .find('(')
+            .map_or(self.source.len(), |open| open + 1);
+        let blamed = (0..self.attributes.len()).find(|&count| {
+            let end = match count {
+                0 => head,
+                count => self.attributes[count - 1].1.end,
+            };
+
+            check(&format!("{})", &self.source[..end])).as_ref() == Some(&error)
+        });
+
+        match blamed {
+            Some(0) => self.locate(0),
+            Some(count) => self.attributes[count - 1].2,
+            None => self
+                .attributes
+                .last()
+                .map_or(self.locate(0), |(_, _, key)| *key),
+        }
+    }
+
+    /// Where the attribute `key` of the component was written, if it is given
+    pub fn key(&self, key: &str) -> Option<Position> {
+        self.attributes
+            .iter()
+            .find(|(name, _, _)| name == key)
+            .map(|(_, _, position)| *position)
+    }
+
+    fn push(&mut self, part: &str, position: Position) {
+        self.parts.push((self.source.len(), position));
+        self.source.push_str(part);
+    }
+}
+
+/// A parsed layout file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LayoutFile {
+    /// The name of the store, if the file gives one, along with where it was written
+    pub name: Option<(String, Position)>,
+    /// Components from the top layer down to the base layer
+    pub components: Vec<Component>,
+}
+
+impl LayoutFile {
+    /// Parse a layout file in the given format
+    ///
+    /// ```
+    /// use layout_file::{Format, LayoutFile};
+    ///
+    /// let file = LayoutFile::parse(
+    ///     r#"{ "components": [{ "type": "btree_top" }, { "type": "btree", "fanout": 8 }] }"#,
+    ///     Format::Json,
+    /// )
+    /// .unwrap();
+    ///
+    /// assert_eq!(file.components[1].source(), "btree(fanout = 8)");
+    /// ```
+    pub fn parse(input: &str, format: Format) -> Result<Self, Error> {
+        match format {
+            Format::Toml => Self::parse_toml(input),
+            Format::Json => Self::parse_json(input),
+        }
+    }
+
+    fn parse_toml(input: &str) -> Result<Self, Error> {
+        let file: TomlFile =
+            toml::from_str(input).map_err(|error| Error::from_toml(input, error))?;
+
+        let components = file
+            .components
+            .into_iter()
+            .map(|table| {
+                let offset = table.span().start;
+                let entries = table
+                    .into_inner()
+                    .into_iter()
+                    .map(|(key, value)| Entry {
+                        key_offset: key.span().start,
+                        key: key.into_inner(),
+                        value_offset: value.span().start,
+                        value: value.into_inner(),
+                    })
+                    .collect();
+
+                component(input, offset, entries)
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self {
+            name: file.name.map(|name| {
+                let position = Position::of_offset(input, name.span().start);
+                (name.into_inner(), position)
+            }),
+            components,
+        })
+    }
+
+    fn parse_json(input: &str) -> Result<Self, Error> {
+        let file: JsonFile = serde_json::from_str(input).map_err(Error::from_json)?;
+
+        // Raw values borrow from `input`, which gives their offsets
+        let offset = |raw: &RawValue| raw.get().as_ptr() as usize - input.as_ptr() as usize;
+        let position = |raw: &RawValue| Position::of_offset(input, offset(raw));
+        let error = |error: serde_json::Error, raw: &RawValue| Error {
+            position: Some(position(raw)),
+            ..Error::from_json(error)
+        };
+
+        let name = match file.name {
+            Some(raw) => match serde_json::from_str(raw.get()) {
+                Ok(name) => Some((name, position(raw))),
+                Err(parse_error) => return Err(error(parse_error, raw)),
+            },
+            None => None,
+        };
+
+        let components = file
+            .components
+            .into_iter()
+            .map(|raw| {
+                let start = offset(raw);
+                let table: BTreeMap<String, &RawValue> = serde_json::from_str(raw.get())
+                    .map_err(|parse_error| error(parse_error, raw))?;
+
+                let entries = table
+                    .into_iter()
+                    .map(|(key, value)| {
+                        let value_offset = offset(value);
+                        let key_offset = input[start..value_offset]
+                            .rfind(&format!("\"{key}\""))
+                            .map_or(value_offset, |found| start + found);
+
+                        Ok(Entry {
+                            key,
+                            key_offset,
+                            value: serde_json::from_str(value.get())
+                                .map_err(|parse_error| error(parse_error, value))?,
+                            value_offset,
+                        })
+                    })
+                    .collect::<Result<_, Error>>()?;
+
+                component(input, start, entries)
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self { name, components })
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TomlFile {
+    name: Option<Spanned<String>>,
+    components: Vec<Spanned<BTreeMap<Spanned<String>, Spanned<Attribute>>>>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct JsonFile<'a> {
+    #[serde(borrow)]
+    name: Option<&'a RawValue>,
+    #[serde(borrow)]
+    components: Vec<&'a RawValue>,
+}
+
+// An attribute of a component along with the byte offsets of its key and value in the file
+struct Entry {
+    key: String,
+    key_offset: usize,
+    value: Attribute,
+    value_offset: usize,
+}
+
+// Translate the attributes of a component starting at `offset` into the token syntax, with
+// flags for `true` and nothing for `false`
+fn component(input: &str, offset: usize, entries: Vec<Entry>) -> Result<Component, Error> {
+    let position = |offset: usize| Position::of_offset(input, offset);
+
+    let string = |key: &str| -> Result<Option<(String, Position)>, Error> {
+        match entries.iter().find(|entry| entry.key == key) {
+            None => Ok(None),
+            Some(Entry {
+                value: Attribute::String(string),
+                value_offset,
+                ..
+            }) => Ok(Some((string.clone(), position(*value_offset)))),
+            Some(entry) => Err(Error {
+                message: format!("Expected a string for `{key}`"),
+                position: Some(position(entry.value_offset)),
+            }),
+        }
+    };
+
+    let Some((kind, kind_position)) = string("type")? else {
+        return Err(Error {
+            message: "Component has no `type`".to_string(),
+            position: Some(position(offset)),
+        });
+    };
+
+    // Custom components start with the path to their type
+    let mut attributes = Vec::new();
+    if let Some((path, path_position)) = string("path")? {
+        attributes.push(("path", vec![(path, path_position)], path_position));
+    }
+
+    for entry in &entries {
+        if entry.key == "type" || entry.key == "path" {
+            continue;
+        }
+
+        let key_position = position(entry.key_offset);
+        match &entry.value {
+            Attribute::Flag(true) => attributes.push((
+                &entry.key,
+                vec![(entry.key.clone(), key_position)],
+                key_position,
+            )),
+            Attribute::Flag(false) => (),
+            Attribute::Number(value) | Attribute::String(value) => attributes.push((
+                &entry.key,
+                vec![
+                    (format!("{} = ", entry.key), key_position),
+                    (value.clone(), position(entry.value_offset)),
+                ],
+                key_position,
+            )),
+        }
+    }
+
+    let mut component = Component {
+        source: String::new(),
+        position: position(offset),
+        parts: Vec::new(),
+        attributes: Vec::new(),
+    };
+
+    component.push(&kind, kind_position);
+    component.source.push('(');
+
+    for (index, (key, parts, key_position)) in attributes.into_iter().enumerate() {
+        if index > 0 {
+            component.source.push_str(", ");
+        }
+
+        let start = component.source.len();
+        for (part, position) in parts {
+            component.push(&part, position);
+        }
+
+        let end = component.source.len();
+        component
+            .attributes
+            .push((key.to_string(), start..end, key_position));
+    }
+
+    component.source.push(')');
+    Ok(component)
+}
+
+// The value of an attribute, with numbers kept as they are written in the token syntax
+enum Attribute {
+    Flag(bool),
+    Number(String),
+    String(String),
+}
+
+impl<'de> Deserialize<'de> for Attribute {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct AttributeVisitor;
+
+        impl Visitor<'_> for AttributeVisitor {
+            type Value = Attribute;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "a number, a string or a boolean")
+            }
+
+            fn visit_bool<E: de::Error>(self, value: bool) -> Result<Attribute, E> {
+                Ok(Attribute::Flag(value))
+            }
+
+            fn visit_i64<E: de::Error>(self, value: i64) -> Result<Attribute, E> {
+                Ok(Attribute::Number(value.to_string()))
+            }
+
+            fn visit_u64<E: de::Error>(self, value: u64) -> Result<Attribute, E> {
+                Ok(Attribute::Number(value.to_string()))
+            }
+
+            fn visit_f64<E: de::Error>(self, value: f64) -> Result<Attribute, E> {
+                Ok(Attribute::Number(value.to_string()))
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<Attribute, E> {
+                Ok(Attribute::String(value.to_string()))
+            }
+        }
+
+        deserializer.deserialize_any(AttributeVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOML: &str = r#"
+# The store used by the ingest service
+name = "IngestStore"
+
+[[components]]
+type = "btree_top"
+
+[[components]]
+type = 'pgm'
+epsilon = 1_6
+
+[[components]]
+type = "btree"
+persist = true
+fanout = 64
+mmap = false
+"#;
+
+    const JSON: &str = r#"{
+    "name": "IngestStore",
+    "components": [
+        { "type": "btree_top" },
+        { "type": "pgm", "epsilon": 16 },
+        { "type": "btree", "fanout": 64, "persist": true, "mmap": false }
+    ]
+}"#;
+
+    fn sources(file: &LayoutFile) -> Vec<&str> {
+        file.components.iter().map(Component::source).collect()
+    }
+
+    fn error(input: &str, format: Format) -> String {
+        LayoutFile::parse(input, format).unwrap_err().to_string()
+    }
+
+    fn position(line: usize, column: usize) -> Position {
+        Position { line, column }
+    }
+
+    #[test]
+    fn parse_formats() {
+        let toml = LayoutFile::parse(TOML, Format::Toml).unwrap();
+        let json = LayoutFile::parse(JSON, Format::Json).unwrap();
+
+        let expected = [
+            "btree_top()",
+            "pgm(epsilon = 16)",
+            "btree(fanout = 64, persist)",
+        ];
+        assert_eq!(sources(&toml), expected);
+        assert_eq!(sources(&json), expected);
+
+        assert_eq!(toml.name, Some(("IngestStore".to_string(), position(3, 8))));
+        assert_eq!(
+            json.name,
+            Some(("IngestStore".to_string(), position(2, 13)))
+        );
+
+        // Inline tables describe the same layout
+        let inline = LayoutFile::parse(
+            "components = [\n  { type = \"btree_top\" }, # top\n  { type = \"btree\", fanout = 8 },\n]",
+            Format::Toml,
+        )
+        .unwrap();
+        assert_eq!(sources(&inline), ["btree_top()", "btree(fanout = 8)"]);
+        assert_eq!(inline.components[1].position(), position(3, 3));
+        assert_eq!(inline.name, None);
+    }
+
+    #[test]
+    fn custom_components() {
+        let file = LayoutFile::parse(
+            r#"{ "components": [{ "type": "custom", "path": "my::Component", "fanout": 16, "persist": true }] }"#,
+            Format::Json,
+        )
+        .unwrap();
+
+        assert_eq!(
+            sources(&file),
+            ["custom(my::Component, fanout = 16, persist)"]
+        );
+    }
+
+    #[test]
+    fn locate_parts() {
+        let file = LayoutFile::parse(TOML, Format::Toml).unwrap();
+        let btree = &file.components[2];
+
+        assert_eq!(btree.position(), position(12, 1));
+        assert_eq!(btree.locate(0), position(13, 8));
+
+        let fanout = btree.source().find("fanout").unwrap();
+        assert_eq!(btree.locate(fanout), position(15, 1));
+        assert_eq!(btree.locate(fanout + 9), position(15, 10));
+
+        let persist = btree.source().find("persist").unwrap();
+        assert_eq!(btree.locate(persist), position(14, 1));
+
+        // Keys and values of JSON objects are located as well
+        let file = LayoutFile::parse(JSON, Format::Json).unwrap();
+        let btree = &file.components[2];
+
+        assert_eq!(btree.position(), position(6, 9));
+        assert_eq!(btree.locate(0), position(6, 19));
+
+        let fanout = btree.source().find("fanout").unwrap();
+        assert_eq!(btree.locate(fanout), position(6, 28));
+        assert_eq!(btree.locate(fanout + 9), position(6, 38));
+    }
+
+    #[test]
+    fn blame_attributes() {
+        let file = LayoutFile::parse(TOML, Format::Toml).unwrap();
+        let btree = &file.components[2];
+
+        // Errors are blamed on the key of the first attribute causing them
+        let check = |source: &str| source.contains("fanout").then_some("fanout");
+        assert_eq!(btree.blame(check), position(15, 1));
+
+        let check = |source: &str| source.contains("persist").then_some("persisted");
+        assert_eq!(btree.blame(check), position(14, 1));
+
+        // Or on the type of the component if it fails without any attributes
+        assert_eq!(btree.blame(|_| Some("always")), position(13, 8));
+        assert_eq!(btree.blame(|_| None::<()>), btree.position());
+
+        assert_eq!(btree.key("persist"), Some(position(14, 1)));
+        assert_eq!(btree.key("mmap"), None);
+    }
+
+    #[test]
+    fn syntax_errors() {
+        assert!(
+            error("[[components]]\ntype = \"btree\"\nfanout 8", Format::Toml)
+                .ends_with("at line 3, column 8")
+        );
+        assert!(error("name = \"a\"\nname = \"b\"", Format::Toml).ends_with("at line 2, column 1"));
+        assert!(error(
+            "{\n  \"components\": [\n    { \"type\": btree }\n  ]\n}",
+            Format::Json
+        )
+        .ends_with("at line 3, column 15"));
+        assert!(error("{ \"components\": [] ", Format::Json).contains("EOF"));
+    }
+
+    #[test]
+    fn layout_errors() {
+        assert!(error("name = \"a\"", Format::Toml).contains("missing field `components`"));
+        assert_eq!(
+            error("[[components]]\nfanout = 8", Format::Toml),
+            "Component has no `type` at line 1, column 1"
+        );
+        assert_eq!(
+            error("[[components]]\ntype = 8", Format::Toml),
+            "Expected a string for `type` at line 2, column 8"
+        );
+        assert!(error(
+            "[[components]]\ntype = \"btree\"\nfanout = [8]",
+            Format::Toml
+        )
+        .ends_with("expected a number, a string or a boolean at line 3, column 10"));
+        assert!(error(
+            "{ \"components\": [{ \"type\": \"btree\", \"fanout\": [8] }] }",
+            Format::Json
+        )
+        .ends_with("expected a number, a string or a boolean at line 1, column 47"));
+        assert_eq!(
+            error("{ \"name\": \"a\", \"layers\": [] }", Format::Json),
+            "unknown field `layers`, expected `name` or `components` at line 1, column 23"
+        );
+    }
+
+    #[test]
+    fn formats_by_extension() {
+        assert_eq!(Format::from_path("layouts/ingest.toml"), Some(Format::Toml));
+        assert_eq!(Format::from_path("ingest.json"), Some(Format::Json));
+        assert_eq!(Format::from_path(".layout"), None);
+    }
+}
@@ -3,8 +3,9 @@
 //! A collection of algorithms for searching within slices.
 //!
 //! This module provides different search strategies and utilities to work with sorted slices.
-//! Currently, it supports binary and linear search algorithms, as well as an optimal search
-//! algorithm which picks between binary and linear searches depending on the size of the slice.
+//! Currently, it supports binary, linear, interpolation and chunked linear search algorithms, as
+//! well as an optimal search algorithm which picks between binary and linear searches depending
+//! on the size of the slice.
 #![deny(missing_docs)]
 
 /// Returns the index of the smallest element greater than or equal to the search
@@ -71,10 +72,11 @@ pub fn lower_bound_always(search: Result<usize, usize>) -> usize {
 }
 
 use core::borrow::Borrow;
+use core::cmp::Ordering;
 
-/// An algorithm for searching a sorted slice, e.g. Binary or Linear
-pub trait Search {
-    /// Search a slice of `T` by comparing with a given value of `T`
+/// An algorithm for searching a sorted slice of keys `K`, e.g. Binary or Linear
+pub trait Search<K: Ord> {
+    /// Search a slice of `K` by comparing with a given value of `K`
     ///
     /// If the value is found then `Result::Ok` is returned, containing the index of
     /// the matching element. If there are multiple matches, then any one of the matches
@@ -96,7 +98,7 @@ pub trait Search {
     /// let result = BinarySearch::search(&slice, &6);
     /// assert_eq!(result, Err(4));
     /// ```
-    fn search<T: Ord>(slice: &[T], x: &T) -> Result<usize, usize> {
+    fn search(slice: &[K], x: &K) -> Result<usize, usize> {
         Self::search_by_key(slice, x)
     }
 
@@ -134,15 +136,15 @@ pub trait Search {
     /// let result = BinarySearch::search_by_key(&slice, &3);
     /// assert_eq!(result, Ok(1));
     /// ```
-    fn search_by_key<K: Ord, T: Borrow<K>>(slice: &[T], x: &K) -> Result<usize, usize>;
+    fn search_by_key<T: Borrow<K>>(slice: &[T], x: &K) -> Result<usize, usize>;
 }
 
 /// Performs a binary search on a slice, with computational complexity `O(log n)`
 /// However, for small searches, a linear search may be faster.
 pub struct BinarySearch;
 
-impl Search for BinarySearch {
-    fn search_by_key<K: Ord, T: Borrow<K>>(slice: &[T], x: &K) -> Result<usize, usize> {
+impl<K: Ord> Search<K> for BinarySearch {
+    fn search_by_key<T: Borrow<K>>(slice: &[T], x: &K) -> Result<usize, usize> {
         slice.binary_search_by(|y| y.borrow().cmp(x))
     }
 }
@@ -150,8 +152,8 @@ impl Search for BinarySearch {
 /// Performs a simple linear search on a slice, with computational complexity `O(n)`
 pub struct LinearSearch;
 
-impl Search for LinearSearch {
-    fn search_by_key<K: Ord, T: Borrow<K>>(slice: &[T], x: &K) -> Result<usize, usize> {
+impl<K: Ord> Search<K> for LinearSearch {
+    fn search_by_key<T: Borrow<K>>(slice: &[T], x: &K) -> Result<usize, usize> {
         let mut index = 0;
         let size = slice.len();
 
@@ -174,8 +176,8 @@ const BINARY_SEARCH_CUTOFF: usize = 1024;
 /// Chooses between binary and linear search depending on the size of the slice to search
 pub struct OptimalSearch;
 
-impl Search for OptimalSearch {
-    fn search_by_key<K: Ord, T: Borrow<K>>(slice: &[T], x: &K) -> Result<usize, usize> {
+impl<K: Ord> Search<K> for OptimalSearch {
+    fn search_by_key<T: Borrow<K>>(slice: &[T], x: &K) -> Result<usize, usize> {
         if slice.len() * core::mem::size_of::<K>() > BINARY_SEARCH_CUTOFF {
             BinarySearch::search_by_key(slice, x)
         } else {
@@ -184,6 +186,117 @@ impl Search for OptimalSearch {
     }
 }
 
+/// A key whose position in a sorted slice can be estimated from its value, used by
+/// `InterpolationSearch`
+pub trait Interpolate: Ord {
+    /// The position of `x` between `lo` and `hi`, as a fraction between 0 and 1
+    ///
+    /// This method is only called with `lo < hi` and `lo <= x <= hi`.
+    fn fraction(lo: &Self, hi: &Self, x: &Self) -> f64;
+}
+
+macro_rules! impl_interpolate {
+    ($($t:ty),+) => {
+        $(
+            impl Interpolate for $t {
+                #[inline(always)]
+                fn fraction(lo: &Self, hi: &Self, x: &Self) -> f64 {
+                    x.abs_diff(*lo) as f64 / hi.abs_diff(*lo) as f64
+                }
+            }
+        )*
+    }
+}
+
+impl_interpolate!(usize, u8, u16, u32, u64, u128, isize, i8, i16, i32, i64, i128);
+
+/// Performs an interpolation search on a slice, probing where the key would be if the keys
+/// were evenly distributed between the bounds of the remaining range
+///
+/// The computational complexity is `O(log log n)` for uniformly distributed keys, but degrades
+/// to `O(n)` for heavily skewed ones.
+pub struct InterpolationSearch;
+
+impl<K: Interpolate> Search<K> for InterpolationSearch {
+    fn search_by_key<T: Borrow<K>>(slice: &[T], x: &K) -> Result<usize, usize> {
+        // All elements before `lo` are less than `x`, all elements from `hi` on are greater
+        let mut lo = 0;
+        let mut hi = slice.len();
+
+        while lo < hi {
+            let first = slice[lo].borrow();
+            let last = slice[hi - 1].borrow();
+
+            if x < first {
+                return Err(lo);
+            }
+
+            if x > last {
+                return Err(hi);
+            }
+
+            let probe = if first == last {
+                lo
+            } else {
+                let offset = K::fraction(first, last, x) * (hi - 1 - lo) as f64;
+                (lo + offset as usize).min(hi - 1)
+            };
+
+            match slice[probe].borrow().cmp(x) {
+                Ordering::Less => lo = probe + 1,
+                Ordering::Greater => hi = probe,
+                Ordering::Equal => return Ok(probe),
+            }
+        }
+
+        Err(lo)
+    }
+}
+
+const CHUNK_SIZE: usize = 16;
+
+/// Performs a linear search on a slice in fixed-size chunks, with computational complexity
+/// `O(n)`
+///
+/// Every chunk counts its keys less than the search key without branching, and the search
+/// stops at the first chunk containing a greater or equal key. This trades a few extra
+/// comparisons for far fewer mispredicted branches than the plain linear search on larger
+/// nodes.
+pub struct ChunkedSearch;
+
+impl<K: Ord> Search<K> for ChunkedSearch {
+    fn search_by_key<T: Borrow<K>>(slice: &[T], x: &K) -> Result<usize, usize> {
+        let mut index = 0;
+        let mut chunks = slice.chunks_exact(CHUNK_SIZE);
+
+        for chunk in &mut chunks {
+            let count: usize = chunk.iter().map(|y| (y.borrow() < x) as usize).sum();
+            index += count;
+
+            if count < CHUNK_SIZE {
+                return finish_count(slice, index, x);
+            }
+        }
+
+        index += chunks
+            .remainder()
+            .iter()
+            .map(|y| (y.borrow() < x) as usize)
+            .sum::<usize>();
+
+        finish_count(slice, index, x)
+    }
+}
+
+// Turn the number of keys less than `x` into a search result
+#[inline(always)]
+fn finish_count<K: Ord, T: Borrow<K>>(slice: &[T], index: usize, x: &K) -> Result<usize, usize> {
+    match slice.get(index) {
+        Some(y) if y.borrow() == x => Ok(index),
+        _ => Err(index),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -234,4 +347,50 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn binary_interpolation_search() {
+        let array = [1, 2, 3, 4, 7, 10, 24, 55, 56, 57, 100];
+        let repeated = [3, 3, 3, 3, 5, 5, 9];
+        let skewed = [i64::MIN, -5, 0, 1, 2, 3, i64::MAX];
+
+        for i in -10..110 {
+            assert_eq!(
+                BinarySearch::search(&array[..], &i),
+                InterpolationSearch::search(&array[..], &i)
+            );
+
+            assert_eq!(
+                lower_bound(BinarySearch::search(&repeated[..], &i)).map(|index| repeated[index]),
+                lower_bound(InterpolationSearch::search(&repeated[..], &i))
+                    .map(|index| repeated[index])
+            );
+
+            assert_eq!(
+                BinarySearch::search(&skewed[..], &(i as i64)),
+                InterpolationSearch::search(&skewed[..], &(i as i64))
+            );
+        }
+
+        assert_eq!(InterpolationSearch::search(&[0u8; 0][..], &1), Err(0));
+        assert_eq!(InterpolationSearch::search(&skewed[..], &i64::MIN), Ok(0));
+        assert_eq!(InterpolationSearch::search(&skewed[..], &i64::MAX), Ok(6));
+    }
+
+    #[test]
+    fn binary_chunked_search() {
+        let mut array = [0u32; 100];
+        for (i, key) in array.iter_mut().enumerate() {
+            *key = 3 * i as u32;
+        }
+
+        for len in [0, 1, 15, 16, 17, 32, 100] {
+            for i in 0..310 {
+                assert_eq!(
+                    BinarySearch::search(&array[..len], &i),
+                    ChunkedSearch::search(&array[..len], &i)
+                );
+            }
+        }
+    }
 }
@@ -8,20 +8,27 @@
 mod entry;
 pub use entry::SortedArrayEntry;
 
+mod slice;
+pub use slice::SortedSlice;
+
 #[cfg(feature = "serde")]
 mod serde;
 
+use core::marker::PhantomData;
 use core::mem::MaybeUninit;
 use slice_search::*;
 
 /// A constant-size, zero-allocation associative container based on a sorted array.
-pub struct SortedArray<K, V, const N: usize> {
+///
+/// Keys are looked up with the search strategy `S`, see `slice_search` for the available ones.
+pub struct SortedArray<K, V, const N: usize, S = OptimalSearch> {
     inner: [MaybeUninit<SortedArrayEntry<K, V>>; N],
     len: usize,
+    _ph: PhantomData<S>,
 }
 
 #[allow(unused)]
-impl<K, V, const N: usize> SortedArray<K, V, N> {
+impl<K, V, const N: usize, S> SortedArray<K, V, N, S> {
     /// Create an empty sorted array
     pub fn empty() -> Self {
         SortedArray {
@@ -29,6 +36,7 @@ impl<K, V, const N: usize> SortedArray<K, V, N> {
                 MaybeUninit::<[MaybeUninit<SortedArrayEntry<K, V>>; N]>::uninit().assume_init()
             },
             len: 0,
+            _ph: PhantomData,
         }
     }
 
@@ -36,14 +44,16 @@ impl<K, V, const N: usize> SortedArray<K, V, N> {
     fn search(&self, key: &K) -> Result<usize, usize>
     where
         K: Ord,
+        S: Search<K>,
     {
-        OptimalSearch::search_by_key(self.entries(), key)
+        S::search_by_key(self.entries(), key)
     }
 
     /// Return an entry which is an exact match for the key
     pub fn get_exact(&self, key: &K) -> Option<&V>
     where
         K: Ord,
+        S: Search<K>,
     {
         if let Ok(index) = self.search(key) {
             Some(unsafe { &self.inner.get_unchecked(index).assume_init_ref().value })
@@ -65,6 +75,7 @@ impl<K, V, const N: usize> SortedArray<K, V, N> {
     pub fn insert(&mut self, key: K, value: V) -> Option<V>
     where
         K: Ord,
+        S: Search<K>,
     {
         match self.search(&key) {
             Ok(index) => {
@@ -98,6 +109,7 @@ impl<K, V, const N: usize> SortedArray<K, V, N> {
     pub fn remove(&mut self, key: &K) -> Option<V>
     where
         K: Ord,
+        S: Search<K>,
     {
         // TODO: fix undefined behavior here
         if let Ok(index) = self.search(key) {
@@ -124,6 +136,7 @@ impl<K, V, const N: usize> SortedArray<K, V, N> {
     pub fn contains_key(&self, key: &K) -> bool
     where
         K: Ord,
+        S: Search<K>,
     {
         self.search(key).is_ok()
     }
@@ -162,6 +175,7 @@ impl<K, V, const N: usize> SortedArray<K, V, N> {
     pub fn get_lower_bound(&self, key: &K) -> Option<&V>
     where
         K: Ord,
+        S: Search<K>,
     {
         // binary search LUB
         if let Some(index) = lower_bound(self.search(key)) {
@@ -176,6 +190,7 @@ impl<K, V, const N: usize> SortedArray<K, V, N> {
     pub fn get_lower_bound_always(&self, key: &K) -> &V
     where
         K: Ord,
+        S: Search<K>,
     {
         // binary search LUB
         let index = lower_bound_always(self.search(key));
@@ -184,7 +199,7 @@ impl<K, V, const N: usize> SortedArray<K, V, N> {
 }
 
 #[allow(unused)]
-impl<K, V, const N: usize> SortedArray<K, V, N> {
+impl<K, V, const N: usize, S> SortedArray<K, V, N, S> {
     /// Borrow a slice view into the entries stored in the `SortedArray`
     pub fn entries(&self) -> &[SortedArrayEntry<K, V>] {
         // SAFETY: `len` must be strictly less than `F`
@@ -239,7 +254,7 @@ impl<K, V, const N: usize> SortedArray<K, V, N> {
     }
 }
 
-impl<K, V, const N: usize> PartialEq for SortedArray<K, V, N>
+impl<K, V, const N: usize, S> PartialEq for SortedArray<K, V, N, S>
 where
     K: PartialEq,
     V: PartialEq,
@@ -250,20 +265,20 @@ where
 }
 
 use core::fmt::Debug;
-impl<K, V, const N: usize> Eq for SortedArray<K, V, N>
+impl<K, V, const N: usize, S> Eq for SortedArray<K, V, N, S>
 where
     K: PartialEq,
     V: PartialEq,
 {
 }
 
-impl<K: Debug, V: Debug, const N: usize> Debug for SortedArray<K, V, N> {
+impl<K: Debug, V: Debug, const N: usize, S> Debug for SortedArray<K, V, N, S> {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         f.debug_list().entries(self.entries().iter()).finish()
     }
 }
 
-impl<K: Clone, V: Clone, const N: usize> Clone for SortedArray<K, V, N> {
+impl<K: Clone, V: Clone, const N: usize, S> Clone for SortedArray<K, V, N, S> {
     fn clone(&self) -> Self {
         let mut inner: [MaybeUninit<SortedArrayEntry<K, V>>; N] = unsafe {
             MaybeUninit::<[MaybeUninit<SortedArrayEntry<K, V>>; N]>::uninit().assume_init()
@@ -276,11 +291,12 @@ impl<K: Clone, V: Clone, const N: usize> Clone for SortedArray<K, V, N> {
         SortedArray {
             inner,
             len: self.len,
+            _ph: PhantomData,
         }
     }
 }
 
-impl<K, V, const N: usize> Default for SortedArray<K, V, N> {
+impl<K, V, const N: usize, S> Default for SortedArray<K, V, N, S> {
     fn default() -> Self {
         Self::empty()
     }
@@ -289,7 +305,8 @@ impl<K, V, const N: usize> Default for SortedArray<K, V, N> {
 #[cfg(test)]
 mod tests {
     use crate::entry::SortedArrayEntry;
-    use crate::SortedArray;
+    use crate::{SortedArray, SortedSlice};
+    use slice_search::{BinarySearch, ChunkedSearch, InterpolationSearch, LinearSearch};
 
     #[test]
     fn test_insert_and_get() {
@@ -372,4 +389,45 @@ mod tests {
         assert_eq!(stack_map.get_lower_bound(&8), Some(&"seven"));
         assert_eq!(stack_map.get_lower_bound(&0), None);
     }
+
+    #[test]
+    fn test_search_strategies() {
+        fn check<S: slice_search::Search<u32>>() {
+            let mut array: SortedArray<u32, u32, 64, S> = SortedArray::empty();
+            for key in (0..64).rev() {
+                array.insert(key * 5, key);
+            }
+
+            for key in 0..330 {
+                let expected = (key % 5 == 0 && key < 320).then_some(key / 5);
+                assert_eq!(array.get_exact(&key).copied(), expected);
+                assert_eq!(*array.get_lower_bound_always(&key), (key / 5).min(63));
+            }
+
+            let keys: [u32; 64] = core::array::from_fn(|i| array.entries()[i].key);
+            let values: [u32; 64] = core::array::from_fn(|i| array.entries()[i].value);
+            let slice = SortedSlice::<u32, u32, S>::with_search(&keys, &values);
+            assert_eq!(slice.get_exact(&35), Some(&7));
+            assert_eq!(slice.get_lower_bound(&36), Some(&7));
+        }
+
+        check::<BinarySearch>();
+        check::<LinearSearch>();
+        check::<InterpolationSearch>();
+        check::<ChunkedSearch>();
+    }
+
+    #[test]
+    fn test_sorted_slice() {
+        let keys = [1, 3, 5, 7];
+        let values = ["one", "three", "five", "seven"];
+        let slice = SortedSlice::new(&keys, &values);
+
+        assert_eq!(slice.get_exact(&3), Some(&"three"));
+        assert_eq!(slice.get_exact(&4), None);
+        assert_eq!(slice.get_lower_bound(&6), Some(&"five"));
+        assert_eq!(slice.get_lower_bound(&0), None);
+        assert_eq!(slice.get_lower_bound_always(&0), &"one");
+        assert_eq!(slice.len(), 4);
+    }
 }
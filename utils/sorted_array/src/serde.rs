@@ -1,18 +1,19 @@
 use serde::de::{SeqAccess, Visitor};
 use serde::ser::SerializeSeq;
 use serde::{Deserialize, Serialize};
+use slice_search::Search;
 
 use crate::entry::SortedArrayEntry;
 use crate::SortedArray;
 
-impl<K, V, const FANOUT: usize> Serialize for SortedArray<K, V, FANOUT>
+impl<K, V, const FANOUT: usize, S> Serialize for SortedArray<K, V, FANOUT, S>
 where
     K: Serialize,
     V: Serialize,
 {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    fn serialize<Ser>(&self, serializer: Ser) -> Result<Ser::Ok, Ser::Error>
     where
-        S: serde::Serializer,
+        Ser: serde::Serializer,
     {
         let mut seq = serializer.serialize_seq(Some(self.len))?;
         for entry in self.entries() {
@@ -22,16 +23,17 @@ where
     }
 }
 
-struct SortedArrayDeserializer<K, V, const FANOUT: usize>(
-    core::marker::PhantomData<(K, V, [(); FANOUT])>,
+struct SortedArrayDeserializer<K, V, const FANOUT: usize, S>(
+    core::marker::PhantomData<(K, V, [(); FANOUT], S)>,
 );
 
-impl<'de, K, V, const FANOUT: usize> Visitor<'de> for SortedArrayDeserializer<K, V, FANOUT>
+impl<'de, K, V, const FANOUT: usize, S> Visitor<'de> for SortedArrayDeserializer<K, V, FANOUT, S>
 where
     K: Deserialize<'de> + Ord,
     V: Deserialize<'de>,
+    S: Search<K>,
 {
-    type Value = SortedArray<K, V, FANOUT>;
+    type Value = SortedArray<K, V, FANOUT, S>;
 
     fn expecting(&self, formatter: &mut core::fmt::Formatter) -> core::fmt::Result {
         formatter.write_str("A sequence of entries for SortedArray")
@@ -41,7 +43,7 @@ where
     where
         A: SeqAccess<'de>,
     {
-        let mut map = SortedArray::<K, V, FANOUT>::empty();
+        let mut map = SortedArray::<K, V, FANOUT, S>::empty();
 
         while let Some(entry) = seq.next_element::<SortedArrayEntry<K, V>>()? {
             if map.len() >= FANOUT {
@@ -56,10 +58,11 @@ where
     }
 }
 
-impl<'de, K, V, const FANOUT: usize> Deserialize<'de> for SortedArray<K, V, FANOUT>
+impl<'de, K, V, const FANOUT: usize, S> Deserialize<'de> for SortedArray<K, V, FANOUT, S>
 where
-    K: Serialize + Deserialize<'de> + Ord,
-    V: Serialize + Deserialize<'de>,
+    K: Deserialize<'de> + Ord,
+    V: Deserialize<'de>,
+    S: Search<K>,
 {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
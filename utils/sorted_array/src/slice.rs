@@ -0,0 +1,108 @@
+use core::marker::PhantomData;
+use slice_search::*;
+
+/// A borrowed, read-only view of sorted entries stored as separate key and value slices.
+///
+/// This allows searching data which is not owned by a `SortedArray`, such as nodes laid out in
+/// a memory-mapped file, without copying it first. Keys are looked up with the search strategy
+/// `S`, like in `SortedArray`.
+pub struct SortedSlice<'a, K, V, S = OptimalSearch> {
+    keys: &'a [K],
+    values: &'a [V],
+    _ph: PhantomData<S>,
+}
+
+impl<K, V, S> Clone for SortedSlice<'_, K, V, S> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<K, V, S> Copy for SortedSlice<'_, K, V, S> {}
+
+impl<'a, K, V> SortedSlice<'a, K, V> {
+    /// Create a view from sorted keys and their corresponding values
+    ///
+    /// # Panics
+    ///
+    /// This method will panic if the slices have different lengths.
+    pub fn new(keys: &'a [K], values: &'a [V]) -> Self {
+        Self::with_search(keys, values)
+    }
+}
+
+impl<'a, K, V, S> SortedSlice<'a, K, V, S> {
+    /// Create a view from sorted keys and their corresponding values, searched with `S`
+    ///
+    /// # Panics
+    ///
+    /// This method will panic if the slices have different lengths.
+    pub fn with_search(keys: &'a [K], values: &'a [V]) -> Self {
+        assert_eq!(keys.len(), values.len());
+        Self {
+            keys,
+            values,
+            _ph: PhantomData,
+        }
+    }
+
+    /// Utility method to search the slice by key
+    fn search(&self, key: &K) -> Result<usize, usize>
+    where
+        K: Ord,
+        S: Search<K>,
+    {
+        S::search(self.keys, key)
+    }
+
+    /// Return an entry which is an exact match for the key
+    pub fn get_exact(&self, key: &K) -> Option<&'a V>
+    where
+        K: Ord,
+        S: Search<K>,
+    {
+        self.search(key).ok().map(|index| &self.values[index])
+    }
+
+    /// Get the value whose key is less than or equal to the provided key.
+    pub fn get_lower_bound(&self, key: &K) -> Option<&'a V>
+    where
+        K: Ord,
+        S: Search<K>,
+    {
+        lower_bound(self.search(key)).map(|index| &self.values[index])
+    }
+
+    /// Get the value whose key is less than or equal to the provided key, or the first value.
+    ///
+    /// # Panics
+    ///
+    /// This method will panic if the slice is empty.
+    pub fn get_lower_bound_always(&self, key: &K) -> &'a V
+    where
+        K: Ord,
+        S: Search<K>,
+    {
+        &self.values[lower_bound_always(self.search(key))]
+    }
+
+    /// The sorted keys of the view
+    pub fn keys(&self) -> &'a [K] {
+        self.keys
+    }
+
+    /// The values of the view, in the same order as the keys
+    pub fn values(&self) -> &'a [V] {
+        self.values
+    }
+
+    /// Returns the number of elements in the view.
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// Returns whether the view has any elements.
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+}